@@ -0,0 +1,9 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/tx_engine.proto");
+        let fds = protox::compile(["tx_engine.proto"], ["proto"]).expect("proto is valid");
+        tonic_prost_build::compile_fds(fds).expect("proto compiles to rust");
+    }
+}
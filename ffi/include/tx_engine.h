@@ -0,0 +1,105 @@
+#ifndef TX_ENGINE_H
+#define TX_ENGINE_H
+
+/* Generated by cbindgen from ffi/src/lib.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Returned by `tx_engine_apply` when the transaction was applied. Failures are reported by
+ * the code of the error, as per `AccountError::code`, or by one of the codes below.
+ */
+#define TX_ENGINE_OK 0
+
+/**
+ * Returned when a pointer argument is null.
+ */
+#define TX_ENGINE_NULL_ARGUMENT -1
+
+/**
+ * Returned when the transaction type isn't one of the csv input.
+ */
+#define TX_ENGINE_UNKNOWN_TYPE -2
+
+/**
+ * Accounts to which transactions are applied one at a time, created by `tx_engine_new` and
+ * released by `tx_engine_free`.
+ */
+typedef struct TxEngine TxEngine;
+
+/**
+ * Account as output by the engine.
+ */
+typedef struct TxAccount {
+  uint16_t client;
+  double available;
+  double held;
+  double total;
+  bool locked;
+} TxAccount;
+
+/**
+ * Create an engine with no accounts, to be released with `tx_engine_free`.
+ */
+struct TxEngine *tx_engine_new(void);
+
+/**
+ * Release `engine`.
+ *
+ * # Safety
+ *
+ * `engine` must be null or returned by `tx_engine_new`, and not used afterwards.
+ */
+void tx_engine_free(struct TxEngine *engine);
+
+/**
+ * Apply a transaction of type `type_`, e.g. `"deposit"`, to `engine`, with `amount` unless
+ * `has_amount` is false, returning `TX_ENGINE_OK` or the code of the failure.
+ *
+ * # Safety
+ *
+ * `engine` must be returned by `tx_engine_new`, and `type_` a nul-terminated string.
+ */
+int32_t tx_engine_apply(struct TxEngine *engine,
+                        const char *type_,
+                        uint16_t client,
+                        uint32_t tx,
+                        double amount,
+                        bool has_amount);
+
+/**
+ * Write the account of `client` to `account`, returning whether any transaction was
+ * applied to it.
+ *
+ * # Safety
+ *
+ * `engine` must be returned by `tx_engine_new`, and `account` point to a `TxAccount`.
+ */
+bool tx_engine_get_account(const struct TxEngine *engine,
+                           uint16_t client,
+                           struct TxAccount *account);
+
+/**
+ * Accounts of `engine` as csv, ordered by client id, to be released with
+ * `tx_engine_string_free`, or null on failure.
+ *
+ * # Safety
+ *
+ * `engine` must be returned by `tx_engine_new`.
+ */
+char *tx_engine_to_csv(const struct TxEngine *engine);
+
+/**
+ * Release a string returned by the engine.
+ *
+ * # Safety
+ *
+ * `string` must be null or returned by `tx_engine_to_csv`, and not used afterwards.
+ */
+void tx_engine_string_free(char *string);
+
+#endif  /* TX_ENGINE_H */
@@ -0,0 +1,187 @@
+use std::{
+    ffi::{c_char, CStr, CString},
+    iter::once,
+    ptr,
+};
+
+use tx_engine::{Accounts, Strictness, Transaction, TransactionType};
+
+/// Returned by `tx_engine_apply` when the transaction was applied. Failures are reported by
+/// the code of the error, as per `AccountError::code`, or by one of the codes below.
+pub const TX_ENGINE_OK: i32 = 0;
+/// Returned when a pointer argument is null.
+pub const TX_ENGINE_NULL_ARGUMENT: i32 = -1;
+/// Returned when the transaction type isn't one of the csv input.
+pub const TX_ENGINE_UNKNOWN_TYPE: i32 = -2;
+
+/// Accounts to which transactions are applied one at a time, created by `tx_engine_new` and
+/// released by `tx_engine_free`.
+pub struct TxEngine {
+    accounts: Accounts,
+}
+
+/// Account as output by the engine.
+#[repr(C)]
+pub struct TxAccount {
+    pub client: u16,
+    pub available: f64,
+    pub held: f64,
+    pub total: f64,
+    pub locked: bool,
+}
+
+/// Create an engine with no accounts, to be released with `tx_engine_free`.
+#[no_mangle]
+pub extern "C" fn tx_engine_new() -> *mut TxEngine {
+    Box::into_raw(Box::new(TxEngine {
+        accounts: Accounts::default(),
+    }))
+}
+
+/// Release `engine`.
+///
+/// # Safety
+///
+/// `engine` must be null or returned by `tx_engine_new`, and not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn tx_engine_free(engine: *mut TxEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+/// Apply a transaction of type `type_`, e.g. `"deposit"`, to `engine`, with `amount` unless
+/// `has_amount` is false, returning `TX_ENGINE_OK` or the code of the failure.
+///
+/// # Safety
+///
+/// `engine` must be returned by `tx_engine_new`, and `type_` a nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn tx_engine_apply(
+    engine: *mut TxEngine,
+    type_: *const c_char,
+    client: u16,
+    tx: u32,
+    amount: f64,
+    has_amount: bool,
+) -> i32 {
+    let (Some(engine), false) = (engine.as_mut(), type_.is_null()) else {
+        return TX_ENGINE_NULL_ARGUMENT;
+    };
+    let Some(type_) = CStr::from_ptr(type_)
+        .to_str()
+        .ok()
+        .and_then(|type_| type_.parse::<TransactionType>().ok())
+    else {
+        return TX_ENGINE_UNKNOWN_TYPE;
+    };
+    let tx = Transaction::new(type_, client, tx, has_amount.then_some(amount), false);
+    match engine
+        .accounts
+        .apply_transaction_iter(once(Ok(tx)), Strictness::Strict)
+    {
+        Ok(()) => TX_ENGINE_OK,
+        Err(e) => e.code().into(),
+    }
+}
+
+/// Write the account of `client` to `account`, returning whether any transaction was
+/// applied to it.
+///
+/// # Safety
+///
+/// `engine` must be returned by `tx_engine_new`, and `account` point to a `TxAccount`.
+#[no_mangle]
+pub unsafe extern "C" fn tx_engine_get_account(
+    engine: *const TxEngine,
+    client: u16,
+    account: *mut TxAccount,
+) -> bool {
+    let (Some(engine), false) = (engine.as_ref(), account.is_null()) else {
+        return false;
+    };
+    let Some(found) = engine.accounts.get(client) else {
+        return false;
+    };
+    account.write(TxAccount {
+        client,
+        available: *found.available(),
+        held: *found.held(),
+        total: *found.total(),
+        locked: *found.locked(),
+    });
+    true
+}
+
+/// Accounts of `engine` as csv, ordered by client id, to be released with
+/// `tx_engine_string_free`, or null on failure.
+///
+/// # Safety
+///
+/// `engine` must be returned by `tx_engine_new`.
+#[no_mangle]
+pub unsafe extern "C" fn tx_engine_to_csv(engine: *const TxEngine) -> *mut c_char {
+    let Some(engine) = engine.as_ref() else {
+        return ptr::null_mut();
+    };
+    let mut csv = Vec::new();
+    match engine.accounts.to_csv_writer(&mut csv) {
+        Ok(()) => CString::new(csv).map_or(ptr::null_mut(), CString::into_raw),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Release a string returned by the engine.
+///
+/// # Safety
+///
+/// `string` must be null or returned by `tx_engine_to_csv`, and not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn tx_engine_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use super::{
+        tx_engine_apply, tx_engine_free, tx_engine_get_account, tx_engine_new,
+        tx_engine_string_free, tx_engine_to_csv, TxAccount, TX_ENGINE_OK, TX_ENGINE_UNKNOWN_TYPE,
+    };
+
+    #[test]
+    fn embed_engine() {
+        unsafe {
+            let engine = tx_engine_new();
+            let apply = |type_: &CStr, tx, amount| {
+                tx_engine_apply(engine, type_.as_ptr(), 1, tx, amount, amount > 0.0)
+            };
+            assert_eq!(apply(c"deposit", 1, 2.0), TX_ENGINE_OK);
+            assert_eq!(apply(c"withdrawal", 2, 3.0), 200);
+            assert_eq!(apply(c"dispute", 1, 0.0), TX_ENGINE_OK);
+            assert_eq!(apply(c"teleport", 3, 0.0), TX_ENGINE_UNKNOWN_TYPE);
+
+            let mut account = TxAccount {
+                client: 0,
+                available: 0.0,
+                held: 0.0,
+                total: 0.0,
+                locked: false,
+            };
+            assert!(tx_engine_get_account(engine, 1, &mut account));
+            assert_eq!((account.available, account.held), (0.0, 2.0));
+            assert!(!tx_engine_get_account(engine, 2, &mut account));
+
+            let csv = tx_engine_to_csv(engine);
+            assert_eq!(
+                CStr::from_ptr(csv).to_str().unwrap(),
+                "client,available,held,total,locked\n1,0.0,2.0,2.0,false\n"
+            );
+            tx_engine_string_free(csv);
+            tx_engine_free(engine);
+        }
+    }
+}
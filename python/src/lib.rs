@@ -0,0 +1,112 @@
+use pyo3::{
+    create_exception,
+    exceptions::PyException,
+    prelude::*,
+    types::{PyDict, PyList},
+};
+use tx_engine::{
+    Account, AccountRecord, Accounts, ClientId, Strictness, Transaction, TransactionId,
+    TransactionType, TransactionsCsv,
+};
+
+create_exception!(tx_engine, TxEngineError, PyException);
+
+fn to_py_err<E: ToString>(e: E) -> PyErr {
+    TxEngineError::new_err(e.to_string())
+}
+
+/// Account as a dict with the columns of the csv output.
+fn account_dict<'py>(py: Python<'py>, account: &Account) -> PyResult<Bound<'py, PyDict>> {
+    let record = AccountRecord::from(account);
+    let dict = PyDict::new(py);
+    dict.set_item("client", record.client)?;
+    dict.set_item("available", record.available)?;
+    dict.set_item("held", record.held)?;
+    dict.set_item("total", record.total)?;
+    dict.set_item("locked", record.locked)?;
+    Ok(dict)
+}
+
+fn accounts_list<'py>(py: Python<'py>, accounts: &Accounts) -> PyResult<Bound<'py, PyList>> {
+    let accounts = accounts
+        .iter_sorted()
+        .map(|account| account_dict(py, account))
+        .collect::<PyResult<Vec<_>>>()?;
+    PyList::new(py, accounts)
+}
+
+/// Process the transactions of the csv file at `path`, skipping the ones breaking business
+/// rules, returning the accounts ordered by client id.
+#[pyfunction]
+fn process_csv<'py>(py: Python<'py>, path: &str) -> PyResult<Bound<'py, PyList>> {
+    let mut transactions = TransactionsCsv::from_csv(path).map_err(to_py_err)?;
+    let accounts = Accounts::from_transaction_iter(transactions.iter(), Strictness::Lenient)
+        .map_err(to_py_err)?;
+    accounts_list(py, &accounts)
+}
+
+/// Accounts to which transactions are applied one at a time.
+#[pyclass(unsendable)]
+#[derive(Default)]
+struct Engine {
+    accounts: Accounts,
+}
+
+#[pymethods]
+impl Engine {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply `tx`, a dict with the fields of the csv input, e.g.
+    /// `{"type": "deposit", "client": 1, "tx": 1, "amount": 1.5}`, raising `TxEngineError`
+    /// if it breaks a business rule.
+    fn apply(&mut self, tx: &Bound<'_, PyDict>) -> PyResult<()> {
+        let field = |name| {
+            tx.get_item(name)?
+                .ok_or_else(|| TxEngineError::new_err(format!("missing field `{name}`")))
+        };
+        let type_ = field("type")?
+            .extract::<String>()?
+            .parse::<TransactionType>()
+            .map_err(to_py_err)?;
+        let client = field("client")?.extract::<ClientId>()?;
+        let id = field("tx")?.extract::<TransactionId>()?;
+        let amount = tx
+            .get_item("amount")?
+            .map(|amount| amount.extract::<Option<f64>>())
+            .transpose()?
+            .flatten();
+        let tx = Transaction::new(type_, client, id, amount, false);
+        self.accounts
+            .apply_transaction_iter(std::iter::once(Ok(tx)), Strictness::Strict)
+            .map_err(to_py_err)
+    }
+
+    /// Account of `client`, if any transaction was applied to it.
+    fn account<'py>(
+        &self,
+        py: Python<'py>,
+        client: ClientId,
+    ) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.accounts
+            .get(client)
+            .map(|account| account_dict(py, account))
+            .transpose()
+    }
+
+    /// Accounts, ordered by client id.
+    fn accounts<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        accounts_list(py, &self.accounts)
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "tx_engine")]
+fn tx_engine_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(process_csv, m)?)?;
+    m.add_class::<Engine>()?;
+    m.add("TxEngineError", m.py().get_type::<TxEngineError>())?;
+    Ok(())
+}
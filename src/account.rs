@@ -1,63 +1,642 @@
-use std::{collections::HashMap, fmt::Display, io::stdout};
+use std::{
+    cmp::Ordering,
+    collections::{btree_map, hash_map::Entry, BTreeMap, BTreeSet, HashMap, VecDeque},
+    fmt::Display,
+    mem,
+};
+#[cfg(any(feature = "csv", feature = "json"))]
+use std::{fs, path::Path};
+#[cfg(feature = "json")]
+use std::{fs::File, io::BufWriter};
+#[cfg(feature = "csv")]
+use std::{
+    fs::OpenOptions,
+    io::{stdout, Read, Write},
+};
 
+use derive_getters::Getters;
 use derive_more::{Deref, DerefMut};
-use serde::{Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
 use super::{
-    transaction::{Transaction, TransactionError, TransactionType, Transactions},
-    types::{ClientId, TransactionId},
+    amount::Amount,
+    anomaly::{Anomaly, AnomalyDetector, ClientStats},
+    arbitration::{DisputeArbiter, DisputeDecision},
+    config::Config,
+    dedup::{dedup_key, DedupStore},
+    graph::TransactionGraph,
+    interest::{InterestAccrual, InterestPolicy, SECONDS_PER_DAY},
+    memory::{hash_map_bytes, vec_bytes, vec_deque_bytes, AccountFootprint, MemoryFootprint},
+    observer::Observer,
+    policy::{CapAction, FreezePolicy, Strictness, TimeoutAction},
+    reconcile::AccountRecord,
+    recurring::Recurrence,
+    rejection::{ErrorDecision, ErrorHandler, RejectedTx},
+    report::{FundsSummary, ProcessingReport},
+    risk::{RiskDecision, RiskFlag, RiskScorer},
+    rules::RuleAction,
+    statement::{DisputeStatus, Statement, StatementEntry},
+    transaction::{Transaction, TransactionError, TransactionState, TransactionType, Transactions},
+    types::{ClientId, Timestamp, TransactionId},
+    validate::InvariantViolation,
 };
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum AccountError {
+    #[cfg(feature = "csv")]
     #[error("csv error: {0}")]
     Csv(#[from] csv::Error),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
+    #[cfg(feature = "json")]
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "toml")]
+    #[error("toml error: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[cfg(feature = "checkpoint")]
+    #[error("unsupported checkpoint version: {0}")]
+    CheckpointVersion(u32),
+    #[error("merge error: {0}")]
+    Merge(#[from] MergeError),
+    #[error("unsupported by sharded accounts: {0}")]
+    ShardConfig(&'static str),
+    #[cfg(feature = "arrow")]
+    #[error("arrow error: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+    #[cfg(feature = "avro")]
+    #[error("avro error: {0}")]
+    Avro(#[from] apache_avro::Error),
+    #[cfg(feature = "kafka")]
+    #[error("kafka error: {0}")]
+    Kafka(#[from] kafka::Error),
+    #[cfg(feature = "nats")]
+    #[error("nats error: {0}")]
+    Nats(#[from] async_nats::Error),
+    #[cfg(feature = "parquet")]
+    #[error("parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[cfg(feature = "watch")]
+    #[error("watch error: {0}")]
+    Watch(#[from] notify::Error),
+    #[cfg(feature = "snapshot")]
+    #[error("snapshot encoding error: {0}")]
+    SnapshotEncode(#[from] bincode::error::EncodeError),
+    #[cfg(feature = "snapshot")]
+    #[error("snapshot decoding error: {0}")]
+    SnapshotDecode(#[from] bincode::error::DecodeError),
+    #[cfg(feature = "snapshot")]
+    #[error("unsupported snapshot version: {0}")]
+    SnapshotVersion(u32),
+    #[cfg(feature = "sqlite")]
+    #[error("sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[cfg(feature = "postgres")]
+    #[error("postgres error: {0}")]
+    Postgres(#[from] postgres::Error),
+    #[cfg(feature = "postgres")]
+    #[error("postgres pool error: {0}")]
+    PostgresPool(#[from] r2d2::Error),
+    #[cfg(feature = "redis")]
+    #[error("redis error: {0}")]
+    Redis(#[from] redis::RedisError),
     #[error("insufficient funds to apply withdrawal, account: {0}, withdrawal: {1}")]
     Withdrawal(ClientId, TransactionId),
+    #[error("insufficient funds to apply transaction, account: {0}, transaction: {1}")]
+    Overdrawn(ClientId, TransactionId),
+    #[error("withdrawal velocity limit exceeded, account: {0}, withdrawal: {1}")]
+    Velocity(ClientId, TransactionId),
     #[error("disputed transaction not found, account, {0}, transaction: {1}")]
     Dispute(ClientId, TransactionId),
+    #[error("disputed transaction is an authorization, account, {0}, transaction: {1}")]
+    DisputeAuthorization(ClientId, TransactionId),
     #[error("resolved transaction not found, account, {0}, transaction: {1}")]
     Resolve(ClientId, TransactionId),
     #[error("resolved transaction wasn't disputed, account, {0}, transaction: {1}")]
     ResolveUndisputed(ClientId, TransactionId),
+    #[error("represented transaction not found, account, {0}, transaction: {1}")]
+    Representment(ClientId, TransactionId),
+    #[error("represented transaction wasn't charged back, account, {0}, transaction: {1}")]
+    RepresentmentNotChargedBack(ClientId, TransactionId),
+    #[error("refunded transaction not found, account, {0}, transaction: {1}")]
+    Refund(ClientId, TransactionId),
+    #[error("refunded transaction isn't a withdrawal, account, {0}, transaction: {1}")]
+    RefundNotWithdrawal(ClientId, TransactionId),
+    #[error("refunded transaction was already refunded, account, {0}, transaction: {1}")]
+    RefundRepeated(ClientId, TransactionId),
+    #[error("refunded transaction is disputed or charged back, account, {0}, transaction: {1}")]
+    RefundDisputed(ClientId, TransactionId),
+    #[error("disputed transaction was refunded, account, {0}, transaction: {1}")]
+    DisputeRefunded(ClientId, TransactionId),
+    #[error("insufficient funds to apply authorization, account: {0}, authorization: {1}")]
+    Authorization(ClientId, TransactionId),
+    #[error("authorization not found, account, {0}, transaction: {1}")]
+    AuthorizationNotFound(ClientId, TransactionId),
+    #[error("authorization was already captured or voided, account, {0}, transaction: {1}")]
+    AuthorizationSettled(ClientId, TransactionId),
+    #[error("insufficient funds to apply hold, account: {0}, hold: {1}")]
+    Hold(ClientId, TransactionId),
+    #[error("hold not found, account, {0}, transaction: {1}")]
+    HoldNotFound(ClientId, TransactionId),
+    #[error("hold was already released, account, {0}, transaction: {1}")]
+    HoldReleased(ClientId, TransactionId),
+    #[error("adjustments are not allowed, account: {0}, transaction: {1}")]
+    AdjustmentsDisabled(ClientId, TransactionId),
+    #[error("interest is only posted as accrued, account: {0}, transaction: {1}")]
+    InterestNotAccrued(ClientId, TransactionId),
+    #[error("reversed transaction not found, account, {0}, transaction: {1}")]
+    Reversal(ClientId, TransactionId),
+    #[error(
+        "reversed transaction is disputed, charged back or refunded, account, {0}, \
+         transaction: {1}"
+    )]
+    ReversalDisputed(ClientId, TransactionId),
+    #[error("transaction was reversed, account, {0}, transaction: {1}")]
+    Reversed(ClientId, TransactionId),
+    #[error("no exchange rate to apply conversion at, account: {0}, transaction: {1}")]
+    Conversion(ClientId, TransactionId),
+    #[error("tier maximum balance exceeded, account: {0}, deposit: {1}, tier: {2}")]
+    TierBalance(ClientId, TransactionId, String),
+    #[error("tier withdrawal limit exceeded, account: {0}, withdrawal: {1}, tier: {2}")]
+    TierWithdrawal(ClientId, TransactionId, String),
+    #[error("balance cap exceeded, account: {0}, deposit: {1}")]
+    BalanceCap(ClientId, TransactionId),
+    #[error(
+        "daily withdrawal limit exceeded, account: {0}, withdrawal: {1}, limit: {2}, \
+         attempted: {3}"
+    )]
+    LimitExceeded(ClientId, TransactionId, f64, f64),
+    #[error("transaction vetoed, account: {0}, transaction: {1}, reason: {2}")]
+    Vetoed(ClientId, TransactionId, String),
+    #[error("transaction rejected by rule, account: {0}, transaction: {1}, rule: {2}")]
+    RuleViolated(ClientId, TransactionId, String),
+    #[error("balance invariants violated, account: {0}, transaction: {1}, violations: {2:?}")]
+    InvariantViolated(ClientId, TransactionId, Vec<InvariantViolation>),
+    #[error("balance overflow, account: {0}, transaction: {1}")]
+    Overflow(ClientId, TransactionId),
+    #[error("transaction with the same id already applied, account: {0}, transaction: {1}")]
+    DuplicateTransaction(ClientId, TransactionId),
+    #[error(
+        "transaction applied to another client's account, account: {0}, transaction: {1}, \
+         client: {2}"
+    )]
+    WrongAccount(ClientId, TransactionId, ClientId),
+    #[error("transaction id already used, account: {0}, transaction: {1}, used by account: {2}")]
+    TransactionIdReused(ClientId, TransactionId, ClientId),
+    #[error(
+        "referenced transaction belongs to another account, account: {0}, transaction: {1}, \
+         owned by account: {2}"
+    )]
+    ClientMismatch(ClientId, TransactionId, ClientId),
     #[error("transaction error: {0}")]
     Transaction(#[from] TransactionError),
 }
 
-type TransactionMap = HashMap<TransactionId, Transaction>;
+impl AccountError {
+    /// Name of the kind of error, e.g. for labelling metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "csv")]
+            Self::Csv(..) => "csv",
+            Self::Io(..) => "io",
+            #[cfg(feature = "json")]
+            Self::Json(..) => "json",
+            #[cfg(feature = "toml")]
+            Self::Toml(..) => "toml",
+            Self::Merge(..) => "merge",
+            Self::ShardConfig(..) => "shard_config",
+            #[cfg(feature = "checkpoint")]
+            Self::CheckpointVersion(..) => "checkpoint_version",
+            #[cfg(feature = "arrow")]
+            Self::Arrow(..) => "arrow",
+            #[cfg(feature = "avro")]
+            Self::Avro(..) => "avro",
+            #[cfg(feature = "kafka")]
+            Self::Kafka(..) => "kafka",
+            #[cfg(feature = "nats")]
+            Self::Nats(..) => "nats",
+            #[cfg(feature = "parquet")]
+            Self::Parquet(..) => "parquet",
+            #[cfg(feature = "watch")]
+            Self::Watch(..) => "watch",
+            #[cfg(feature = "snapshot")]
+            Self::SnapshotEncode(..) => "snapshot_encode",
+            #[cfg(feature = "snapshot")]
+            Self::SnapshotDecode(..) => "snapshot_decode",
+            #[cfg(feature = "snapshot")]
+            Self::SnapshotVersion(..) => "snapshot_version",
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(..) => "sqlite",
+            #[cfg(feature = "postgres")]
+            Self::Postgres(..) => "postgres",
+            #[cfg(feature = "postgres")]
+            Self::PostgresPool(..) => "postgres_pool",
+            #[cfg(feature = "redis")]
+            Self::Redis(..) => "redis",
+            Self::Withdrawal(..) => "withdrawal",
+            Self::Overdrawn(..) => "overdrawn",
+            Self::Velocity(..) => "velocity",
+            Self::Dispute(..) => "dispute",
+            Self::DisputeAuthorization(..) => "dispute_authorization",
+            Self::Resolve(..) => "resolve",
+            Self::ResolveUndisputed(..) => "resolve_undisputed",
+            Self::Representment(..) => "representment",
+            Self::RepresentmentNotChargedBack(..) => "representment_not_charged_back",
+            Self::Refund(..) => "refund",
+            Self::RefundNotWithdrawal(..) => "refund_not_withdrawal",
+            Self::RefundRepeated(..) => "refund_repeated",
+            Self::RefundDisputed(..) => "refund_disputed",
+            Self::DisputeRefunded(..) => "dispute_refunded",
+            Self::Authorization(..) => "authorization",
+            Self::AuthorizationNotFound(..) => "authorization_not_found",
+            Self::AuthorizationSettled(..) => "authorization_settled",
+            Self::Hold(..) => "hold",
+            Self::HoldNotFound(..) => "hold_not_found",
+            Self::HoldReleased(..) => "hold_released",
+            Self::AdjustmentsDisabled(..) => "adjustments_disabled",
+            Self::InterestNotAccrued(..) => "interest_not_accrued",
+            Self::Reversal(..) => "reversal",
+            Self::ReversalDisputed(..) => "reversal_disputed",
+            Self::Reversed(..) => "reversed",
+            Self::Conversion(..) => "conversion",
+            Self::TierBalance(..) => "tier_balance",
+            Self::TierWithdrawal(..) => "tier_withdrawal",
+            Self::BalanceCap(..) => "balance_cap",
+            Self::LimitExceeded(..) => "limit_exceeded",
+            Self::Vetoed(..) => "vetoed",
+            Self::RuleViolated(..) => "rule_violated",
+            Self::InvariantViolated(..) => "invariant_violated",
+            Self::Overflow(..) => "overflow",
+            Self::DuplicateTransaction(..) => "duplicate_transaction",
+            Self::WrongAccount(..) => "wrong_account",
+            Self::TransactionIdReused(..) => "transaction_id_reused",
+            Self::ClientMismatch(..) => "client_mismatch",
+            Self::Transaction(e) => e.kind(),
+        }
+    }
 
-const DECIMAL_PRECISION: i32 = 4;
+    /// Stable numeric code of the error, e.g. for mapping to api responses. Codes are never
+    /// reused, and those of transaction errors are the ones of `TransactionError::code`.
+    pub fn code(&self) -> u16 {
+        match self {
+            #[cfg(feature = "csv")]
+            Self::Csv(..) => 100,
+            Self::Io(..) => 101,
+            #[cfg(feature = "json")]
+            Self::Json(..) => 102,
+            #[cfg(feature = "checkpoint")]
+            Self::CheckpointVersion(..) => 103,
+            #[cfg(feature = "arrow")]
+            Self::Arrow(..) => 104,
+            #[cfg(feature = "avro")]
+            Self::Avro(..) => 105,
+            #[cfg(feature = "kafka")]
+            Self::Kafka(..) => 106,
+            #[cfg(feature = "nats")]
+            Self::Nats(..) => 107,
+            #[cfg(feature = "parquet")]
+            Self::Parquet(..) => 108,
+            #[cfg(feature = "watch")]
+            Self::Watch(..) => 109,
+            #[cfg(feature = "snapshot")]
+            Self::SnapshotEncode(..) => 110,
+            #[cfg(feature = "snapshot")]
+            Self::SnapshotDecode(..) => 111,
+            #[cfg(feature = "snapshot")]
+            Self::SnapshotVersion(..) => 112,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(..) => 113,
+            #[cfg(feature = "postgres")]
+            Self::Postgres(..) => 114,
+            #[cfg(feature = "postgres")]
+            Self::PostgresPool(..) => 115,
+            #[cfg(feature = "redis")]
+            Self::Redis(..) => 116,
+            #[cfg(feature = "toml")]
+            Self::Toml(..) => 117,
+            Self::Merge(..) => 118,
+            Self::ShardConfig(..) => 119,
+            Self::Withdrawal(..) => 200,
+            Self::Overdrawn(..) => 201,
+            Self::Velocity(..) => 202,
+            Self::Dispute(..) => 203,
+            Self::DisputeAuthorization(..) => 204,
+            Self::Resolve(..) => 205,
+            Self::ResolveUndisputed(..) => 206,
+            Self::Representment(..) => 207,
+            Self::RepresentmentNotChargedBack(..) => 208,
+            Self::Refund(..) => 209,
+            Self::RefundNotWithdrawal(..) => 210,
+            Self::RefundRepeated(..) => 211,
+            Self::Authorization(..) => 212,
+            Self::AuthorizationNotFound(..) => 213,
+            Self::AuthorizationSettled(..) => 214,
+            Self::Vetoed(..) => 215,
+            Self::InvariantViolated(..) => 216,
+            Self::Overflow(..) => 217,
+            Self::DuplicateTransaction(..) => 218,
+            Self::WrongAccount(..) => 219,
+            Self::TransactionIdReused(..) => 220,
+            Self::ClientMismatch(..) => 221,
+            Self::Hold(..) => 222,
+            Self::HoldNotFound(..) => 223,
+            Self::HoldReleased(..) => 224,
+            Self::AdjustmentsDisabled(..) => 225,
+            Self::Reversal(..) => 226,
+            Self::ReversalDisputed(..) => 227,
+            Self::Reversed(..) => 228,
+            Self::Conversion(..) => 229,
+            Self::TierBalance(..) => 230,
+            Self::TierWithdrawal(..) => 231,
+            Self::BalanceCap(..) => 232,
+            Self::LimitExceeded(..) => 233,
+            Self::RuleViolated(..) => 234,
+            Self::RefundDisputed(..) => 235,
+            Self::DisputeRefunded(..) => 236,
+            Self::InterestNotAccrued(..) => 237,
+            Self::Transaction(e) => e.code(),
+        }
+    }
 
-fn serialize_f64_to_decimal_precision<S>(num: &f64, ser: S) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
+    /// Whether the error may be caused by a transaction referring to another one not seen or
+    /// disputed yet, i.e. by transactions applied out of order.
+    pub fn is_out_of_order(&self) -> bool {
+        matches!(
+            self,
+            Self::Dispute(..) | Self::Resolve(..) | Self::ResolveUndisputed(..)
+        )
+    }
+
+    /// Whether the error is caused by a transaction breaking a business rule, as opposed to
+    /// e.g. an io error. Such errors are disregarded during non-strict processing.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(
+            self,
+            Self::Withdrawal(..)
+                | Self::Overdrawn(..)
+                | Self::Velocity(..)
+                | Self::Dispute(..)
+                | Self::DisputeAuthorization(..)
+                | Self::Resolve(..)
+                | Self::ResolveUndisputed(..)
+                | Self::Representment(..)
+                | Self::RepresentmentNotChargedBack(..)
+                | Self::Refund(..)
+                | Self::RefundNotWithdrawal(..)
+                | Self::RefundRepeated(..)
+                | Self::RefundDisputed(..)
+                | Self::DisputeRefunded(..)
+                | Self::Authorization(..)
+                | Self::AuthorizationNotFound(..)
+                | Self::AuthorizationSettled(..)
+                | Self::Hold(..)
+                | Self::HoldNotFound(..)
+                | Self::HoldReleased(..)
+                | Self::AdjustmentsDisabled(..)
+                | Self::InterestNotAccrued(..)
+                | Self::Reversal(..)
+                | Self::ReversalDisputed(..)
+                | Self::Reversed(..)
+                | Self::Conversion(..)
+                | Self::TierBalance(..)
+                | Self::TierWithdrawal(..)
+                | Self::BalanceCap(..)
+                | Self::LimitExceeded(..)
+                | Self::Vetoed(..)
+                | Self::RuleViolated(..)
+                | Self::Overflow(..)
+                | Self::DuplicateTransaction(..)
+                | Self::TransactionIdReused(..)
+                | Self::ClientMismatch(..)
+                | Self::Transaction(TransactionError::InvalidAmount(..))
+                | Self::Transaction(TransactionError::ExcessPrecision(..))
+        )
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum MergeError {
+    #[error("transaction found in both merged accounts, account: {0}, transaction: {1}")]
+    TransactionClash(ClientId, TransactionId),
+    #[error("balance overflow merging accounts, account: {0}")]
+    Overflow(ClientId),
+}
+
+type TransactionMap<A = f64> = HashMap<TransactionId, Transaction<A>>;
+
+pub(crate) const DECIMAL_PRECISION: i32 = 4;
+
+pub(crate) fn truncate_to_decimal_precision(num: f64) -> f64 {
     let (int, mut frac) = (num.trunc(), num.fract());
     frac *= 10.0f64.powi(DECIMAL_PRECISION);
     frac = frac.trunc();
     frac /= 10.0f64.powi(DECIMAL_PRECISION);
 
-    ser.serialize_f64(int + frac)
+    int + frac
+}
+
+pub(crate) fn serialize_to_decimal_precision<A, S>(num: &A, ser: S) -> Result<S::Ok, S::Error>
+where
+    A: Amount,
+    S: Serializer,
+{
+    num.truncate().serialize(ser)
 }
 
-#[derive(Debug, Default, PartialEq, Serialize)]
-pub struct Account {
+#[derive(Debug, Default, Getters, PartialEq, Serialize)]
+pub struct Account<A: Amount = f64> {
     client: ClientId,
     #[serde(skip)]
-    transactions: TransactionMap,
-    #[serde(serialize_with = "serialize_f64_to_decimal_precision")]
+    #[getter(skip)]
+    transactions: TransactionMap<A>,
+    /// Ids of the stored transactions, in the order they were applied.
+    #[serde(skip)]
+    #[getter(skip)]
+    history: Vec<TransactionId>,
+    /// Every transaction applied to the account, when journaling is enabled.
+    #[serde(skip)]
+    #[getter(skip)]
+    journal: Vec<Transaction<A>>,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    available: A,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    held: A,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    total: A,
+    locked: bool,
+    #[serde(skip)]
+    fees: A,
+    /// Funds of disputed deposits left available, when crediting them provisionally.
+    #[serde(skip)]
+    provisional: A,
+    #[serde(skip)]
+    #[getter(skip)]
+    interest: InterestAccrual,
+    /// Timestamps and amounts of the withdrawals subject to velocity limits.
+    #[serde(skip)]
+    #[getter(skip)]
+    recent_withdrawals: VecDeque<(Timestamp, A)>,
+    /// Start of the day of the last withdrawal subject to a daily limit, and the amount
+    /// withdrawn that day.
+    #[serde(skip)]
+    #[getter(skip)]
+    withdrawn_today: Option<(Timestamp, A)>,
+    #[serde(skip)]
+    disputes: usize,
+    #[serde(skip)]
+    chargebacks: usize,
+    /// Balances and transactions in assets other than the default one, by symbol.
+    #[serde(skip)]
+    #[getter(skip)]
+    assets: BTreeMap<String, Account<A>>,
+}
+
+/// Account record extended with the fees charged, output when a fee schedule is configured.
+#[cfg(any(feature = "csv", feature = "avro"))]
+#[derive(Serialize)]
+pub(crate) struct AccountWithFees<A: Amount = f64> {
+    client: ClientId,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    available: A,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    held: A,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    total: A,
+    locked: bool,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    fees: A,
+}
+
+#[cfg(any(feature = "csv", feature = "avro"))]
+impl<A: Amount> From<&Account<A>> for AccountWithFees<A> {
+    fn from(acc: &Account<A>) -> Self {
+        Self {
+            client: acc.client,
+            available: acc.available,
+            held: acc.held,
+            total: acc.total,
+            locked: acc.locked,
+            fees: acc.fees,
+        }
+    }
+}
+
+/// Record of the csv output for each asset of an account, output when accounts hold assets
+/// other than the default one, denoted by an empty symbol.
+#[cfg(feature = "csv")]
+#[derive(Serialize)]
+struct AccountAssetRecord<'a, A: Amount = f64> {
+    client: ClientId,
+    asset: &'a str,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    available: A,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    held: A,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    total: A,
+    locked: bool,
+}
+
+/// Complete state of an account, including its stored transactions, as persisted in
+/// checkpoints and serialized along with `Accounts`.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AccountState {
+    client: ClientId,
+    /// Stored transactions, in the order they were applied.
+    transactions: Vec<TransactionState>,
+    journal: Vec<TransactionState>,
     available: f64,
-    #[serde(serialize_with = "serialize_f64_to_decimal_precision")]
     held: f64,
-    #[serde(serialize_with = "serialize_f64_to_decimal_precision")]
     total: f64,
     locked: bool,
+    fees: f64,
+    #[serde(default)]
+    provisional: f64,
+    interest: InterestAccrual,
+    recent_withdrawals: VecDeque<(Timestamp, f64)>,
+    #[serde(default)]
+    withdrawn_today: Option<(Timestamp, f64)>,
+    disputes: usize,
+    chargebacks: usize,
+    #[serde(default)]
+    assets: BTreeMap<String, AccountState>,
+}
+
+impl From<&Account> for AccountState {
+    fn from(acc: &Account) -> Self {
+        Self {
+            client: acc.client,
+            transactions: acc.history().map(TransactionState::from).collect(),
+            journal: acc.journal.iter().map(TransactionState::from).collect(),
+            available: acc.available,
+            held: acc.held,
+            total: acc.total,
+            locked: acc.locked,
+            fees: acc.fees,
+            provisional: acc.provisional,
+            interest: acc.interest.clone(),
+            recent_withdrawals: acc.recent_withdrawals.clone(),
+            withdrawn_today: acc.withdrawn_today,
+            disputes: acc.disputes,
+            chargebacks: acc.chargebacks,
+            assets: acc
+                .assets
+                .iter()
+                .map(|(asset, ledger)| (asset.clone(), AccountState::from(ledger)))
+                .collect(),
+        }
+    }
+}
+
+impl From<AccountState> for Account {
+    fn from(state: AccountState) -> Self {
+        let transactions = state
+            .transactions
+            .into_iter()
+            .map(Transaction::from)
+            .collect::<Vec<_>>();
+        Self {
+            client: state.client,
+            history: transactions.iter().map(|tx| *tx.tx()).collect(),
+            transactions: transactions.into_iter().map(|tx| (*tx.tx(), tx)).collect(),
+            journal: state.journal.into_iter().map(Transaction::from).collect(),
+            available: state.available,
+            held: state.held,
+            total: state.total,
+            locked: state.locked,
+            fees: state.fees,
+            provisional: state.provisional,
+            interest: state.interest,
+            recent_withdrawals: state.recent_withdrawals,
+            withdrawn_today: state.withdrawn_today,
+            disputes: state.disputes,
+            chargebacks: state.chargebacks,
+            assets: state
+                .assets
+                .into_iter()
+                .map(|(asset, ledger)| (asset, Account::from(ledger)))
+                .collect(),
+        }
+    }
+}
+
+/// Account with the balances of a record of the csv output, without any transactions.
+impl From<AccountRecord> for Account {
+    fn from(record: AccountRecord) -> Self {
+        Self {
+            client: record.client,
+            available: record.available,
+            held: record.held,
+            total: record.total,
+            locked: record.locked,
+            ..Self::default()
+        }
+    }
 }
 
-impl Account {
+impl<A: Amount> Account<A> {
     pub fn new(client: ClientId) -> Self {
         Self {
             client,
@@ -69,63 +648,538 @@ impl Account {
         self.locked = true;
     }
 
-    pub fn apply_transaction(&mut self, tx: Transaction) -> Result<(), AccountError> {
+    /// Approximate memory used by the transactions of the account.
+    pub fn footprint(&self) -> AccountFootprint {
+        let key_bytes =
+            |tx: &Transaction<A>| tx.idempotency_key().as_ref().map_or(0, String::capacity);
+        AccountFootprint {
+            client: self.client,
+            transactions: self.transactions.len(),
+            journaled: self.journal.len(),
+            bytes: hash_map_bytes(&self.transactions)
+                + vec_bytes(&self.history)
+                + vec_bytes(&self.journal)
+                + vec_deque_bytes(&self.recent_withdrawals)
+                + self
+                    .transactions
+                    .values()
+                    .chain(&self.journal)
+                    .map(key_bytes)
+                    .sum::<usize>(),
+        }
+    }
+
+    fn store(&mut self, tx: Transaction<A>) {
+        let id = *tx.tx();
+        let clashed = self.transactions.insert(id, tx);
+        debug_assert!(clashed.is_none(), "duplicate ids are rejected beforehand");
+        self.history.push(id);
+    }
+
+    /// Combine the state of `other`, an account of the same client produced from a disjoint
+    /// set of transactions, into this account.
+    fn merge(&mut self, mut other: Account<A>) -> Result<(), MergeError> {
+        if let Some(tx) = other
+            .history
+            .iter()
+            .find(|tx| self.transactions.contains_key(tx))
+        {
+            return Err(MergeError::TransactionClash(self.client, *tx));
+        }
+        let overflow = || MergeError::Overflow(self.client);
+        (
+            self.available,
+            self.held,
+            self.total,
+            self.fees,
+            self.provisional,
+        ) = (
+            (self.available.checked_add(other.available)).ok_or_else(overflow)?,
+            (self.held.checked_add(other.held)).ok_or_else(overflow)?,
+            (self.total.checked_add(other.total)).ok_or_else(overflow)?,
+            (self.fees.checked_add(other.fees)).ok_or_else(overflow)?,
+            (self.provisional.checked_add(other.provisional)).ok_or_else(overflow)?,
+        );
+        self.transactions.extend(other.transactions);
+        self.history.append(&mut other.history);
+        self.journal.append(&mut other.journal);
+        self.locked |= other.locked;
+        self.interest.accrued += other.interest.accrued;
+        self.interest.accrued_until = self
+            .interest
+            .accrued_until
+            .max(other.interest.accrued_until);
+        self.recent_withdrawals.extend(other.recent_withdrawals);
+        self.recent_withdrawals
+            .make_contiguous()
+            .sort_by_key(|(timestamp, _)| *timestamp);
+        self.withdrawn_today = match (self.withdrawn_today, other.withdrawn_today) {
+            (Some((day, withdrawn)), Some((other_day, other_withdrawn))) => {
+                Some(match day.cmp(&other_day) {
+                    Ordering::Equal => (
+                        day,
+                        withdrawn
+                            .checked_add(other_withdrawn)
+                            .ok_or_else(overflow)?,
+                    ),
+                    Ordering::Less => (other_day, other_withdrawn),
+                    Ordering::Greater => (day, withdrawn),
+                })
+            }
+            (ours, theirs) => ours.or(theirs),
+        };
+        self.disputes += other.disputes;
+        self.chargebacks += other.chargebacks;
+        for (asset, ledger) in other.assets {
+            match self.assets.entry(asset) {
+                btree_map::Entry::Occupied(mut entry) => entry.get_mut().merge(ledger)?,
+                btree_map::Entry::Vacant(entry) => {
+                    entry.insert(ledger);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Transactions stored by the account, i.e. deposits, withdrawals and authorizations,
+    /// in the order they were applied and along with their current dispute state.
+    pub fn history(&self) -> impl Iterator<Item = &Transaction<A>> {
+        self.history
+            .iter()
+            .filter_map(|id| self.transactions.get(id))
+    }
+
+    /// Balances and transactions of the account in `asset`, other than the default one, if
+    /// any transaction in it was applied. Its locked status is the one of the account.
+    pub fn asset(&self, asset: &str) -> Option<&Account<A>> {
+        self.assets.get(asset)
+    }
+
+    /// Assets other than the default one the account holds, by symbol, along with their
+    /// balances and transactions.
+    pub fn assets(&self) -> impl Iterator<Item = (&str, &Account<A>)> {
+        self.assets
+            .iter()
+            .map(|(asset, ledger)| (asset.as_str(), ledger))
+    }
+
+    /// Apply `tx` to the balances of `asset`, scoping references to its transactions.
+    fn apply_to_asset(
+        &mut self,
+        asset: String,
+        tx: Transaction<A>,
+        config: &Config,
+    ) -> Result<(), AccountError> {
+        let id = *tx.tx();
+        if tx.type_().is_stored()
+            && (self.transactions.contains_key(&id)
+                || (self.assets.iter()).any(|(other, ledger)| {
+                    *other != asset && ledger.transactions.contains_key(&id)
+                }))
+        {
+            return Err(AccountError::DuplicateTransaction(self.client, id));
+        }
+        let client = self.client;
+        let ledger = self
+            .assets
+            .entry(asset)
+            .or_insert_with(|| Account::new(client));
+        let applied = ledger.apply_transaction_with_config(tx, config);
+        if ledger.locked {
+            self.freeze();
+        } else if applied.is_err() && *ledger == Account::new(client) {
+            self.assets
+                .retain(|_, ledger| *ledger != Account::new(client));
+        }
+        applied
+    }
+
+    /// Balances of `asset`, or of the default asset if `None`, created blank if missing.
+    fn ledger_mut(&mut self, asset: Option<&str>) -> &mut Account<A> {
+        let client = self.client;
+        match asset {
+            Some(asset) => {
+                (self.assets.entry(asset.to_string())).or_insert_with(|| Account::new(client))
+            }
+            None => self,
+        }
+    }
+
+    /// Move the amount of conversion `tx` from the balances of its asset to the ones of its
+    /// target asset, at the rate recorded on it or else the one configured.
+    fn convert(&mut self, tx: &Transaction<A>, config: &Config) -> Result<(), AccountError> {
+        let (client, tx_id) = (self.client, *tx.tx());
+        let overflow = || AccountError::Overflow(client, tx_id);
+        let (from, to) = (tx.asset().as_deref(), tx.to_asset().as_deref());
+        let rate = tx
+            .rate()
+            .or_else(|| config.fx.as_ref()?.rate(from, to))
+            .ok_or(AccountError::Conversion(client, tx_id))?;
+        let amount = tx.amount();
+        let converted = A::from_f64(amount.to_f64() * rate).truncate();
+
+        let available = match from {
+            Some(from) => (self.assets.get(from)).map_or(A::ZERO, |ledger| ledger.available),
+            None => self.available,
+        };
+        let debited = available.checked_sub(amount).ok_or_else(overflow)?;
+        if amount > A::ZERO && !config.balance.allows(debited.to_f64()) {
+            return Err(AccountError::Overdrawn(client, tx_id));
+        }
+        let source = self.ledger_mut(from);
+        (source.available, source.total) = (
+            debited,
+            source.total.checked_sub(amount).ok_or_else(overflow)?,
+        );
+        let target = self.ledger_mut(to);
+        (target.available, target.total) = (
+            target
+                .available
+                .checked_add(converted)
+                .ok_or_else(overflow)?,
+            target.total.checked_add(converted).ok_or_else(overflow)?,
+        );
+        Ok(())
+    }
+
+    /// Amount deposit `tx` is reduced to under the balance cap of `config`, if reduced,
+    /// failing if rejected instead as per the policy of the cap.
+    pub(crate) fn capped_amount(
+        &self,
+        tx: &Transaction<A>,
+        config: &Config,
+    ) -> Result<Option<A>, AccountError> {
+        let (Some(cap), TransactionType::Deposit) = (&config.balance_cap, tx.type_()) else {
+            return Ok(None);
+        };
+        let total = self.total.to_f64();
+        if total + tx.amount().to_f64() <= cap.max_total {
+            return Ok(None);
+        }
+        let headroom = A::from_f64(cap.headroom(total)).truncate();
+        if cap.action == CapAction::Reject || headroom <= A::ZERO {
+            return Err(AccountError::BalanceCap(self.client, *tx.tx()));
+        }
+        Ok(Some(headroom))
+    }
+
+    /// Stored transaction with id `tx`, if any.
+    pub(crate) fn stored(&self, tx: TransactionId) -> Option<&Transaction<A>> {
+        self.transactions.get(&tx)
+    }
+
+    /// Whether applying a transaction of type `type_`, with held funds amounting to
+    /// `held_before` beforehand, triggers freezing the account according to `policy`.
+    fn freeze_triggered(
+        &self,
+        policy: &FreezePolicy,
+        type_: &TransactionType,
+        held_before: A,
+    ) -> bool {
+        match policy {
+            FreezePolicy::FirstChargeback => *type_ == TransactionType::Chargeback,
+            FreezePolicy::Chargebacks(n) => {
+                *type_ == TransactionType::Chargeback && self.chargebacks >= *n
+            }
+            FreezePolicy::Disputes(n) => *type_ == TransactionType::Dispute && self.disputes >= *n,
+            FreezePolicy::HeldAbove(threshold) => {
+                self.held > held_before && self.held.to_f64() > *threshold
+            }
+        }
+    }
+
+    /// Unlock the account, e.g. after operations manually reviewed a chargeback.
+    pub fn unfreeze(&mut self) {
+        self.locked = false;
+        for ledger in self.assets.values_mut() {
+            ledger.unfreeze();
+        }
+    }
+
+    /// Accrue daily interest on available funds up to the day of `now`, stopping early once
+    /// a posting period elapses to return the interest to post as of then, applied as an
+    /// `interest` transaction before accruing further.
+    pub fn accrue_interest(
+        &mut self,
+        now: Timestamp,
+        policy: &InterestPolicy,
+    ) -> Option<(Timestamp, A)> {
+        let today = now - now % SECONDS_PER_DAY;
+        let Some(mut accrued_until) = self.interest.accrued_until else {
+            self.interest.accrued_until = Some(today);
+            return None;
+        };
+        let mut posting = None;
+        while accrued_until < today && posting.is_none() {
+            self.interest.accrued += policy.daily_interest(self.available.to_f64());
+            self.interest.days_since_posting += 1;
+            accrued_until += SECONDS_PER_DAY;
+            if self.interest.days_since_posting >= policy.posting_period_days {
+                self.interest.days_since_posting = 0;
+                let accrued = std::mem::take(&mut self.interest.accrued);
+                posting = Some((accrued_until, A::from_f64(accrued)));
+            }
+        }
+        self.interest.accrued_until = Some(accrued_until);
+        posting
+    }
+
+    pub fn apply_transaction(&mut self, tx: Transaction<A>) -> Result<(), AccountError> {
+        self.apply_transaction_with_config(tx, &Config::default())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(client = tx.client(), tx = tx.tx(), tx_type = %tx.type_()),
+            err(level = "debug"),
+        )
+    )]
+    pub fn apply_transaction_with_config(
+        &mut self,
+        tx: Transaction<A>,
+        config: &Config,
+    ) -> Result<(), AccountError> {
         if *tx.client() != self.client {
-            panic!(
-                "applied transaction on client {} to account {}",
-                tx.client(),
-                self.client
-            );
+            return Err(AccountError::WrongAccount(
+                self.client,
+                *tx.tx(),
+                *tx.client(),
+            ));
+        }
+        let mut tx = tx;
+        // Transactions referring to one in another asset default to that asset, conversions
+        // moving funds between assets themselves.
+        let converted = *tx.type_() == TransactionType::Convert;
+        let asset = (!converted).then(|| tx.take_asset()).flatten().or_else(|| {
+            (tx.type_().is_reference() && !self.transactions.contains_key(tx.tx()))
+                .then(|| {
+                    (self.assets.iter())
+                        .find(|(_, ledger)| ledger.transactions.contains_key(tx.tx()))
+                        .map(|(asset, _)| asset.clone())
+                })
+                .flatten()
+        });
+        if let Some(asset) = asset {
+            return self.apply_to_asset(asset, tx, config);
+        }
+        tx.validate()?;
+        if *tx.type_() == TransactionType::Adjustment && !config.allow_adjustments {
+            return Err(AccountError::AdjustmentsDisabled(self.client, *tx.tx()));
+        }
+        let tx = tx.with_precision(config.precision)?;
+        let tx = match self.capped_amount(&tx, config)? {
+            Some(amount) => tx.with_amount(amount),
+            None => tx,
+        };
+        if tx.type_().is_stored()
+            && (self.transactions.contains_key(tx.tx())
+                || (self.assets.values()).any(|ledger| ledger.transactions.contains_key(tx.tx())))
+        {
+            return Err(AccountError::DuplicateTransaction(self.client, *tx.tx()));
+        }
+
+        let fee = config.fees.as_ref().map_or(A::ZERO, |fees| {
+            A::from_f64(fees.fee(tx.type_(), tx.amount().to_f64()))
+        });
+
+        let (client, tx_id) = (self.client, *tx.tx());
+        let overflow = || AccountError::Overflow(client, tx_id);
+
+        // Amount debited from available funds by the transaction itself.
+        let debit = match tx.type_() {
+            TransactionType::Deposit | TransactionType::Interest | TransactionType::Adjustment => {
+                -tx.amount()
+            }
+            TransactionType::Withdrawal | TransactionType::Auth | TransactionType::Hold => {
+                tx.amount()
+            }
+            TransactionType::Dispute if !config.provisional_credit => self
+                .transactions
+                .get(tx.tx())
+                .filter(|disputed| {
+                    *disputed.type_() == TransactionType::Deposit && !disputed.reversed()
+                })
+                .map_or(A::ZERO, |disputed| disputed.amount()),
+            TransactionType::Reversal => self
+                .transactions
+                .get(tx.tx())
+                .filter(|reversed| !reversed.reversed())
+                .map_or(A::ZERO, |reversed| match reversed.type_() {
+                    TransactionType::Deposit => reversed.amount(),
+                    TransactionType::Withdrawal => -reversed.amount(),
+                    _ => A::ZERO,
+                }),
+            TransactionType::Representment => self
+                .transactions
+                .get(tx.tx())
+                .filter(|charged_back| {
+                    *charged_back.type_() == TransactionType::Withdrawal
+                        && *charged_back.charged_back()
+                })
+                .map_or(A::ZERO, |charged_back| charged_back.amount()),
+            _ => A::ZERO,
+        };
+        // Net amount debited, fees included.
+        let debit = fee.checked_add(debit).ok_or_else(overflow)?;
+        let debited = self.available.checked_sub(debit).ok_or_else(overflow)?;
+        if debit > A::ZERO && !config.balance.allows(debited.to_f64()) {
+            return Err(match tx.type_() {
+                TransactionType::Withdrawal => AccountError::Withdrawal(self.client, *tx.tx()),
+                TransactionType::Auth => AccountError::Authorization(self.client, *tx.tx()),
+                TransactionType::Hold => AccountError::Hold(self.client, *tx.tx()),
+                _ => AccountError::Overdrawn(self.client, *tx.tx()),
+            });
+        }
+
+        if let Some((tier, limits)) = config.tiers.tier(self.client) {
+            let amount = tx.amount().to_f64();
+            match tx.type_() {
+                TransactionType::Deposit if !limits.allows_deposit(self.total.to_f64(), amount) => {
+                    return Err(AccountError::TierBalance(
+                        self.client,
+                        *tx.tx(),
+                        tier.to_string(),
+                    ));
+                }
+                TransactionType::Withdrawal if !limits.allows_withdrawal(amount) => {
+                    return Err(AccountError::TierWithdrawal(
+                        self.client,
+                        *tx.tx(),
+                        tier.to_string(),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        // Amount withdrawn on the day of the withdrawal, if subject to a daily limit.
+        let withdrawn_today = match (tx.type_(), config.daily_withdrawals.limit(self.client)) {
+            (TransactionType::Withdrawal, Some(limit)) => tx.timestamp().map(|timestamp| {
+                let today = timestamp - timestamp % SECONDS_PER_DAY;
+                let withdrawn = match self.withdrawn_today {
+                    Some((day, withdrawn)) if day == today => withdrawn,
+                    _ => A::ZERO,
+                };
+                (today, limit, withdrawn)
+            }),
+            _ => None,
+        };
+        if let Some((_, limit, withdrawn)) = withdrawn_today {
+            let amount = tx.amount().to_f64();
+            if withdrawn.to_f64() + amount > limit {
+                return Err(AccountError::LimitExceeded(
+                    self.client,
+                    *tx.tx(),
+                    limit,
+                    amount,
+                ));
+            }
+        }
+
+        let velocity_limit = config.velocity.limit(self.client);
+        if let (TransactionType::Withdrawal, Some(limit), Some(timestamp)) =
+            (tx.type_(), velocity_limit, tx.timestamp())
+        {
+            while self
+                .recent_withdrawals
+                .front()
+                .is_some_and(|(ts, _)| ts.saturating_add(limit.window) <= *timestamp)
+            {
+                self.recent_withdrawals.pop_front();
+            }
+            let withdrawn = self
+                .recent_withdrawals
+                .iter()
+                .map(|(_, a)| a.to_f64())
+                .sum();
+            if !limit.allows(
+                self.recent_withdrawals.len(),
+                withdrawn,
+                tx.amount().to_f64(),
+            ) {
+                return Err(AccountError::Velocity(self.client, *tx.tx()));
+            }
         }
+        // Amount withdrawn on the day once the withdrawal is debited.
+        let withdrawn_today = match withdrawn_today {
+            Some((today, _, withdrawn)) => Some((
+                today,
+                withdrawn.checked_add(tx.amount()).ok_or_else(overflow)?,
+            )),
+            None => None,
+        };
 
+        let (type_, timestamp, amount) = (*tx.type_(), *tx.timestamp(), tx.amount());
+        let held_before = self.held;
         match &tx.type_() {
             TransactionType::Deposit => {
                 let amount = tx.amount();
-                self.available += amount;
-                self.total += amount;
-                if let Some(tx_clashed) = self.transactions.insert(*tx.tx(), tx) {
-                    panic!(
-                        "multiple transactions with the same id: {}",
-                        *tx_clashed.tx()
-                    );
-                }
+                (self.available, self.total) = (
+                    self.available.checked_add(amount).ok_or_else(overflow)?,
+                    self.total.checked_add(amount).ok_or_else(overflow)?,
+                );
+                self.store(tx);
             }
             TransactionType::Withdrawal => {
                 let amount = tx.amount();
-                if self.available < amount {
-                    return Err(AccountError::Withdrawal(self.client, *tx.tx()));
-                }
-                self.available -= amount;
-                self.total -= amount;
-                if let Some(tx_clashed) = self.transactions.insert(*tx.tx(), tx) {
-                    panic!(
-                        "multiple transactions with the same id: {}",
-                        *tx_clashed.tx()
-                    );
-                }
+                (self.available, self.total) = (
+                    self.available.checked_sub(amount).ok_or_else(overflow)?,
+                    self.total.checked_sub(amount).ok_or_else(overflow)?,
+                );
+                self.store(tx);
             }
             TransactionType::Dispute => {
                 let disputed = self
                     .transactions
                     .get_mut(tx.tx())
                     .ok_or(AccountError::Dispute(self.client, *tx.tx()))?;
+                if *disputed.reversed() {
+                    return Err(AccountError::Reversed(self.client, *disputed.tx()));
+                }
+                // Refunded withdrawals were already credited back, and would be once more if
+                // charged back.
+                if *disputed.refunded() {
+                    return Err(AccountError::DisputeRefunded(self.client, *disputed.tx()));
+                }
                 match disputed.type_() {
+                    TransactionType::Deposit if config.provisional_credit => {
+                        let amount = disputed.amount();
+                        self.provisional =
+                            self.provisional.checked_add(amount).ok_or_else(overflow)?;
+                        disputed.dispute_at(*tx.timestamp());
+                        self.disputes += 1;
+                    }
                     TransactionType::Deposit => {
                         let amount = disputed.amount();
-                        self.available -= amount;
-                        self.held += amount;
-                        disputed.dispute();
+                        (self.available, self.held) = (
+                            self.available.checked_sub(amount).ok_or_else(overflow)?,
+                            self.held.checked_add(amount).ok_or_else(overflow)?,
+                        );
+                        disputed.dispute_at(*tx.timestamp());
+                        self.disputes += 1;
                     }
                     TransactionType::Withdrawal => {
                         // Disputing a withdrawal, e.g. disputing having received amount withdrawn.
                         // A valid withdrawal dispute would imply that the client has once more a
                         // total amount of funds that includes the ones they attempted to withdraw.
                         let amount = disputed.amount();
-                        self.held += amount;
-                        self.total += amount;
-                        disputed.dispute();
+                        (self.held, self.total) = (
+                            self.held.checked_add(amount).ok_or_else(overflow)?,
+                            self.total.checked_add(amount).ok_or_else(overflow)?,
+                        );
+                        disputed.dispute_at(*tx.timestamp());
+                        self.disputes += 1;
                     }
-                    _ => panic!("deposits and withdrawals are the only transaction types stored"),
+                    TransactionType::Auth => {
+                        return Err(AccountError::DisputeAuthorization(
+                            self.client,
+                            *disputed.tx(),
+                        ))
+                    }
+                    _ => return Err(AccountError::Dispute(self.client, *disputed.tx())),
                 }
             }
             TransactionType::Resolve => {
@@ -137,10 +1191,18 @@ impl Account {
                     return Err(AccountError::ResolveUndisputed(self.client, *disputed.tx()));
                 }
                 match disputed.type_() {
+                    TransactionType::Deposit if config.provisional_credit => {
+                        // The provisional credit becomes final.
+                        let amount = disputed.amount();
+                        self.provisional =
+                            self.provisional.checked_sub(amount).ok_or_else(overflow)?;
+                    }
                     TransactionType::Deposit => {
                         let amount = disputed.amount();
-                        self.available += amount;
-                        self.held -= amount;
+                        (self.available, self.held) = (
+                            self.available.checked_add(amount).ok_or_else(overflow)?,
+                            self.held.checked_sub(amount).ok_or_else(overflow)?,
+                        );
                     }
                     TransactionType::Withdrawal => {
                         // The withdrawal dispute was resolved, which means e.g. that the dispute
@@ -148,10 +1210,12 @@ impl Account {
                         // place as expected and the funds involved cannot be credited to the
                         // client any longer.
                         let amount = disputed.amount();
-                        self.held -= amount;
-                        self.total -= amount;
+                        (self.held, self.total) = (
+                            self.held.checked_sub(amount).ok_or_else(overflow)?,
+                            self.total.checked_sub(amount).ok_or_else(overflow)?,
+                        );
                     }
-                    _ => panic!("deposits and withdrawals are the only transaction types stored"),
+                    _ => return Err(AccountError::ResolveUndisputed(self.client, *disputed.tx())),
                 }
                 disputed.resolve();
             }
@@ -164,10 +1228,22 @@ impl Account {
                     return Err(AccountError::ResolveUndisputed(self.client, *disputed.tx()));
                 }
                 match disputed.type_() {
+                    TransactionType::Deposit if config.provisional_credit => {
+                        // The provisional credit is clawed back from available funds.
+                        let amount = disputed.amount();
+                        (self.available, self.total, self.provisional) = (
+                            self.available.checked_sub(amount).ok_or_else(overflow)?,
+                            self.total.checked_sub(amount).ok_or_else(overflow)?,
+                            self.provisional.checked_sub(amount).ok_or_else(overflow)?,
+                        );
+                        disputed.resolve();
+                    }
                     TransactionType::Deposit => {
                         let amount = disputed.amount();
-                        self.held -= amount;
-                        self.total -= amount;
+                        (self.held, self.total) = (
+                            self.held.checked_sub(amount).ok_or_else(overflow)?,
+                            self.total.checked_sub(amount).ok_or_else(overflow)?,
+                        );
                         disputed.resolve();
                     }
                     TransactionType::Withdrawal => {
@@ -175,20 +1251,210 @@ impl Account {
                         // withdrawal didn't take place as expected, and those funds should once
                         // more become available to the client.
                         let amount = disputed.amount();
-                        self.available += amount;
-                        self.held -= amount;
+                        (self.available, self.held) = (
+                            self.available.checked_add(amount).ok_or_else(overflow)?,
+                            self.held.checked_sub(amount).ok_or_else(overflow)?,
+                        );
                     }
-                    _ => panic!("deposits and withdrawals are the only transaction types stored"),
+                    _ => return Err(AccountError::ResolveUndisputed(self.client, *disputed.tx())),
                 }
-                disputed.resolve();
-                self.freeze();
+                disputed.charge_back();
+                self.chargebacks += 1;
+            }
+            TransactionType::Representment => {
+                let charged_back = self
+                    .transactions
+                    .get_mut(tx.tx())
+                    .ok_or(AccountError::Representment(self.client, *tx.tx()))?;
+                if !charged_back.charged_back() {
+                    return Err(AccountError::RepresentmentNotChargedBack(
+                        self.client,
+                        *charged_back.tx(),
+                    ));
+                }
+                match charged_back.type_() {
+                    TransactionType::Deposit => {
+                        // The merchant successfully contested the chargeback, i.e. the deposit did
+                        // take place after all, so the funds are credited to the client once more.
+                        let amount = charged_back.amount();
+                        (self.available, self.total) = (
+                            self.available.checked_add(amount).ok_or_else(overflow)?,
+                            self.total.checked_add(amount).ok_or_else(overflow)?,
+                        );
+                    }
+                    TransactionType::Withdrawal => {
+                        // The withdrawal did take place after all, so the funds that were made
+                        // available by the chargeback are debited once more.
+                        let amount = charged_back.amount();
+                        (self.available, self.total) = (
+                            self.available.checked_sub(amount).ok_or_else(overflow)?,
+                            self.total.checked_sub(amount).ok_or_else(overflow)?,
+                        );
+                    }
+                    _ => {
+                        return Err(AccountError::RepresentmentNotChargedBack(
+                            self.client,
+                            *charged_back.tx(),
+                        ))
+                    }
+                }
+                charged_back.represent();
+            }
+            TransactionType::Unlock => self.unfreeze(),
+            TransactionType::Convert => self.convert(&tx, config)?,
+            TransactionType::Interest | TransactionType::Adjustment => {
+                let amount = tx.amount();
+                (self.available, self.total) = (
+                    self.available.checked_add(amount).ok_or_else(overflow)?,
+                    self.total.checked_add(amount).ok_or_else(overflow)?,
+                );
+            }
+            TransactionType::Refund => {
+                let refunded = self
+                    .transactions
+                    .get_mut(tx.tx())
+                    .ok_or(AccountError::Refund(self.client, *tx.tx()))?;
+                if *refunded.type_() != TransactionType::Withdrawal {
+                    return Err(AccountError::RefundNotWithdrawal(
+                        self.client,
+                        *refunded.tx(),
+                    ));
+                }
+                if *refunded.refunded() {
+                    return Err(AccountError::RefundRepeated(self.client, *refunded.tx()));
+                }
+                if *refunded.reversed() {
+                    return Err(AccountError::Reversed(self.client, *refunded.tx()));
+                }
+                // Disputed withdrawals are credited back on chargeback, so refunding them
+                // would credit them twice.
+                if *refunded.disputed() || *refunded.charged_back() {
+                    return Err(AccountError::RefundDisputed(self.client, *refunded.tx()));
+                }
+                let amount = refunded.amount();
+                (self.available, self.total) = (
+                    self.available.checked_add(amount).ok_or_else(overflow)?,
+                    self.total.checked_add(amount).ok_or_else(overflow)?,
+                );
+                refunded.refund();
+            }
+            TransactionType::Reversal => {
+                let reversed = self
+                    .transactions
+                    .get_mut(tx.tx())
+                    .filter(|reversed| {
+                        matches!(
+                            reversed.type_(),
+                            TransactionType::Deposit | TransactionType::Withdrawal
+                        )
+                    })
+                    .ok_or(AccountError::Reversal(self.client, *tx.tx()))?;
+                if *reversed.reversed() {
+                    return Err(AccountError::Reversed(self.client, *reversed.tx()));
+                }
+                if *reversed.disputed() || *reversed.charged_back() || *reversed.refunded() {
+                    return Err(AccountError::ReversalDisputed(self.client, *reversed.tx()));
+                }
+                let amount = match reversed.type_() {
+                    TransactionType::Deposit => -reversed.amount(),
+                    _ => reversed.amount(),
+                };
+                (self.available, self.total) = (
+                    self.available.checked_add(amount).ok_or_else(overflow)?,
+                    self.total.checked_add(amount).ok_or_else(overflow)?,
+                );
+                reversed.reverse();
+            }
+            TransactionType::Auth => {
+                let amount = tx.amount();
+                (self.available, self.held) = (
+                    self.available.checked_sub(amount).ok_or_else(overflow)?,
+                    self.held.checked_add(amount).ok_or_else(overflow)?,
+                );
+                self.store(tx);
+            }
+            TransactionType::Capture | TransactionType::Void => {
+                let auth = self
+                    .transactions
+                    .get_mut(tx.tx())
+                    .filter(|auth| *auth.type_() == TransactionType::Auth)
+                    .ok_or(AccountError::AuthorizationNotFound(self.client, *tx.tx()))?;
+                if *auth.settled() {
+                    return Err(AccountError::AuthorizationSettled(self.client, *auth.tx()));
+                }
+                let amount = auth.amount();
+                if *tx.type_() == TransactionType::Capture {
+                    // The held funds leave the account, as for a withdrawal.
+                    (self.held, self.total) = (
+                        self.held.checked_sub(amount).ok_or_else(overflow)?,
+                        self.total.checked_sub(amount).ok_or_else(overflow)?,
+                    );
+                } else {
+                    (self.available, self.held) = (
+                        self.available.checked_add(amount).ok_or_else(overflow)?,
+                        self.held.checked_sub(amount).ok_or_else(overflow)?,
+                    );
+                }
+                auth.settle();
+            }
+            TransactionType::Hold => {
+                let amount = tx.amount();
+                (self.available, self.held) = (
+                    self.available.checked_sub(amount).ok_or_else(overflow)?,
+                    self.held.checked_add(amount).ok_or_else(overflow)?,
+                );
+                self.store(tx);
+            }
+            TransactionType::Release => {
+                let hold = self
+                    .transactions
+                    .get_mut(tx.tx())
+                    .filter(|hold| *hold.type_() == TransactionType::Hold)
+                    .ok_or(AccountError::HoldNotFound(self.client, *tx.tx()))?;
+                if *hold.settled() {
+                    return Err(AccountError::HoldReleased(self.client, *hold.tx()));
+                }
+                let amount = hold.amount();
+                (self.available, self.held) = (
+                    self.available.checked_add(amount).ok_or_else(overflow)?,
+                    self.held.checked_sub(amount).ok_or_else(overflow)?,
+                );
+                hold.settle();
+            }
+        }
+        if self.freeze_triggered(&config.freeze, &type_, held_before) {
+            self.freeze();
+        }
+        (self.available, self.total, self.fees) = (
+            self.available.checked_sub(fee).ok_or_else(overflow)?,
+            self.total.checked_sub(fee).ok_or_else(overflow)?,
+            self.fees.checked_add(fee).ok_or_else(overflow)?,
+        );
+        if withdrawn_today.is_some() {
+            self.withdrawn_today = withdrawn_today;
+        }
+        // Only withdrawals debited count towards the velocity limit.
+        if let (TransactionType::Withdrawal, Some(_), Some(timestamp)) =
+            (type_, velocity_limit, timestamp)
+        {
+            self.recent_withdrawals.push_back((timestamp, amount));
+        }
+
+        if config.validate {
+            let violations = self.validate_with_policy(&config.balance);
+            if !violations.is_empty() {
+                return Err(AccountError::InvariantViolated(
+                    self.client,
+                    tx_id,
+                    violations,
+                ));
             }
         }
         Ok(())
     }
 }
 
-impl Display for Account {
+impl<A: Amount> Display for Account<A> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
@@ -198,71 +1464,974 @@ impl Display for Account {
     }
 }
 
+/// Point in the sequence of transactions applied to an account.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PointInTime {
+    /// Right after the last transaction with the given id, e.g. a chargeback of a deposit.
+    Transaction(TransactionId),
+    /// Right after the last transaction timestamped at or before the given time.
+    Timestamp(Timestamp),
+}
+
+#[cfg(any(feature = "csv", feature = "json"))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HistoryFormat {
+    #[cfg(feature = "csv")]
+    Csv,
+    #[cfg(feature = "json")]
+    Json,
+}
+
+#[cfg(any(feature = "csv", feature = "json"))]
+impl HistoryFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "csv")]
+            Self::Csv => "csv",
+            #[cfg(feature = "json")]
+            Self::Json => "json",
+        }
+    }
+}
+
 #[derive(Default, Deref, DerefMut)]
-pub struct Accounts(HashMap<ClientId, Account>);
+pub struct Accounts<A: Amount = f64> {
+    #[deref]
+    #[deref_mut]
+    accounts: HashMap<ClientId, Account<A>>,
+    config: Config,
+    risk_scorer: Option<Box<dyn RiskScorer<A>>>,
+    risk_flags: Vec<RiskFlag>,
+    anomaly_detectors: Vec<Box<dyn AnomalyDetector<A>>>,
+    /// Rolling statistics of each client, when detecting anomalies.
+    client_stats: HashMap<ClientId, ClientStats>,
+    anomalies: Vec<Anomaly>,
+    observers: Vec<Box<dyn Observer<A>>>,
+    dedup: Option<Box<dyn DedupStore>>,
+    error_handler: Option<Box<dyn ErrorHandler<A>>>,
+    dispute_arbiter: Option<Box<dyn DisputeArbiter<A>>>,
+    /// Clients of the stored transactions by id, when enforcing globally unique ids, built
+    /// on first use.
+    tx_index: Option<HashMap<TransactionId, ClientId>>,
+    /// Deadlines of the disputes opened, by client and transaction id, when settling stale
+    /// disputes. Entries of disputes settled before their deadline are discarded on expiry.
+    dispute_deadlines: BTreeSet<(Timestamp, ClientId, TransactionId)>,
+    /// Transactions scheduled to take effect later, by effective date and order of arrival.
+    scheduled: BTreeMap<(Timestamp, usize), Transaction<A>>,
+    scheduled_seq: usize,
+    /// Recurring instructions, along with their next occurrence to schedule.
+    recurrences: Vec<(Recurrence, u32)>,
+    /// Latest date processing reached, as per transaction timestamps and ticks.
+    clock: Option<Timestamp>,
+    warnings: Vec<AccountError>,
+    report: ProcessingReport,
+}
 
-impl Accounts {
-    pub fn from_transaction_iter<T: Iterator<Item = Result<Transaction, TransactionError>>>(
-        tx_iter: T,
-        strict: bool,
-    ) -> Result<Self, AccountError> {
-        let mut accounts = Self::default();
-        for tx in tx_iter {
-            let tx = tx?;
-            if let Err(e) = accounts
-                .entry(*tx.client())
-                .or_insert(Account::new(*tx.client()))
-                .apply_transaction(tx)
-            {
-                if !strict
-                    && matches!(
-                        e,
-                        AccountError::Withdrawal(..)
-                            | AccountError::Dispute(..)
-                            | AccountError::Resolve(..)
-                            | AccountError::ResolveUndisputed(..)
-                    )
-                {
-                    continue;
-                }
-                return Err(e);
-            }
+impl<A: Amount> Accounts<A> {
+    pub fn with_config(config: Config) -> Self {
+        Self {
+            config,
+            ..Self::default()
         }
-        Ok(accounts)
     }
 
-    pub fn from_transactions(
-        transactions: Transactions,
-        strict: bool,
-    ) -> Result<Self, AccountError> {
-        Self::from_transaction_iter(transactions.0.into_iter().map(Ok), strict)
+    /// Unlock accounts once a chargeback on them is successfully represented.
+    pub fn unfreeze_on_representment(mut self, unfreeze: bool) -> Self {
+        self.config.unfreeze_on_representment = unfreeze;
+        self
     }
 
-    pub fn to_csv(&self) -> Result<(), AccountError> {
-        let mut wrt = csv::Writer::from_writer(stdout());
-        for acc in self.0.values() {
-            wrt.serialize(acc)?;
-        }
-        wrt.flush()?;
+    /// Score each transaction with `scorer` before applying it.
+    pub fn with_risk_scorer<R: RiskScorer<A> + 'static>(mut self, scorer: R) -> Self {
+        self.risk_scorer = Some(Box::new(scorer));
+        self
+    }
 
-        Ok(())
+    /// Check each deposit, withdrawal or other stored transaction with `detector` before
+    /// applying it, withholding the ones it flags, as well as the ones any other detector
+    /// flags.
+    pub fn with_anomaly_detector<D: AnomalyDetector<A> + 'static>(mut self, detector: D) -> Self {
+        self.anomaly_detectors.push(Box::new(detector));
+        self
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::{Account, AccountError, Transaction, TransactionMap, TransactionType};
+    /// Apply the transactions of `recurrence` as processing reaches each of its occurrences.
+    pub fn with_recurrence(mut self, recurrence: Recurrence) -> Self {
+        self.add_recurrence(recurrence);
+        self
+    }
 
-    #[test]
-    fn serialize_accounts() {
-        let accounts = vec![
-            Account {
+    /// Apply the transactions of `recurrence` as processing reaches each of its occurrences,
+    /// e.g. to add instructions while serving.
+    pub fn add_recurrence(&mut self, recurrence: Recurrence) {
+        self.recurrences.push((recurrence, 0));
+    }
+
+    /// Assign `client` to KYC tier `tier`, replacing any previous tier, e.g. once the client
+    /// completed verification while serving.
+    pub fn set_tier<S: Into<String>>(&mut self, client: ClientId, tier: S) {
+        self.config.tiers.clients.insert(client, tier.into());
+    }
+
+    /// Skip transactions already recorded in `store`, recording the others as they are
+    /// processed.
+    pub fn with_dedup<D: DedupStore + 'static>(mut self, store: D) -> Self {
+        self.dedup = Some(Box::new(store));
+        self
+    }
+
+    pub(crate) fn dedup(&self) -> Option<&dyn DedupStore> {
+        self.dedup.as_deref()
+    }
+
+    #[cfg(feature = "checkpoint")]
+    pub(crate) fn dedup_mut(&mut self) -> Option<&mut (dyn DedupStore + 'static)> {
+        self.dedup.as_deref_mut()
+    }
+
+    /// Let `handler` decide whether to skip transactions breaking business rules or abort
+    /// processing, regardless of the strictness of processing.
+    pub fn with_error_handler<H: ErrorHandler<A> + 'static>(mut self, handler: H) -> Self {
+        self.error_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Let `arbiter` decide the immediate outcome of disputes as they are opened.
+    pub fn with_dispute_arbiter<D: DisputeArbiter<A> + 'static>(mut self, arbiter: D) -> Self {
+        self.dispute_arbiter = Some(Box::new(arbiter));
+        self
+    }
+
+    /// Notify `observer` of transaction lifecycle events.
+    pub fn with_observer<O: Observer<A> + 'static>(mut self, observer: O) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    pub fn get(&self, client: ClientId) -> Option<&Account<A>> {
+        self.accounts.get(&client)
+    }
+
+    /// Accounts, ordered by client id.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = &Account<A>> {
+        let mut accounts = self.accounts.values().collect::<Vec<_>>();
+        accounts.sort_unstable_by_key(|acc| acc.client);
+        accounts.into_iter()
+    }
+
+    pub fn locked_accounts(&self) -> impl Iterator<Item = &Account<A>> {
+        self.accounts.values().filter(|acc| acc.locked)
+    }
+
+    pub fn accounts_with_held_funds(&self) -> impl Iterator<Item = &Account<A>> {
+        self.accounts.values().filter(|acc| acc.held > A::ZERO)
+    }
+
+    /// The `n` accounts with the largest `key`, in decreasing order, accounts with equal keys
+    /// being ordered by client id, e.g. the largest ones by total with
+    /// `accounts.top_by(|acc| *acc.total(), 10)`.
+    pub fn top_by<K: PartialOrd, F: Fn(&Account<A>) -> K>(
+        &self,
+        key: F,
+        n: usize,
+    ) -> Vec<&Account<A>> {
+        let mut accounts = self
+            .iter_sorted()
+            .map(|acc| (key(acc), acc))
+            .collect::<Vec<_>>();
+        // Sorting is stable, keeping accounts with equal keys ordered by client id.
+        accounts.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        accounts.into_iter().take(n).map(|(_, acc)| acc).collect()
+    }
+
+    /// Combine accounts produced from disjoint shards of transactions: the balances of clients
+    /// found in both are summed and their stored transactions unioned, failing if the same
+    /// transaction was applied to both. Configuration and hooks of `self` are retained.
+    pub fn merge(mut self, other: Accounts<A>) -> Result<Accounts<A>, MergeError> {
+        for (client, account) in other.accounts {
+            match self.accounts.entry(client) {
+                Entry::Occupied(mut entry) => entry.get_mut().merge(account)?,
+                Entry::Vacant(entry) => {
+                    entry.insert(account);
+                }
+            }
+        }
+        for ((effective, _), tx) in other.scheduled {
+            self.scheduled.insert((effective, self.scheduled_seq), tx);
+            self.scheduled_seq += 1;
+        }
+        self.dispute_deadlines.extend(other.dispute_deadlines);
+        self.recurrences.extend(other.recurrences);
+        self.clock = self.clock.max(other.clock);
+        self.report.merge(&other.report);
+        self.risk_flags.extend(other.risk_flags);
+        self.anomalies.extend(other.anomalies);
+        self.warnings.extend(other.warnings);
+        self.tx_index = None;
+        Ok(self)
+    }
+
+    /// State of the account of `client` at the given point in time, obtained by replaying its
+    /// journal, which requires journaling to be enabled through `Config`. Replaying fails if
+    /// the configuration changed such that journaled transactions can't be applied anymore.
+    pub fn account_at(
+        &self,
+        client: ClientId,
+        at: PointInTime,
+    ) -> Result<Option<Account<A>>, AccountError> {
+        let Some(account) = self.accounts.get(&client) else {
+            return Ok(None);
+        };
+        let journal = &account.journal;
+        let end = match at {
+            PointInTime::Transaction(id) => match journal.iter().rposition(|tx| *tx.tx() == id) {
+                Some(i) => i + 1,
+                None => return Ok(None),
+            },
+            PointInTime::Timestamp(timestamp) => journal
+                .iter()
+                .position(|tx| tx.timestamp().is_some_and(|ts| ts > timestamp))
+                .unwrap_or(journal.len()),
+        };
+
+        self.replay(client, &journal[..end], |_, _| ()).map(Some)
+    }
+
+    /// Chronological statement of the account of `client`, listing every transaction
+    /// applied to it along with the balances right after it, obtained by replaying its
+    /// journal as `account_at` does.
+    pub fn statement(&self, client: ClientId) -> Result<Option<Statement>, AccountError> {
+        let Some(account) = self.accounts.get(&client) else {
+            return Ok(None);
+        };
+        let mut entries = Vec::with_capacity(account.journal.len());
+        self.replay(client, &account.journal, |replayed, tx| {
+            // Transactions referring to a stored one are annotated with its amount.
+            let stored = match tx.type_().is_stored() || tx.type_().is_reference() {
+                true => account.transactions.get(tx.tx()),
+                false => None,
+            };
+            entries.push(StatementEntry {
+                type_: *tx.type_(),
+                tx: *tx.tx(),
+                timestamp: *tx.timestamp(),
+                amount: stored.map_or(tx.amount(), Transaction::amount).to_f64(),
+                available: replayed.available.to_f64(),
+                held: replayed.held.to_f64(),
+                total: replayed.total.to_f64(),
+                locked: replayed.locked,
+                dispute: stored.and_then(DisputeStatus::of),
+            });
+        })?;
+        Ok(Some(Statement { client, entries }))
+    }
+
+    /// Graph of the relationships between `client`, the transactions stored by its account
+    /// and the ones referring to them, e.g. disputes, if the account exists. The latter are
+    /// taken from the journal of the account, so without journaling the graph only links the
+    /// client to its stored transactions, along with their dispute state.
+    pub fn graph(&self, client: ClientId) -> Option<TransactionGraph> {
+        let account = self.accounts.get(&client)?;
+        let stored = (account.history().map(|tx| (tx, None))).chain(
+            account
+                .assets()
+                .flat_map(|(asset, ledger)| ledger.history().map(move |tx| (tx, Some(asset)))),
+        );
+        Some(TransactionGraph::new(client, stored, &account.journal))
+    }
+
+    /// Replay `journal` into a new account of `client`, calling `on_applied` with the account
+    /// right after each transaction.
+    fn replay<F: FnMut(&Account<A>, &Transaction<A>)>(
+        &self,
+        client: ClientId,
+        journal: &[Transaction<A>],
+        mut on_applied: F,
+    ) -> Result<Account<A>, AccountError> {
+        let mut account = Account::new(client);
+        for tx in journal {
+            // Interest postings are journaled themselves.
+            if let (Some(policy), Some(timestamp)) = (&self.config.interest, tx.timestamp()) {
+                while account.accrue_interest(*timestamp, policy).is_some() {}
+            }
+            let type_ = *tx.type_();
+            account.apply_transaction_with_config(tx.clone(), &self.config)?;
+            if type_ == TransactionType::Representment && self.config.unfreeze_on_representment {
+                account.unfreeze();
+            }
+            on_applied(&account, tx);
+        }
+        Ok(account)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Summary of the transactions processed so far.
+    pub fn report(&self) -> &ProcessingReport {
+        &self.report
+    }
+
+    /// Sums of the balances of all accounts, along with the locked and negative ones.
+    pub fn funds_summary(&self) -> FundsSummary {
+        let mut summary = FundsSummary::default();
+        for acc in self.iter_sorted() {
+            summary.available += acc.available.to_f64();
+            summary.held += acc.held.to_f64();
+            summary.total += acc.total.to_f64();
+            summary.locked += usize::from(acc.locked);
+            if acc.available < A::ZERO || acc.total < A::ZERO {
+                summary.negative.push(acc.client);
+            }
+        }
+        summary
+    }
+
+    #[cfg(any(feature = "checkpoint", feature = "server", feature = "snapshot"))]
+    pub(crate) fn report_mut(&mut self) -> &mut ProcessingReport {
+        &mut self.report
+    }
+
+    /// Transactions flagged by the risk scorer, in the order they were applied.
+    pub fn risk_flags(&self) -> &[RiskFlag] {
+        &self.risk_flags
+    }
+
+    /// Transactions flagged by anomaly detectors, and withheld, in the order they were
+    /// processed.
+    pub fn anomalies(&self) -> &[Anomaly] {
+        &self.anomalies
+    }
+
+    /// Approximate memory used by the accounts, e.g. to size hosts for large inputs.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        let per_account = self
+            .iter_sorted()
+            .map(Account::footprint)
+            .collect::<Vec<_>>();
+        MemoryFootprint {
+            account_bytes: hash_map_bytes(&self.accounts),
+            transaction_bytes: per_account.iter().map(|acc| acc.bytes).sum(),
+            index_bytes: self.tx_index.as_ref().map_or(0, hash_map_bytes),
+            per_account,
+        }
+    }
+
+    /// Errors of the transactions skipped while processing with `Strictness::Warn`, in the
+    /// order they were applied.
+    pub fn warnings(&self) -> &[AccountError] {
+        &self.warnings
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(strictness = ?strictness)))]
+    pub fn from_transaction_iter<T: Iterator<Item = Result<Transaction<A>, TransactionError>>>(
+        tx_iter: T,
+        strictness: Strictness,
+    ) -> Result<Self, AccountError> {
+        let mut accounts = Self::default();
+        accounts.apply_transaction_iter(tx_iter, strictness)?;
+        Ok(accounts)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(strictness = ?strictness)))]
+    pub fn apply_transaction_iter<T: Iterator<Item = Result<Transaction<A>, TransactionError>>>(
+        &mut self,
+        tx_iter: T,
+        strictness: Strictness,
+    ) -> Result<(), AccountError> {
+        let mut quarantined = Vec::new();
+        for tx in tx_iter {
+            let tx = tx?;
+            let key = self.dedup.as_ref().map(|_| dedup_key(&tx));
+            if let (Some(dedup), Some(key)) = (&self.dedup, &key) {
+                if dedup.contains(key) {
+                    self.report.record_duplicate();
+                    continue;
+                }
+            }
+            let rejected = self.report.rejected;
+            let quarantine = (self.config.quarantine_retries > 0).then_some(&mut quarantined);
+            self.apply(tx, strictness, quarantine)?;
+            // Rejected transactions may apply once re-delivered, e.g. after a deposit.
+            if let (Some(dedup), Some(key)) = (&mut self.dedup, key) {
+                if self.report.rejected == rejected {
+                    dedup.insert(&key)?;
+                }
+            }
+        }
+        // Retry quarantined transactions until none is left or no retry applies any of them,
+        // rejecting the ones left on the last retry.
+        let mut retries = self.config.quarantine_retries;
+        while !quarantined.is_empty() && retries > 0 {
+            retries -= 1;
+            let pending = mem::take(&mut quarantined);
+            let count = pending.len();
+            for tx in pending {
+                self.apply(tx, strictness, (retries > 0).then_some(&mut quarantined))?;
+            }
+            if quarantined.len() == count {
+                retries = retries.min(1);
+            }
+        }
+        #[cfg(feature = "tracing")]
+        tracing::info!(accounts = self.accounts.len(), "processing completed");
+        Ok(())
+    }
+
+    /// Apply `tx`, handling errors as per `strictness`, unless quarantined to be retried.
+    fn apply(
+        &mut self,
+        tx: Transaction<A>,
+        strictness: Strictness,
+        quarantine: Option<&mut Vec<Transaction<A>>>,
+    ) -> Result<(), AccountError> {
+        if let Some(timestamp) = tx.timestamp() {
+            self.tick(*timestamp, strictness)?;
+        }
+        if let Some(effective) = *tx.effective() {
+            if self.clock.is_none_or(|now| effective > now) {
+                self.scheduled.insert((effective, self.scheduled_seq), tx);
+                self.scheduled_seq += 1;
+                return Ok(());
+            }
+        }
+        if !self.anomaly_detectors.is_empty() && tx.type_().is_stored() {
+            let stats = self.client_stats.entry(*tx.client()).or_default();
+            let reason = self
+                .anomaly_detectors
+                .iter()
+                .find_map(|detector| detector.detect(stats, &tx));
+            if let Some(reason) = reason {
+                self.anomalies.push(Anomaly {
+                    type_: *tx.type_(),
+                    client: *tx.client(),
+                    tx: *tx.tx(),
+                    amount: tx.amount().to_f64(),
+                    reason,
+                });
+                self.report.record_withheld();
+                return Ok(());
+            }
+        }
+        // Conversions record the rate they are applied at, e.g. in journals.
+        let tx = match (&self.config.fx, tx.type_()) {
+            (Some(fx), TransactionType::Convert) if tx.rate().is_none() => {
+                match fx.rate(tx.asset().as_deref(), tx.to_asset().as_deref()) {
+                    Some(rate) => tx.with_rate(rate),
+                    None => tx,
+                }
+            }
+            _ => tx,
+        };
+        if let Some(timestamp) = tx.timestamp() {
+            self.post_interest(*tx.client(), *timestamp, strictness)?;
+        }
+        let owner = self.tx_id_owner(&tx);
+        let account = self
+            .accounts
+            .entry(*tx.client())
+            .or_insert(Account::new(*tx.client()));
+        // Deposits partially applied under the balance cap record the amount left unapplied.
+        let (tx, excess) = match account.capped_amount(&tx, &self.config) {
+            Ok(Some(capped)) if tx.asset().is_none() => {
+                let excess = tx.amount() - capped;
+                (tx.with_amount(capped), Some(excess))
+            }
+            _ => (tx, None),
+        };
+        let (client, tx_id, type_, amount) = (*tx.client(), *tx.tx(), *tx.type_(), tx.amount());
+        let (timestamp, posted) = (*tx.timestamp(), *tx.posted());
+        let retained = (!self.observers.is_empty()
+            || self.error_handler.is_some()
+            || self.config.journal
+            || quarantine.is_some())
+        .then(|| tx.clone());
+        let was_locked = account.locked;
+        let rule = self.config.rules.evaluate(account, &tx);
+        let mut flag = rule
+            .filter(|rule| rule.action == RuleAction::Flag)
+            .map(|rule| RiskFlag {
+                client,
+                tx: tx_id,
+                annotation: format!("rule: {}", rule.name),
+            });
+        let applied = if type_ == TransactionType::Interest && !posted {
+            Err(AccountError::InterestNotAccrued(client, tx_id))
+        } else if let (Some(owner), true) = (owner, type_.is_stored()) {
+            Err(AccountError::TransactionIdReused(client, tx_id, owner))
+        } else if let Some(owner) = owner.filter(|owner| *owner != client) {
+            Err(AccountError::ClientMismatch(client, tx_id, owner))
+        } else if let Some(rule) = rule.filter(|rule| rule.action != RuleAction::Flag) {
+            if rule.action == RuleAction::Freeze && !account.locked {
+                account.freeze();
+                for observer in &self.observers {
+                    observer.on_frozen(account);
+                }
+            }
+            Err(AccountError::RuleViolated(client, tx_id, rule.name.clone()))
+        } else {
+            match self
+                .risk_scorer
+                .as_ref()
+                .map_or(RiskDecision::Allow, |scorer| scorer.score(account, &tx))
+            {
+                RiskDecision::Veto(reason) => Err(AccountError::Vetoed(client, tx_id, reason)),
+                decision => {
+                    if let RiskDecision::Flag(annotation) = decision {
+                        flag = Some(RiskFlag {
+                            client,
+                            tx: tx_id,
+                            annotation,
+                        });
+                    }
+                    account.apply_transaction_with_config(tx, &self.config)
+                }
+            }
+        };
+        match applied {
+            Ok(()) => {
+                self.report.record_applied(type_, amount.to_f64());
+                if let Some(excess) = excess {
+                    self.report.record_capped(excess.to_f64());
+                }
+                if let (Some(index), true) = (&mut self.tx_index, type_.is_stored()) {
+                    index.insert(tx_id, client);
+                }
+                if type_ == TransactionType::Representment && self.config.unfreeze_on_representment
+                {
+                    account.unfreeze();
+                }
+                self.risk_flags.extend(flag);
+                if !self.anomaly_detectors.is_empty() && type_.is_stored() {
+                    let stats = self.client_stats.entry(client).or_default();
+                    stats.record(timestamp, amount.to_f64());
+                }
+                if let Some(tx) = retained {
+                    for observer in &self.observers {
+                        observer.on_applied(account, &tx);
+                        if !was_locked && account.locked {
+                            observer.on_frozen(account);
+                        }
+                    }
+                    if self.config.journal {
+                        account.journal.push(tx);
+                    }
+                }
+                if let (Some(timeout), TransactionType::Dispute, Some(timestamp)) =
+                    (&self.config.dispute_timeout, type_, timestamp)
+                {
+                    self.dispute_deadlines.insert((
+                        timestamp.saturating_add(timeout.after),
+                        client,
+                        tx_id,
+                    ));
+                }
+                let decision = match (&self.dispute_arbiter, type_) {
+                    (Some(arbiter), TransactionType::Dispute) => account
+                        .stored(tx_id)
+                        .map_or(DisputeDecision::Hold, |disputed| {
+                            arbiter.arbitrate(account, disputed)
+                        }),
+                    _ => DisputeDecision::Hold,
+                };
+                let outcome = match decision {
+                    DisputeDecision::Hold => return Ok(()),
+                    DisputeDecision::Resolve => TransactionType::Resolve,
+                    DisputeDecision::Chargeback => TransactionType::Chargeback,
+                };
+                let mut outcome = Transaction::new(outcome, client, tx_id, None, false);
+                if let Some(timestamp) = timestamp {
+                    outcome = outcome.with_timestamp(timestamp);
+                }
+                self.apply(outcome, strictness, None)
+            }
+            Err(e) => {
+                if let (Some(quarantine), Some(tx)) = (quarantine, &retained) {
+                    if e.is_out_of_order() {
+                        quarantine.push(tx.clone());
+                        return Ok(());
+                    }
+                }
+                if let Some(tx) = &retained {
+                    for observer in &self.observers {
+                        observer.on_rejected(tx, &e);
+                    }
+                }
+                let decision = match (&mut self.error_handler, &retained) {
+                    (Some(handler), Some(tx)) if e.is_recoverable() => {
+                        handler.on_error(&RejectedTx {
+                            tx,
+                            error: &e,
+                            report: &self.report,
+                        })
+                    }
+                    _ if e.is_recoverable() && strictness != Strictness::Strict => {
+                        ErrorDecision::Continue
+                    }
+                    _ => ErrorDecision::Abort,
+                };
+                if decision == ErrorDecision::Continue {
+                    self.report.record_rejected();
+                    if strictness == Strictness::Warn {
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!(client, tx = tx_id, error = %e, "transaction skipped");
+                        self.warnings.push(e);
+                    } else {
+                        #[cfg(feature = "tracing")]
+                        tracing::info!(client, tx = tx_id, error = %e, "transaction skipped");
+                    }
+                    return Ok(());
+                }
+                #[cfg(feature = "tracing")]
+                tracing::error!(client, tx = tx_id, error = %e, "processing aborted");
+                Err(e)
+            }
+        }
+    }
+
+    /// Transactions scheduled to take effect later, in the order they will be applied.
+    pub fn scheduled(&self) -> impl Iterator<Item = &Transaction<A>> {
+        self.scheduled.values()
+    }
+
+    /// Advance processing to `now`, applying the transactions scheduled to take effect by
+    /// then, including the occurrences of recurrences, timestamped at their effective date,
+    /// and settling stale disputes, handling errors as per `strictness`. Applying a
+    /// timestamped transaction ticks as of its timestamp beforehand.
+    pub fn tick(&mut self, now: Timestamp, strictness: Strictness) -> Result<(), AccountError> {
+        self.clock = self.clock.max(Some(now));
+        for (recurrence, next) in &mut self.recurrences {
+            while let Some((at, tx_id)) = recurrence.occurrence(*next).filter(|(at, _)| *at <= now)
+            {
+                let amount = Some(A::from_f64(recurrence.amount));
+                let tx =
+                    Transaction::new(recurrence.type_, recurrence.client, tx_id, amount, false);
+                self.scheduled
+                    .insert((at, self.scheduled_seq), tx.with_effective(at));
+                self.scheduled_seq += 1;
+                *next += 1;
+            }
+        }
+        while let Some(entry) = self.scheduled.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+            let ((effective, _), tx) = entry.remove_entry();
+            self.apply(tx.with_timestamp(effective), strictness, None)?;
+        }
+        self.expire_disputes(now, strictness)
+    }
+
+    /// Apply all the scheduled transactions, ticking up to the latest effective date.
+    pub fn flush(&mut self, strictness: Strictness) -> Result<(), AccountError> {
+        match self.scheduled.last_key_value() {
+            Some(((last, _), _)) => self.tick(*last, strictness),
+            None => Ok(()),
+        }
+    }
+
+    /// Settle the disputes which were open for longer than the configured `DisputeTimeout`
+    /// as of `now`, applying a resolve or chargeback timestamped at their deadline, handling
+    /// errors as per `strictness`. Only disputes opened with a timestamp are settled.
+    pub fn expire_disputes(
+        &mut self,
+        now: Timestamp,
+        strictness: Strictness,
+    ) -> Result<(), AccountError> {
+        let Some(timeout) = self.config.dispute_timeout else {
+            return Ok(());
+        };
+        let type_ = match timeout.action {
+            TimeoutAction::Resolve => TransactionType::Resolve,
+            TimeoutAction::Chargeback => TransactionType::Chargeback,
+        };
+        while let Some(&(deadline, client, tx_id)) = self.dispute_deadlines.first() {
+            if deadline > now {
+                break;
+            }
+            self.dispute_deadlines.pop_first();
+            // Skip disputes settled in the meantime, or disputed once more since.
+            let open = self
+                .accounts
+                .get(&client)
+                .and_then(|account| account.stored(tx_id))
+                .and_then(|disputed| *disputed.disputed_at())
+                .is_some_and(|at| at.saturating_add(timeout.after) == deadline);
+            if open {
+                let outcome =
+                    Transaction::new(type_, client, tx_id, None, false).with_timestamp(deadline);
+                self.apply(outcome, strictness, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Client of the stored transaction with the id of `tx`, if any, when indexing stored
+    /// transactions across clients and `tx` is to be stored or refers to a stored one.
+    fn tx_id_owner(&mut self, tx: &Transaction<A>) -> Option<ClientId> {
+        if !self.config.unique_tx_ids || !(tx.type_().is_stored() || tx.type_().is_reference()) {
+            return None;
+        }
+        let accounts = &self.accounts;
+        let index = self.tx_index.get_or_insert_with(|| {
+            accounts
+                .values()
+                .flat_map(|acc| acc.history().map(|tx| (*tx.tx(), *tx.client())))
+                .collect()
+        });
+        index.get(tx.tx()).copied()
+    }
+
+    /// Drop the index of stored transactions, to be rebuilt on next use, e.g. after replacing
+    /// accounts.
+    #[cfg(any(feature = "checkpoint", feature = "server"))]
+    pub(crate) fn reset_tx_index(&mut self) {
+        self.tx_index = None;
+    }
+
+    /// Accrue interest on all accounts up to `now`, e.g. at the end of a processing period,
+    /// handling errors applying interest postings as per `strictness`.
+    pub fn accrue_interest(
+        &mut self,
+        now: Timestamp,
+        strictness: Strictness,
+    ) -> Result<(), AccountError> {
+        if self.config.interest.is_some() {
+            for client in self.accounts.keys().copied().collect::<Vec<_>>() {
+                self.post_interest(client, now, strictness)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Accrue interest on the account of `client` up to `now`, if configured, applying the
+    /// interest posted in the meantime as `interest` transactions.
+    fn post_interest(
+        &mut self,
+        client: ClientId,
+        now: Timestamp,
+        strictness: Strictness,
+    ) -> Result<(), AccountError> {
+        while let Some(policy) = &self.config.interest {
+            let account = self.accounts.entry(client).or_insert(Account::new(client));
+            let Some((at, amount)) = account.accrue_interest(now, policy) else {
+                break;
+            };
+            self.apply(Transaction::interest(client, at, amount), strictness, None)?;
+        }
+        Ok(())
+    }
+
+    /// Write the transaction history of each account to a file named after the client in
+    /// `dir`, e.g. `1.csv`.
+    #[cfg(any(feature = "csv", feature = "json"))]
+    pub fn export_history<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        format: HistoryFormat,
+    ) -> Result<(), AccountError> {
+        fs::create_dir_all(&dir)?;
+        for acc in self.accounts.values() {
+            let path = dir
+                .as_ref()
+                .join(format!("{}.{}", acc.client, format.extension()));
+            match format {
+                #[cfg(feature = "csv")]
+                HistoryFormat::Csv => {
+                    let mut wrt = csv::Writer::from_path(path)?;
+                    for tx in acc.history() {
+                        wrt.serialize(tx)?;
+                    }
+                    wrt.flush()?;
+                }
+                #[cfg(feature = "json")]
+                HistoryFormat::Json => {
+                    let wrt = BufWriter::new(File::create(path)?);
+                    serde_json::to_writer_pretty(wrt, &acc.history().collect::<Vec<_>>())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write accounts to stdout as csv, ordered by client id.
+    #[cfg(feature = "csv")]
+    pub fn to_csv(&self) -> Result<(), AccountError> {
+        self.to_csv_writer(stdout())
+    }
+
+    /// Write accounts to `wrt` as csv, ordered by client id.
+    #[cfg(feature = "csv")]
+    pub fn to_csv_writer<W: Write>(&self, wrt: W) -> Result<(), AccountError> {
+        self.write_csv(csv::Writer::from_writer(wrt))
+    }
+
+    /// Write accounts as csv to the file at `path`, ordered by client id. When appending to
+    /// a non-empty file, the header is omitted.
+    #[cfg(feature = "csv")]
+    pub fn to_csv_file<P: AsRef<Path>>(&self, path: P, append: bool) -> Result<(), AccountError> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?;
+        let has_headers = !append || file.metadata()?.len() == 0;
+        self.write_csv(
+            csv::WriterBuilder::new()
+                .has_headers(has_headers)
+                .from_writer(file),
+        )
+    }
+
+    #[cfg(feature = "csv")]
+    fn write_csv<W: Write>(&self, mut wrt: csv::Writer<W>) -> Result<(), AccountError> {
+        if self.values().any(|acc| !acc.assets.is_empty()) {
+            for acc in self.iter_sorted() {
+                // Skip the default asset of accounts only holding other ones.
+                let default =
+                    (!acc.history.is_empty() || acc.total != A::ZERO || acc.assets.is_empty())
+                        .then_some(("", acc));
+                for (asset, ledger) in default.into_iter().chain(acc.assets()) {
+                    wrt.serialize(AccountAssetRecord {
+                        client: acc.client,
+                        asset,
+                        available: ledger.available,
+                        held: ledger.held,
+                        total: ledger.total,
+                        locked: acc.locked,
+                    })?;
+                }
+            }
+            wrt.flush()?;
+            return Ok(());
+        }
+        for acc in self.iter_sorted() {
+            if self.config.fees.is_some() {
+                wrt.serialize(AccountWithFees::from(acc))?;
+            } else {
+                wrt.serialize(acc)?;
+            }
+        }
+        wrt.flush()?;
+
+        Ok(())
+    }
+}
+
+impl Accounts {
+    pub fn from_transactions(
+        transactions: Transactions,
+        strictness: Strictness,
+    ) -> Result<Self, AccountError> {
+        Self::from_transaction_iter(transactions.0.into_iter().map(Ok), strictness)
+    }
+
+    /// Parse accounts from the csv output at `path`, with their balances and locked status
+    /// but without any transactions, so that disputes can't refer to earlier transactions.
+    #[cfg(feature = "csv")]
+    pub fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, AccountError> {
+        Ok(Self::from_records(AccountRecord::from_csv(path)?))
+    }
+
+    /// Parse accounts as `from_csv` does, from csv output read from `rdr`.
+    #[cfg(feature = "csv")]
+    pub fn from_csv_reader<R: Read>(rdr: R) -> Result<Self, AccountError> {
+        Ok(Self::from_records(AccountRecord::from_csv_reader(rdr)?))
+    }
+
+    /// Accounts with the balances of `records`, the last record of a client taking precedence.
+    #[cfg(feature = "csv")]
+    fn from_records(records: Vec<AccountRecord>) -> Self {
+        let mut accounts = Self::default();
+        for record in records {
+            accounts.insert(record.client, Account::from(record));
+        }
+        accounts
+    }
+}
+
+/// Complete state of `Accounts`, as (de)serialized through serde.
+#[derive(Deserialize, Serialize)]
+struct AccountsState {
+    report: ProcessingReport,
+    /// Accounts, ordered by client id.
+    accounts: Vec<AccountState>,
+}
+
+/// Accounts serialize to their complete state, including their stored transactions along with
+/// their dispute states, and the processing report, e.g. to inspect it as json and re-import
+/// it to continue processing. Configuration and hooks aren't serialized: to continue
+/// processing with them, merge deserialized accounts into configured ones with
+/// `Accounts::merge`.
+impl Serialize for Accounts {
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        AccountsState {
+            report: self.report.clone(),
+            accounts: self.iter_sorted().map(AccountState::from).collect(),
+        }
+        .serialize(ser)
+    }
+}
+
+impl<'de> Deserialize<'de> for Accounts {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        let state = AccountsState::deserialize(de)?;
+        let mut accounts = Self {
+            report: state.report,
+            ..Self::default()
+        };
+        accounts.extend(state.accounts.into_iter().map(|state| {
+            let acc = Account::from(state);
+            (acc.client, acc)
+        }));
+        Ok(accounts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        mem::size_of,
+        sync::{Arc, Mutex},
+    };
+
+    use super::{
+        Account, AccountError, Accounts, Config, MergeError, PointInTime, Timestamp, Transaction,
+        TransactionError, TransactionId, TransactionMap, TransactionType,
+    };
+    #[cfg(feature = "csv")]
+    use super::{AccountWithFees, HistoryFormat, Transactions};
+    #[cfg(feature = "csv")]
+    use crate::report::ProcessingReport;
+    use crate::{
+        amount::{Amount, MinorUnits},
+        arbitration::DisputeDecision,
+        fee::{Fee, FeeSchedule},
+        interest::{InterestPolicy, SECONDS_PER_DAY},
+        observer::Observer,
+        policy::{
+            BalanceCap, BalancePolicy, CapAction, DisputeTimeout, FreezePolicy, PrecisionPolicy,
+            Strictness, TimeoutAction, VelocityLimit,
+        },
+        rejection::{ErrorDecision, RejectedTx},
+        risk::{RiskDecision, RiskFlag},
+        validate::Violation,
+    };
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn serialize_accounts() {
+        let accounts = vec![
+            Account {
                 client: 1,
                 transactions: TransactionMap::default(),
                 available: 1.5,
                 held: 0.0,
                 total: 1.5,
                 locked: false,
+                ..Account::default()
             },
             Account {
                 client: 2,
@@ -271,6 +2440,7 @@ mod tests {
                 held: 0.0,
                 total: 2.0,
                 locked: false,
+                ..Account::default()
             },
         ];
 
@@ -288,6 +2458,66 @@ client,available,held,total,locked
         assert_eq!(accounts, accounts_expected);
     }
 
+    #[cfg(feature = "csv")]
+    #[test]
+    fn hold_multiple_assets() {
+        let deposit = |tx, amount, asset: Option<&str>| {
+            let deposit = Transaction::new(TransactionType::Deposit, 1, tx, Some(amount), false);
+            match asset {
+                Some(asset) => deposit.with_asset(asset),
+                None => deposit,
+            }
+        };
+        let mut accounts = Accounts::default();
+        accounts
+            .apply_transaction_iter(
+                [
+                    deposit(1, 10.0, None),
+                    deposit(2, 5.0, Some("EUR")),
+                    deposit(3, 0.5, Some("BTC")),
+                    // Refers to the deposit in EUR.
+                    Transaction::new(TransactionType::Dispute, 1, 2, None, false),
+                    Transaction::new(TransactionType::Withdrawal, 1, 4, Some(1.0), false)
+                        .with_asset("EUR"),
+                    deposit(3, 1.0, None),
+                    Transaction::new(TransactionType::Withdrawal, 2, 5, Some(1.0), false)
+                        .with_asset("EUR"),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Warn,
+            )
+            .unwrap();
+        assert!(matches!(
+            accounts.warnings(),
+            [
+                AccountError::Withdrawal(1, 4),
+                AccountError::DuplicateTransaction(1, 3),
+                AccountError::Withdrawal(2, 5),
+            ]
+        ));
+
+        let account = accounts.get(1).unwrap();
+        assert_eq!(*account.total(), 10.0);
+        let eur = account.asset("EUR").unwrap();
+        assert_eq!((*eur.available(), *eur.held()), (0.0, 5.0));
+        assert!(accounts.get(2).unwrap().asset("EUR").is_none());
+
+        let mut csv = vec![];
+        accounts.to_csv_writer(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "\
+client,asset,available,held,total,locked
+1,,10.0,0.0,10.0,false
+1,BTC,0.5,0.0,0.5,false
+1,EUR,0.0,5.0,5.0,false
+2,,0.0,0.0,0.0,false
+"
+        );
+    }
+
+    #[cfg(feature = "csv")]
     #[test]
     fn serialize_long_floats() {
         let account = Account {
@@ -297,6 +2527,7 @@ client,available,held,total,locked
             held: 0.0,
             total: 1.11223344,
             locked: false,
+            ..Account::default()
         };
 
         let mut wrt = csv::Writer::from_writer(vec![]);
@@ -309,25 +2540,45 @@ client,available,held,total,locked
     }
 
     #[test]
-    #[should_panic(expected = "applied transaction on client 1 to account 0")]
     fn apply_transaction_to_wrong_account() {
-        // If a transaction on client x is applied to account y, then it is an implementation issue
-        // and it should panic.
-        Account::default()
-            .apply_transaction(Transaction::new(
-                TransactionType::Deposit,
-                1,
-                1,
-                Some(1.0),
-                false,
-            ))
-            .unwrap();
+        // If a transaction on client x is applied to account y, then it is an implementation issue,
+        // reported as an error rather than aborting the process.
+        let deposit = Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false);
+        let mut account = Account::default();
+        assert!(matches!(
+            account.apply_transaction(deposit).unwrap_err(),
+            AccountError::WrongAccount(0, 1, 1)
+        ));
+        assert_eq!(account, Account::default());
     }
 
     #[test]
-    fn apply_deposit() {
-        let deposit_amount = 1.0;
-        let deposit = Transaction::new(TransactionType::Deposit, 1, 1, Some(deposit_amount), false);
+    fn reject_duplicate_transaction() {
+        let mut account = Account::new(1);
+        let deposit = Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false);
+        account.apply_transaction(deposit.clone()).unwrap();
+        assert!(matches!(
+            account.apply_transaction(deposit).unwrap_err(),
+            AccountError::DuplicateTransaction(1, 1)
+        ));
+        assert_eq!(*account.total(), 1.0);
+    }
+
+    #[test]
+    fn report_error_codes() {
+        let mut account = Account::new(1);
+        let withdrawal = Transaction::new(TransactionType::Withdrawal, 1, 1, Some(1.0), false);
+        let err = account.apply_transaction(withdrawal).unwrap_err();
+        assert_eq!((err.kind(), err.code()), ("withdrawal", 200));
+        let invalid = Transaction::new(TransactionType::Deposit, 1, 2, Some(-1.0), false);
+        let err = account.apply_transaction(invalid).unwrap_err();
+        assert_eq!((err.kind(), err.code()), ("invalid_amount", 320));
+    }
+
+    #[test]
+    fn apply_deposit() {
+        let deposit_amount = 1.0;
+        let deposit = Transaction::new(TransactionType::Deposit, 1, 1, Some(deposit_amount), false);
         let mut account = Account::new(1);
         account.apply_transaction(deposit.clone()).unwrap();
         let mut transactions = TransactionMap::new();
@@ -337,6 +2588,7 @@ client,available,held,total,locked
             Account {
                 client: 1,
                 transactions,
+                history: vec![1],
                 available: deposit_amount,
                 total: deposit_amount,
                 ..Account::default()
@@ -369,6 +2621,7 @@ client,available,held,total,locked
             Account {
                 client: 1,
                 transactions,
+                history: vec![1],
                 ..Account::default()
             }
         );
@@ -388,6 +2641,268 @@ client,available,held,total,locked
         ));
     }
 
+    #[test]
+    fn reject_overflow() {
+        fn deposit<A: Amount>(tx: TransactionId, amount: A) -> Transaction<A> {
+            Transaction::new(TransactionType::Deposit, 1, tx, Some(amount), false)
+        }
+        let mut account = Account::new(1);
+        account.apply_transaction(deposit(1, 9e11)).unwrap();
+        assert!(matches!(
+            account.apply_transaction(deposit(2, 9e11)).unwrap_err(),
+            AccountError::Overflow(1, 2)
+        ));
+        assert_eq!((*account.available(), *account.total()), (9e11, 9e11));
+
+        let mut account = Account::new(1);
+        account
+            .apply_transaction(deposit(1, MinorUnits(i64::MAX)))
+            .unwrap();
+        assert!(matches!(
+            account
+                .apply_transaction(deposit(2, MinorUnits(1)))
+                .unwrap_err(),
+            AccountError::Overflow(1, 2)
+        ));
+        assert_eq!(*account.total(), MinorUnits(i64::MAX));
+    }
+
+    #[test]
+    fn reject_reused_tx_ids() {
+        let txs = || {
+            [
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+                Transaction::new(TransactionType::Deposit, 2, 1, Some(2.0), false),
+                Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+            ]
+            .into_iter()
+            .map(Ok)
+        };
+        let config = Config {
+            unique_tx_ids: true,
+            ..Config::default()
+        };
+        let mut accounts = Accounts::with_config(config.clone());
+        accounts
+            .apply_transaction_iter(txs(), Strictness::Lenient)
+            .unwrap();
+        assert_eq!(*accounts.get(2).unwrap().total(), 0.0);
+        assert_eq!(*accounts.get(1).unwrap().held(), 1.0);
+        assert_eq!(accounts.report().rejected, 1);
+        assert!(matches!(
+            Accounts::with_config(config)
+                .apply_transaction_iter(txs(), Strictness::Strict)
+                .unwrap_err(),
+            AccountError::TransactionIdReused(2, 1, 1)
+        ));
+        // Ids are only unique per client by default.
+        let accounts = Accounts::from_transaction_iter(txs(), Strictness::Strict).unwrap();
+        assert_eq!(*accounts.get(2).unwrap().total(), 2.0);
+    }
+
+    #[test]
+    fn reject_cross_client_references() {
+        let txs = || {
+            [
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+                Transaction::new(TransactionType::Dispute, 2, 1, None, false),
+            ]
+            .into_iter()
+            .map(Ok)
+        };
+        let config = Config {
+            unique_tx_ids: true,
+            ..Config::default()
+        };
+        assert!(matches!(
+            Accounts::with_config(config)
+                .apply_transaction_iter(txs(), Strictness::Strict)
+                .unwrap_err(),
+            AccountError::ClientMismatch(2, 1, 1)
+        ));
+        assert!(matches!(
+            Accounts::from_transaction_iter(txs(), Strictness::Strict),
+            Err(AccountError::Dispute(2, 1))
+        ));
+    }
+
+    #[test]
+    fn collect_warnings() {
+        let txs = || {
+            [
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+                Transaction::new(TransactionType::Withdrawal, 1, 2, Some(2.0), false),
+                Transaction::new(TransactionType::Withdrawal, 1, 3, Some(1.0), false),
+            ]
+            .into_iter()
+            .map(Ok)
+        };
+        let lenient = Accounts::from_transaction_iter(txs(), Strictness::Lenient).unwrap();
+        assert!(lenient.warnings().is_empty());
+        let warned = Accounts::from_transaction_iter(txs(), Strictness::Warn).unwrap();
+        assert!(matches!(
+            warned.warnings(),
+            [AccountError::Withdrawal(1, 2)]
+        ));
+        assert_eq!(*warned.get(1).unwrap().total(), 0.0);
+        assert_eq!(warned.report().rejected, 1);
+        assert!(matches!(
+            Accounts::from_transaction_iter(txs(), Strictness::Strict),
+            Err(AccountError::Withdrawal(1, 2))
+        ));
+    }
+
+    #[test]
+    fn handle_errors() {
+        let txs = || {
+            [
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+                Transaction::new(TransactionType::Withdrawal, 1, 2, Some(2.0), false),
+                Transaction::new(TransactionType::Withdrawal, 1, 3, Some(2.0), false),
+                Transaction::new(TransactionType::Withdrawal, 1, 4, Some(1.0), false),
+            ]
+            .into_iter()
+            .map(Ok)
+        };
+        // Tolerate a single rejection, even when processing strictly.
+        let tolerate_one = |rejected: &RejectedTx| {
+            if rejected.report.rejected < 1 {
+                ErrorDecision::Continue
+            } else {
+                ErrorDecision::Abort
+            }
+        };
+        let mut accounts = Accounts::default().with_error_handler(tolerate_one);
+        assert!(matches!(
+            accounts.apply_transaction_iter(txs().take(3), Strictness::Strict),
+            Err(AccountError::Withdrawal(1, 3))
+        ));
+        assert_eq!(accounts.report().rejected, 1);
+        let mut accounts = Accounts::default().with_error_handler(|rejected: &RejectedTx| {
+            assert!(matches!(rejected.error, AccountError::Withdrawal(1, _)));
+            ErrorDecision::Continue
+        });
+        accounts
+            .apply_transaction_iter(txs(), Strictness::Strict)
+            .unwrap();
+        assert_eq!(*accounts.get(1).unwrap().total(), 0.0);
+        assert_eq!(accounts.report().rejected, 2);
+    }
+
+    #[test]
+    fn retry_quarantined_transactions() {
+        let txs = || {
+            [
+                Transaction::new(TransactionType::Resolve, 1, 1, None, false),
+                Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+            ]
+            .into_iter()
+            .map(Ok)
+        };
+        let config = Config {
+            quarantine_retries: 2,
+            ..Config::default()
+        };
+        let mut accounts = Accounts::with_config(config.clone());
+        accounts
+            .apply_transaction_iter(txs(), Strictness::Strict)
+            .unwrap();
+        let account = accounts.get(1).unwrap();
+        assert_eq!((*account.available(), *account.held()), (1.0, 0.0));
+        assert_eq!(accounts.report().resolves, 1);
+        let unknown = Transaction::new(TransactionType::Dispute, 1, 2, None, false);
+        assert!(matches!(
+            Accounts::with_config(config)
+                .apply_transaction_iter(txs().chain([Ok(unknown)]), Strictness::Strict),
+            Err(AccountError::Dispute(1, 2))
+        ));
+        let accounts = Accounts::from_transaction_iter(txs(), Strictness::Lenient).unwrap();
+        assert_eq!(accounts.report().rejected, 2);
+    }
+
+    #[test]
+    fn report_memory_footprint() {
+        let mut accounts = Accounts::with_config(Config {
+            journal: true,
+            ..Config::default()
+        });
+        let empty = accounts.memory_footprint();
+        assert_eq!(empty.total_bytes(), 0);
+        accounts
+            .apply_transaction_iter(
+                [
+                    Transaction::new(TransactionType::Deposit, 2, 1, Some(1.0), false),
+                    Transaction::new(TransactionType::Deposit, 1, 2, Some(1.0), false),
+                    Transaction::new(TransactionType::Dispute, 1, 2, None, false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+        let footprint = accounts.memory_footprint();
+        let counts = footprint
+            .per_account
+            .iter()
+            .map(|acc| (acc.client, acc.transactions, acc.journaled))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [(1, 1, 2), (2, 1, 1)]);
+        assert!(footprint.account_bytes > 0);
+        assert!(footprint.transaction_bytes >= 3 * size_of::<Transaction>());
+        assert_eq!(footprint.index_bytes, 0);
+    }
+
+    #[test]
+    fn reject_negative_amount() {
+        let deposit = Transaction::new(TransactionType::Deposit, 1, 1, Some(-1.0), false);
+        let mut account = Account::new(1);
+        assert!(matches!(
+            account.apply_transaction(deposit.clone()).unwrap_err(),
+            AccountError::Transaction(TransactionError::InvalidAmount(1, 1, -1.0))
+        ));
+        assert_eq!(*account.total(), 0.0);
+
+        let accounts = Accounts::from_transaction_iter(
+            [
+                deposit,
+                Transaction::new(TransactionType::Deposit, 1, 2, Some(1.0), false),
+            ]
+            .into_iter()
+            .map(Ok),
+            Strictness::Lenient,
+        )
+        .unwrap();
+        assert_eq!(*accounts.get(1).unwrap().total(), 1.0);
+        assert_eq!(accounts.report().rejected, 1);
+    }
+
+    #[test]
+    fn apply_precision_policy() {
+        let deposit = || Transaction::new(TransactionType::Deposit, 1, 1, Some(1.00016), false);
+        let apply = |precision| {
+            let mut account = Account::new(1);
+            let config = Config {
+                precision,
+                ..Config::default()
+            };
+            account
+                .apply_transaction_with_config(deposit(), &config)
+                .map(|()| *account.total())
+        };
+        assert_eq!(apply(PrecisionPolicy::Keep).unwrap(), 1.00016);
+        assert_eq!(apply(PrecisionPolicy::Truncate).unwrap(), 1.0001);
+        assert_eq!(apply(PrecisionPolicy::Round).unwrap(), 1.0002);
+        assert!(matches!(
+            apply(PrecisionPolicy::Reject),
+            Err(AccountError::Transaction(
+                TransactionError::ExcessPrecision(1, 1, _)
+            ))
+        ));
+        // Amounts with up to four decimal places are never adjusted.
+        assert_eq!(PrecisionPolicy::Reject.apply(0.3), Some(0.3));
+    }
+
     #[test]
     fn apply_dispute() {
         let available = 9.0;
@@ -400,6 +2915,7 @@ client,available,held,total,locked
             held,
             total,
             locked: false,
+            ..Account::default()
         };
 
         let tx_amount = 1.0;
@@ -481,6 +2997,7 @@ client,available,held,total,locked
             held,
             total,
             locked: false,
+            ..Account::default()
         };
 
         account
@@ -572,6 +3089,7 @@ client,available,held,total,locked
             held,
             total,
             locked: false,
+            ..Account::default()
         };
 
         account
@@ -644,4 +3162,1643 @@ client,available,held,total,locked
             AccountError::ResolveUndisputed(1, 3)
         ));
     }
+
+    #[test]
+    fn apply_representment() {
+        let available = 8.0;
+        let held = 0.0;
+        let total = available + held;
+
+        let tx_amount = 1.0;
+        let mut deposit = Transaction::new(TransactionType::Deposit, 1, 1, Some(tx_amount), false);
+        deposit.charge_back();
+        let mut withdrawal =
+            Transaction::new(TransactionType::Withdrawal, 1, 2, Some(tx_amount), false);
+        withdrawal.charge_back();
+        let mut transactions = TransactionMap::new();
+        transactions.insert(*deposit.tx(), deposit);
+        transactions.insert(*withdrawal.tx(), withdrawal);
+
+        let mut account = Account {
+            client: 1,
+            transactions,
+            available,
+            held,
+            total,
+            locked: true,
+            ..Account::default()
+        };
+
+        account
+            .apply_transaction(Transaction::new(
+                TransactionType::Representment,
+                1,
+                1,
+                None,
+                false,
+            ))
+            .unwrap();
+
+        assert_eq!(account.available, available + tx_amount);
+        assert_eq!(account.held, held);
+        assert_eq!(account.total, total + tx_amount);
+        assert!(!*account.transactions.get(&1).unwrap().charged_back());
+        // Unlocking is left to `Accounts`
+        assert!(account.locked);
+
+        account
+            .apply_transaction(Transaction::new(
+                TransactionType::Representment,
+                1,
+                2,
+                None,
+                false,
+            ))
+            .unwrap();
+
+        assert_eq!(account.available, available);
+        assert_eq!(account.total, total);
+        assert!(!*account.transactions.get(&2).unwrap().charged_back());
+
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(
+                    TransactionType::Representment,
+                    1,
+                    2,
+                    None,
+                    false,
+                ))
+                .unwrap_err(),
+            AccountError::RepresentmentNotChargedBack(1, 2)
+        ));
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(
+                    TransactionType::Representment,
+                    1,
+                    3,
+                    None,
+                    false,
+                ))
+                .unwrap_err(),
+            AccountError::Representment(1, 3)
+        ));
+    }
+
+    #[test]
+    fn unfreeze_on_representment() {
+        let transactions = vec![
+            Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+            Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+            Transaction::new(TransactionType::Chargeback, 1, 1, None, false),
+            Transaction::new(TransactionType::Representment, 1, 1, None, false),
+        ];
+
+        let accounts = Accounts::from_transaction_iter(
+            transactions.clone().into_iter().map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap();
+        assert!(accounts.get(1).unwrap().locked);
+
+        let mut accounts = Accounts::default().unfreeze_on_representment(true);
+        accounts
+            .apply_transaction_iter(transactions.into_iter().map(Ok), Strictness::Strict)
+            .unwrap();
+        let account = accounts.get(1).unwrap();
+        assert!(!account.locked);
+        assert_eq!(account.available, 1.0);
+        assert_eq!(account.total, 1.0);
+    }
+
+    #[test]
+    fn apply_unlock() {
+        let mut account: Account = Account {
+            client: 1,
+            locked: true,
+            ..Account::default()
+        };
+
+        account
+            .apply_transaction(Transaction::new(TransactionType::Unlock, 1, 1, None, false))
+            .unwrap();
+
+        assert_eq!(
+            account,
+            Account {
+                client: 1,
+                ..Account::default()
+            }
+        );
+    }
+
+    #[test]
+    fn apply_refund() {
+        let available = 8.0;
+        let total = available;
+
+        let tx_amount = 1.0;
+        let deposit = Transaction::new(TransactionType::Deposit, 1, 1, Some(tx_amount), false);
+        let withdrawal =
+            Transaction::new(TransactionType::Withdrawal, 1, 2, Some(tx_amount), false);
+        let mut transactions = TransactionMap::new();
+        transactions.insert(*deposit.tx(), deposit);
+        transactions.insert(*withdrawal.tx(), withdrawal);
+
+        let mut account = Account {
+            client: 1,
+            transactions,
+            available,
+            total,
+            ..Account::default()
+        };
+
+        account
+            .apply_transaction(Transaction::new(TransactionType::Refund, 1, 2, None, false))
+            .unwrap();
+
+        assert_eq!(account.available, available + tx_amount);
+        assert_eq!(account.total, total + tx_amount);
+        assert!(*account.transactions.get(&2).unwrap().refunded());
+
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(TransactionType::Refund, 1, 2, None, false))
+                .unwrap_err(),
+            AccountError::RefundRepeated(1, 2)
+        ));
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(TransactionType::Refund, 1, 1, None, false))
+                .unwrap_err(),
+            AccountError::RefundNotWithdrawal(1, 1)
+        ));
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(TransactionType::Refund, 1, 3, None, false))
+                .unwrap_err(),
+            AccountError::Refund(1, 3)
+        ));
+    }
+
+    #[test]
+    fn reject_refund_of_disputed_withdrawal() {
+        let tx = |type_, tx, amount| Transaction::new(type_, 1, tx, amount, false);
+        let mut accounts = Accounts::default();
+        accounts
+            .apply_transaction_iter(
+                [
+                    tx(TransactionType::Deposit, 1, Some(10.0)),
+                    tx(TransactionType::Withdrawal, 2, Some(3.0)),
+                    tx(TransactionType::Dispute, 2, None),
+                    tx(TransactionType::Chargeback, 2, None),
+                    tx(TransactionType::Refund, 2, None),
+                    tx(TransactionType::Withdrawal, 3, Some(2.0)),
+                    tx(TransactionType::Refund, 3, None),
+                    tx(TransactionType::Dispute, 3, None),
+                    tx(TransactionType::Chargeback, 3, None),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Warn,
+            )
+            .unwrap();
+        assert!(matches!(
+            accounts.warnings(),
+            [
+                AccountError::RefundDisputed(1, 2),
+                AccountError::DisputeRefunded(1, 3),
+                AccountError::ResolveUndisputed(1, 3),
+            ]
+        ));
+        // Each withdrawal is credited back once, by the chargeback or the refund.
+        assert_eq!(*accounts.get(1).unwrap().total(), 10.0);
+    }
+
+    #[test]
+    fn apply_hold_release() {
+        let mut account = Account {
+            client: 1,
+            available: 5.0,
+            total: 5.0,
+            ..Account::default()
+        };
+        account
+            .apply_transaction(Transaction::new(
+                TransactionType::Hold,
+                1,
+                1,
+                Some(3.0),
+                false,
+            ))
+            .unwrap();
+        assert_eq!(
+            (account.available, account.held, account.total),
+            (2.0, 3.0, 5.0)
+        );
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(
+                    TransactionType::Hold,
+                    1,
+                    2,
+                    Some(3.0),
+                    false
+                ))
+                .unwrap_err(),
+            AccountError::Hold(1, 2)
+        ));
+
+        account
+            .apply_transaction(Transaction::new(
+                TransactionType::Release,
+                1,
+                1,
+                None,
+                false,
+            ))
+            .unwrap();
+        assert_eq!(
+            (account.available, account.held, account.total),
+            (5.0, 0.0, 5.0)
+        );
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(
+                    TransactionType::Release,
+                    1,
+                    1,
+                    None,
+                    false
+                ))
+                .unwrap_err(),
+            AccountError::HoldReleased(1, 1)
+        ));
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(
+                    TransactionType::Release,
+                    1,
+                    3,
+                    None,
+                    false
+                ))
+                .unwrap_err(),
+            AccountError::HoldNotFound(1, 3)
+        ));
+    }
+
+    #[test]
+    fn apply_adjustments() {
+        let adjustment = |tx, amount| {
+            Transaction::new(TransactionType::Adjustment, 1, tx, Some(amount), false)
+                .with_reason("RECON-1")
+        };
+        let mut account = Account {
+            client: 1,
+            available: 5.0,
+            total: 5.0,
+            ..Account::default()
+        };
+        assert!(matches!(
+            account.apply_transaction(adjustment(1, 2.0)).unwrap_err(),
+            AccountError::AdjustmentsDisabled(1, 1)
+        ));
+
+        let config = Config {
+            allow_adjustments: true,
+            ..Config::default()
+        };
+        account
+            .apply_transaction_with_config(adjustment(1, 2.0), &config)
+            .unwrap();
+        account
+            .apply_transaction_with_config(adjustment(2, -1.5), &config)
+            .unwrap();
+        assert_eq!((account.available, account.total), (5.5, 5.5));
+        assert!(matches!(
+            account
+                .apply_transaction_with_config(adjustment(3, -10.0), &config)
+                .unwrap_err(),
+            AccountError::Overdrawn(1, 3)
+        ));
+    }
+
+    #[test]
+    fn apply_reversals() {
+        let reversal = |tx| Transaction::new(TransactionType::Reversal, 1, tx, None, false);
+        let mut account = Account::new(1);
+        account
+            .apply_transaction(Transaction::new(
+                TransactionType::Deposit,
+                1,
+                1,
+                Some(5.0),
+                false,
+            ))
+            .unwrap();
+        account
+            .apply_transaction(Transaction::new(
+                TransactionType::Withdrawal,
+                1,
+                2,
+                Some(2.0),
+                false,
+            ))
+            .unwrap();
+
+        account.apply_transaction(reversal(2)).unwrap();
+        assert_eq!((account.available, account.total), (5.0, 5.0));
+        account.apply_transaction(reversal(1)).unwrap();
+        assert_eq!((account.available, account.total), (0.0, 0.0));
+        assert!(*account.transactions.get(&1).unwrap().reversed());
+
+        assert!(matches!(
+            account.apply_transaction(reversal(1)).unwrap_err(),
+            AccountError::Reversed(1, 1)
+        ));
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(
+                    TransactionType::Dispute,
+                    1,
+                    1,
+                    None,
+                    false
+                ))
+                .unwrap_err(),
+            AccountError::Reversed(1, 1)
+        ));
+        assert!(matches!(
+            account.apply_transaction(reversal(3)).unwrap_err(),
+            AccountError::Reversal(1, 3)
+        ));
+    }
+
+    #[test]
+    fn apply_auth_capture_void() {
+        let available = 8.0;
+        let total = available;
+        let mut account = Account {
+            client: 1,
+            available,
+            total,
+            ..Account::default()
+        };
+
+        let tx_amount = 1.0;
+        // Decrease available and increase held by `tx_amount`
+        account
+            .apply_transaction(Transaction::new(
+                TransactionType::Auth,
+                1,
+                1,
+                Some(tx_amount),
+                false,
+            ))
+            .unwrap();
+        assert_eq!(account.available, available - tx_amount);
+        assert_eq!(account.held, tx_amount);
+        assert_eq!(account.total, total);
+
+        // Decrease held and total by `tx_amount`
+        account
+            .apply_transaction(Transaction::new(
+                TransactionType::Capture,
+                1,
+                1,
+                None,
+                false,
+            ))
+            .unwrap();
+        assert_eq!(account.available, available - tx_amount);
+        assert_eq!(account.held, 0.0);
+        assert_eq!(account.total, total - tx_amount);
+        assert!(*account.transactions.get(&1).unwrap().settled());
+
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(TransactionType::Void, 1, 1, None, false))
+                .unwrap_err(),
+            AccountError::AuthorizationSettled(1, 1)
+        ));
+
+        account
+            .apply_transaction(Transaction::new(
+                TransactionType::Auth,
+                1,
+                2,
+                Some(tx_amount),
+                false,
+            ))
+            .unwrap();
+        // Release the held `tx_amount`
+        account
+            .apply_transaction(Transaction::new(TransactionType::Void, 1, 2, None, false))
+            .unwrap();
+        assert_eq!(account.available, available - tx_amount);
+        assert_eq!(account.held, 0.0);
+        assert_eq!(account.total, total - tx_amount);
+
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(
+                    TransactionType::Dispute,
+                    1,
+                    2,
+                    None,
+                    false
+                ))
+                .unwrap_err(),
+            AccountError::DisputeAuthorization(1, 2)
+        ));
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(
+                    TransactionType::Capture,
+                    1,
+                    3,
+                    None,
+                    false
+                ))
+                .unwrap_err(),
+            AccountError::AuthorizationNotFound(1, 3)
+        ));
+        assert!(matches!(
+            account
+                .apply_transaction(Transaction::new(
+                    TransactionType::Auth,
+                    1,
+                    3,
+                    Some(available),
+                    false,
+                ))
+                .unwrap_err(),
+            AccountError::Authorization(1, 3)
+        ));
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn serialize_accounts_with_fees() {
+        let account = Account {
+            client: 1,
+            available: 1.5,
+            total: 1.5,
+            fees: 0.5,
+            ..Account::default()
+        };
+
+        let mut wrt = csv::Writer::from_writer(vec![]);
+        wrt.serialize(AccountWithFees::from(&account)).unwrap();
+
+        let account = &wrt.into_inner().unwrap();
+        let account = std::str::from_utf8(account).unwrap();
+        let account_expected = "client,available,held,total,locked,fees\n1,1.5,0.0,1.5,false,0.5\n";
+        assert_eq!(account, account_expected);
+    }
+
+    #[test]
+    fn apply_fees() {
+        let config = Config {
+            fees: Some(
+                FeeSchedule::default()
+                    .with_fee(TransactionType::Deposit, Fee::Flat(0.5))
+                    .with_fee(TransactionType::Withdrawal, Fee::Percentage(10.0)),
+            ),
+            ..Config::default()
+        };
+        let mut account = Account::new(1);
+
+        account
+            .apply_transaction_with_config(
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(10.0), false),
+                &config,
+            )
+            .unwrap();
+        assert_eq!(account.available, 9.5);
+        assert_eq!(account.total, 9.5);
+        assert_eq!(account.fees, 0.5);
+
+        account
+            .apply_transaction_with_config(
+                Transaction::new(TransactionType::Withdrawal, 1, 2, Some(5.0), false),
+                &config,
+            )
+            .unwrap();
+        assert_eq!(account.available, 4.0);
+        assert_eq!(account.total, 4.0);
+        assert_eq!(account.fees, 1.0);
+
+        // The withdrawal itself is covered, but its fee isn't
+        assert!(matches!(
+            account
+                .apply_transaction_with_config(
+                    Transaction::new(TransactionType::Withdrawal, 1, 3, Some(4.0), false),
+                    &config,
+                )
+                .unwrap_err(),
+            AccountError::Withdrawal(1, 3)
+        ));
+        assert_eq!(account.fees, 1.0);
+    }
+
+    #[test]
+    fn accrue_interest() {
+        let policy = InterestPolicy::new(0.01, 2);
+        let mut account = Account {
+            client: 1,
+            available: 100.0,
+            total: 100.0,
+            ..Account::default()
+        };
+
+        // The first accrual only marks the start of the accrual period
+        assert_eq!(account.accrue_interest(SECONDS_PER_DAY + 1, &policy), None);
+
+        // A day's worth of interest is accrued, but not posted yet
+        assert_eq!(
+            account.accrue_interest(2 * SECONDS_PER_DAY + 1, &policy),
+            None
+        );
+        assert_eq!(account.interest.accrued, 1.0);
+
+        // Posting period elapsed
+        let posting = account.accrue_interest(3 * SECONDS_PER_DAY, &policy);
+        assert_eq!(posting, Some((3 * SECONDS_PER_DAY, 2.0)));
+        assert_eq!(account.interest.accrued, 0.0);
+        let (at, amount) = posting.unwrap();
+        account
+            .apply_transaction(Transaction::interest(1, at, amount))
+            .unwrap();
+        assert_eq!((account.available, account.total), (102.0, 102.0));
+
+        // Posted interest accrues interest in turn, accrual stopping at each posting
+        let posting = account.accrue_interest(7 * SECONDS_PER_DAY, &policy);
+        assert_eq!(posting, Some((5 * SECONDS_PER_DAY, 2.0 * 1.02)));
+        assert_eq!(account.interest.accrued_until, Some(5 * SECONDS_PER_DAY));
+    }
+
+    #[test]
+    fn accrue_interest_on_transactions() {
+        let config = Config {
+            interest: Some(InterestPolicy::new(0.01, 1)),
+            journal: true,
+            ..Config::default()
+        };
+        let mut accounts = Accounts::with_config(config);
+        accounts
+            .apply_transaction_iter(
+                vec![
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(100.0), false)
+                        .with_timestamp(0),
+                    Transaction::new(TransactionType::Withdrawal, 1, 2, Some(1.0), false)
+                        .with_timestamp(SECONDS_PER_DAY),
+                    // Interest cannot be credited from input
+                    Transaction::new(TransactionType::Interest, 1, 3, Some(50.0), false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Warn,
+            )
+            .unwrap();
+        // A day's worth of interest was posted before applying the withdrawal
+        assert_eq!(accounts.get(1).unwrap().available, 100.0);
+        assert!(matches!(
+            accounts.warnings(),
+            [AccountError::InterestNotAccrued(1, 3)]
+        ));
+
+        accounts
+            .accrue_interest(2 * SECONDS_PER_DAY, Strictness::Strict)
+            .unwrap();
+        assert_eq!(accounts.get(1).unwrap().available, 101.0);
+        assert_eq!(accounts.report().others, 2);
+
+        // Postings are journaled, statements replaying them
+        let interest = (accounts.get(1).unwrap().journal.iter())
+            .filter(|tx| *tx.type_() == TransactionType::Interest)
+            .map(|tx| (*tx.timestamp(), tx.amount()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            interest,
+            [
+                (Some(SECONDS_PER_DAY), 1.0),
+                (Some(2 * SECONDS_PER_DAY), 1.0)
+            ]
+        );
+        let account = accounts
+            .account_at(1, PointInTime::Timestamp(2 * SECONDS_PER_DAY))
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.available, 101.0);
+    }
+
+    #[test]
+    fn apply_balance_policy() {
+        let deposit = Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false);
+        let withdrawal = Transaction::new(TransactionType::Withdrawal, 1, 2, Some(1.5), false);
+        let dispute = Transaction::new(TransactionType::Dispute, 1, 1, None, false);
+
+        // Disputing the spent deposit would overdraw the account
+        let mut account = Account::new(1);
+        account.apply_transaction(deposit.clone()).unwrap();
+        account.apply_transaction(withdrawal.clone()).unwrap();
+        assert!(matches!(
+            account.apply_transaction(dispute.clone()).unwrap_err(),
+            AccountError::Overdrawn(1, 1)
+        ));
+        assert_eq!(account.available, 0.5);
+        assert_eq!(account.held, 0.0);
+
+        let config = Config {
+            balance: BalancePolicy::Overdraft(1.0),
+            ..Config::default()
+        };
+        let mut account = Account::new(1);
+        account
+            .apply_transaction_with_config(deposit.clone(), &config)
+            .unwrap();
+        account
+            .apply_transaction_with_config(withdrawal.clone(), &config)
+            .unwrap();
+        assert!(matches!(
+            account
+                .apply_transaction_with_config(dispute.clone(), &config)
+                .unwrap_err(),
+            AccountError::Overdrawn(1, 1)
+        ));
+        account
+            .apply_transaction_with_config(
+                Transaction::new(TransactionType::Withdrawal, 1, 3, Some(1.5), false),
+                &config,
+            )
+            .unwrap();
+        assert_eq!(account.available, -1.0);
+        assert!(matches!(
+            account
+                .apply_transaction_with_config(
+                    Transaction::new(TransactionType::Withdrawal, 1, 4, Some(0.1), false),
+                    &config,
+                )
+                .unwrap_err(),
+            AccountError::Withdrawal(1, 4)
+        ));
+
+        let config = Config {
+            balance: BalancePolicy::AllowNegative,
+            ..Config::default()
+        };
+        let mut account = Account::new(1);
+        account
+            .apply_transaction_with_config(deposit, &config)
+            .unwrap();
+        account
+            .apply_transaction_with_config(withdrawal, &config)
+            .unwrap();
+        account
+            .apply_transaction_with_config(dispute, &config)
+            .unwrap();
+        assert_eq!(account.available, -1.5);
+        assert_eq!(account.held, 2.0);
+        assert_eq!(account.total, 0.5);
+    }
+
+    #[test]
+    fn apply_provisional_credit() {
+        let mut accounts = Accounts::with_config(Config {
+            provisional_credit: true,
+            ..Config::default()
+        });
+        accounts
+            .apply_transaction_iter(
+                [
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false),
+                    Transaction::new(TransactionType::Deposit, 1, 2, Some(3.0), false),
+                    Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+                    Transaction::new(TransactionType::Dispute, 1, 2, None, false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+        let account = accounts.get(1).unwrap();
+        assert_eq!((account.available, account.held), (5.0, 0.0));
+        assert_eq!(account.provisional, 5.0);
+
+        accounts
+            .apply_transaction_iter(
+                [
+                    Transaction::new(TransactionType::Resolve, 1, 1, None, false),
+                    Transaction::new(TransactionType::Chargeback, 1, 2, None, false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+        let account = accounts.get(1).unwrap();
+        assert_eq!((account.available, account.total), (2.0, 2.0));
+        assert_eq!((account.provisional, account.locked), (0.0, true));
+    }
+
+    #[test]
+    fn apply_velocity_limit() {
+        let mut config = Config::default();
+        config.velocity.default = Some(VelocityLimit {
+            max_count: Some(2),
+            max_amount: Some(3.0),
+            ..VelocityLimit::default()
+        });
+        let withdrawal = |tx, amount, timestamp| {
+            Transaction::new(TransactionType::Withdrawal, 1, tx, Some(amount), false)
+                .with_timestamp(timestamp)
+        };
+        let mut account = Account {
+            client: 1,
+            available: 10.0,
+            total: 10.0,
+            ..Account::default()
+        };
+
+        account
+            .apply_transaction_with_config(withdrawal(1, 1.0, 0), &config)
+            .unwrap();
+        assert!(matches!(
+            account
+                .apply_transaction_with_config(withdrawal(2, 2.5, 1), &config)
+                .unwrap_err(),
+            AccountError::Velocity(1, 2)
+        ));
+        account
+            .apply_transaction_with_config(withdrawal(3, 2.0, 2), &config)
+            .unwrap();
+        assert!(matches!(
+            account
+                .apply_transaction_with_config(withdrawal(4, 0.1, 3), &config)
+                .unwrap_err(),
+            AccountError::Velocity(1, 4)
+        ));
+
+        // The first withdrawal left the window
+        account
+            .apply_transaction_with_config(withdrawal(5, 1.0, SECONDS_PER_DAY), &config)
+            .unwrap();
+        assert_eq!(account.available, 6.0);
+
+        // Per client limits override the default ones
+        config
+            .velocity
+            .per_client
+            .insert(1, VelocityLimit::default());
+        account
+            .apply_transaction_with_config(withdrawal(6, 5.0, SECONDS_PER_DAY), &config)
+            .unwrap();
+        assert_eq!(account.available, 1.0);
+
+        // Windows extending past the latest timestamp don't overflow
+        for (tx, timestamp) in [(7, Timestamp::MAX - 1), (8, Timestamp::MAX)] {
+            account
+                .apply_transaction_with_config(withdrawal(tx, 0.5, timestamp), &config)
+                .unwrap();
+        }
+        assert_eq!(account.available, 0.0);
+
+        // Withdrawals failing to debit the account don't count towards the limit
+        config.velocity.per_client.insert(
+            1,
+            VelocityLimit {
+                max_count: Some(1),
+                ..VelocityLimit::default()
+            },
+        );
+        let mut account = Account {
+            client: 1,
+            available: 10.0,
+            total: f64::MAX,
+            ..Account::default()
+        };
+        assert!(matches!(
+            account
+                .apply_transaction_with_config(withdrawal(9, 1.0, 0), &config)
+                .unwrap_err(),
+            AccountError::Overflow(1, 9)
+        ));
+        account.total = 10.0;
+        account
+            .apply_transaction_with_config(withdrawal(10, 1.0, 1), &config)
+            .unwrap();
+    }
+
+    #[test]
+    fn apply_daily_withdrawal_limit() {
+        let mut config = Config::default();
+        config.daily_withdrawals.default = Some(5.0);
+        config.daily_withdrawals.per_client.insert(2, 1.0);
+        let withdrawal = |client, tx, amount, timestamp| {
+            Transaction::new(TransactionType::Withdrawal, client, tx, Some(amount), false)
+                .with_timestamp(timestamp)
+        };
+        let mut account = Account {
+            client: 1,
+            available: 20.0,
+            total: 20.0,
+            ..Account::default()
+        };
+
+        account
+            .apply_transaction_with_config(withdrawal(1, 1, 3.0, 100), &config)
+            .unwrap();
+        assert!(matches!(
+            account
+                .apply_transaction_with_config(withdrawal(1, 2, 2.5, 200), &config)
+                .unwrap_err(),
+            AccountError::LimitExceeded(1, 2, 5.0, 2.5)
+        ));
+        account
+            .apply_transaction_with_config(withdrawal(1, 3, 2.0, 300), &config)
+            .unwrap();
+        // Withdrawals without a timestamp aren't limited
+        account
+            .apply_transaction_with_config(
+                Transaction::new(TransactionType::Withdrawal, 1, 4, Some(6.0), false),
+                &config,
+            )
+            .unwrap();
+        // The limit resets on the next day
+        account
+            .apply_transaction_with_config(withdrawal(1, 5, 5.0, SECONDS_PER_DAY), &config)
+            .unwrap();
+        assert_eq!(account.available, 4.0);
+
+        let mut account = Account {
+            client: 2,
+            available: 20.0,
+            total: 20.0,
+            ..Account::default()
+        };
+        assert!(matches!(
+            account
+                .apply_transaction_with_config(withdrawal(2, 6, 2.0, 100), &config)
+                .unwrap_err(),
+            AccountError::LimitExceeded(2, 6, 1.0, 2.0)
+        ));
+
+        // Withdrawals failing to debit the account don't count towards the limit
+        let mut account = Account {
+            client: 2,
+            available: 10.0,
+            total: f64::MAX,
+            ..Account::default()
+        };
+        assert!(matches!(
+            account
+                .apply_transaction_with_config(withdrawal(2, 7, 1.0, 100), &config)
+                .unwrap_err(),
+            AccountError::Overflow(2, 7)
+        ));
+        account.total = 10.0;
+        account
+            .apply_transaction_with_config(withdrawal(2, 8, 1.0, 200), &config)
+            .unwrap();
+    }
+
+    #[test]
+    fn apply_balance_cap() {
+        let deposits = || {
+            [(1, 6.0), (2, 3.0), (3, 2.0), (4, 1.0)]
+                .map(|(tx, amount)| {
+                    Transaction::new(TransactionType::Deposit, 1, tx, Some(amount), false)
+                })
+                .into_iter()
+                .map(Ok)
+        };
+        let capped = |action| {
+            let mut accounts = Accounts::with_config(Config {
+                balance_cap: Some(BalanceCap {
+                    max_total: 10.0,
+                    action,
+                }),
+                ..Config::default()
+            });
+            accounts
+                .apply_transaction_iter(deposits(), Strictness::Warn)
+                .unwrap();
+            accounts
+        };
+
+        let accounts = capped(CapAction::Reject);
+        assert!(matches!(
+            accounts.warnings(),
+            [AccountError::BalanceCap(1, 3)]
+        ));
+        assert_eq!(*accounts.get(1).unwrap().total(), 10.0);
+
+        let accounts = capped(CapAction::Partial);
+        assert!(matches!(
+            accounts.warnings(),
+            [AccountError::BalanceCap(1, 4)]
+        ));
+        let account = accounts.get(1).unwrap();
+        assert_eq!(*account.total(), 10.0);
+        assert_eq!(account.stored(3).unwrap().amount(), 1.0);
+        let report = accounts.report();
+        assert_eq!(
+            (report.deposited, report.capped, report.capped_excess),
+            (10.0, 1, 1.0)
+        );
+    }
+
+    #[test]
+    fn apply_freeze_policy() {
+        let transactions = || {
+            vec![
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+                Transaction::new(TransactionType::Deposit, 1, 2, Some(2.0), false),
+                Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+                Transaction::new(TransactionType::Chargeback, 1, 1, None, false),
+                Transaction::new(TransactionType::Dispute, 1, 2, None, false),
+            ]
+        };
+        let locked_after = |freeze, n| {
+            let config = Config {
+                freeze,
+                ..Config::default()
+            };
+            let mut account = Account::new(1);
+            for tx in transactions().into_iter().take(n) {
+                account.apply_transaction_with_config(tx, &config).unwrap();
+            }
+            account.locked
+        };
+
+        assert!(!locked_after(FreezePolicy::FirstChargeback, 3));
+        assert!(locked_after(FreezePolicy::FirstChargeback, 4));
+        assert!(!locked_after(FreezePolicy::Chargebacks(2), 5));
+        assert!(!locked_after(FreezePolicy::Disputes(2), 4));
+        assert!(locked_after(FreezePolicy::Disputes(2), 5));
+        assert!(!locked_after(FreezePolicy::HeldAbove(1.5), 4));
+        assert!(locked_after(FreezePolicy::HeldAbove(1.5), 5));
+    }
+
+    #[test]
+    fn apply_risk_scorer() {
+        let scorer = |account: &Account, tx: &Transaction| {
+            if tx.amount() > *account.available() + 10.0 {
+                RiskDecision::Veto("amount out of bounds".to_string())
+            } else if tx.amount() > 5.0 {
+                RiskDecision::Flag("large amount".to_string())
+            } else {
+                RiskDecision::Allow
+            }
+        };
+        let transactions = vec![
+            Transaction::new(TransactionType::Deposit, 1, 1, Some(5.0), false),
+            Transaction::new(TransactionType::Deposit, 1, 2, Some(6.0), false),
+            Transaction::new(TransactionType::Deposit, 1, 3, Some(100.0), false),
+        ];
+
+        let mut accounts = Accounts::default().with_risk_scorer(scorer);
+        accounts
+            .apply_transaction_iter(
+                transactions.clone().into_iter().map(Ok),
+                Strictness::Lenient,
+            )
+            .unwrap();
+        assert_eq!(*accounts.get(1).unwrap().available(), 11.0);
+        assert_eq!(
+            accounts.risk_flags(),
+            [RiskFlag {
+                client: 1,
+                tx: 2,
+                annotation: "large amount".to_string()
+            }]
+        );
+
+        let mut accounts = Accounts::default().with_risk_scorer(scorer);
+        assert!(matches!(
+            accounts
+                .apply_transaction_iter(transactions.into_iter().map(Ok), Strictness::Strict)
+                .unwrap_err(),
+            AccountError::Vetoed(1, 3, _)
+        ));
+    }
+
+    #[test]
+    fn arbitrate_disputes() {
+        let arbiter = |_: &Account, disputed: &Transaction| {
+            if disputed.amount() < 1.0 {
+                DisputeDecision::Resolve
+            } else if disputed.amount() > 10.0 {
+                DisputeDecision::Chargeback
+            } else {
+                DisputeDecision::Hold
+            }
+        };
+        let mut accounts = Accounts::default().with_dispute_arbiter(arbiter);
+        accounts
+            .apply_transaction_iter(
+                [
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(0.5), false),
+                    Transaction::new(TransactionType::Deposit, 1, 2, Some(5.0), false),
+                    Transaction::new(TransactionType::Deposit, 2, 3, Some(20.0), false),
+                    Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+                    Transaction::new(TransactionType::Dispute, 1, 2, None, false),
+                    Transaction::new(TransactionType::Dispute, 2, 3, None, false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+
+        let account = accounts.get(1).unwrap();
+        assert_eq!((*account.available(), *account.held()), (0.5, 5.0));
+        let account = accounts.get(2).unwrap();
+        assert_eq!((*account.total(), *account.locked()), (0.0, true));
+        assert_eq!(
+            (accounts.report().resolves, accounts.report().chargebacks),
+            (1, 1)
+        );
+    }
+
+    #[test]
+    fn expire_stale_disputes() {
+        let mut accounts = Accounts::with_config(Config {
+            dispute_timeout: Some(DisputeTimeout {
+                after: SECONDS_PER_DAY,
+                action: TimeoutAction::Chargeback,
+            }),
+            journal: true,
+            ..Config::default()
+        });
+        let at = |tx: Transaction, timestamp| tx.with_timestamp(timestamp);
+        accounts
+            .apply_transaction_iter(
+                [
+                    at(
+                        Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false),
+                        0,
+                    ),
+                    at(
+                        Transaction::new(TransactionType::Deposit, 1, 2, Some(3.0), false),
+                        0,
+                    ),
+                    at(
+                        Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+                        10,
+                    ),
+                    at(
+                        Transaction::new(TransactionType::Dispute, 1, 2, None, false),
+                        10,
+                    ),
+                    at(
+                        Transaction::new(TransactionType::Resolve, 1, 2, None, false),
+                        20,
+                    ),
+                    at(
+                        Transaction::new(TransactionType::Deposit, 2, 3, Some(1.0), false),
+                        2 * SECONDS_PER_DAY,
+                    ),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+
+        let account = accounts.get(1).unwrap();
+        assert_eq!((*account.total(), *account.locked()), (3.0, true));
+        let chargeback = account.journal.last().unwrap();
+        assert_eq!(
+            (
+                *chargeback.type_(),
+                *chargeback.tx(),
+                *chargeback.timestamp()
+            ),
+            (TransactionType::Chargeback, 1, Some(SECONDS_PER_DAY + 10))
+        );
+        assert_eq!(accounts.report().chargebacks, 1);
+    }
+
+    #[test]
+    fn apply_scheduled_transactions() {
+        let deposit =
+            |tx, amount| Transaction::new(TransactionType::Deposit, 1, tx, Some(amount), false);
+        let mut accounts = Accounts::default();
+        accounts
+            .apply_transaction_iter(
+                [
+                    deposit(1, 1.0).with_timestamp(10),
+                    deposit(2, 5.0).with_timestamp(10).with_effective(100),
+                    deposit(3, 2.0).with_effective(200),
+                    Transaction::new(TransactionType::Withdrawal, 1, 4, Some(2.0), false)
+                        .with_timestamp(50),
+                    deposit(5, 1.0).with_timestamp(120),
+                    Transaction::new(TransactionType::Deposit, 2, 6, Some(3.0), false)
+                        .with_effective(300),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Lenient,
+            )
+            .unwrap();
+        assert_eq!(*accounts.get(1).unwrap().total(), 7.0);
+        assert_eq!(
+            accounts.scheduled().map(|tx| *tx.tx()).collect::<Vec<_>>(),
+            vec![3, 6]
+        );
+        assert_eq!(accounts.report().rejected, 1);
+        // Accounts are only opened once transactions take effect
+        assert!(accounts.get(2).is_none());
+
+        accounts.flush(Strictness::Strict).unwrap();
+        assert_eq!(*accounts.get(1).unwrap().total(), 9.0);
+        assert_eq!(*accounts.get(2).unwrap().total(), 3.0);
+        assert_eq!(accounts.scheduled().count(), 0);
+    }
+
+    #[test]
+    fn notify_observers() {
+        #[derive(Clone, Default)]
+        struct EventLog(Arc<Mutex<Vec<String>>>);
+
+        impl Observer for EventLog {
+            fn on_applied(&self, account: &Account, tx: &Transaction) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("applied {} to {}", tx.tx(), account.client()));
+            }
+
+            fn on_rejected(&self, tx: &Transaction, _error: &AccountError) {
+                self.0.lock().unwrap().push(format!("rejected {}", tx.tx()));
+            }
+
+            fn on_frozen(&self, account: &Account) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("frozen {}", account.client()));
+            }
+        }
+
+        let log = EventLog::default();
+        let mut accounts = Accounts::default().with_observer(log.clone());
+        accounts
+            .apply_transaction_iter(
+                vec![
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+                    Transaction::new(TransactionType::Withdrawal, 1, 2, Some(2.0), false),
+                    Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+                    Transaction::new(TransactionType::Chargeback, 1, 1, None, false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Lenient,
+            )
+            .unwrap();
+
+        assert_eq!(
+            *log.0.lock().unwrap(),
+            [
+                "applied 1 to 1",
+                "rejected 2",
+                "applied 1 to 1",
+                "applied 1 to 1",
+                "frozen 1"
+            ]
+        );
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn report_processing() {
+        let sample_path = "src/test_utils/test_txs.csv";
+        let accounts = Accounts::from_transactions(
+            Transactions::from_csv(sample_path).unwrap(),
+            Strictness::Lenient,
+        )
+        .unwrap();
+        assert_eq!(
+            *accounts.report(),
+            ProcessingReport {
+                deposits: 1,
+                withdrawals: 1,
+                disputes: 1,
+                resolves: 1,
+                rejected: 1,
+                deposited: 2.0,
+                withdrawn: 1.5,
+                ..ProcessingReport::default()
+            }
+        );
+    }
+
+    #[test]
+    fn query_accounts() {
+        let accounts = Accounts::from_transaction_iter(
+            vec![
+                Transaction::new(TransactionType::Deposit, 3, 1, Some(1.0), false),
+                Transaction::new(TransactionType::Deposit, 1, 2, Some(1.0), false),
+                Transaction::new(TransactionType::Deposit, 2, 3, Some(1.0), false),
+                Transaction::new(TransactionType::Dispute, 2, 3, None, false),
+                Transaction::new(TransactionType::Dispute, 3, 1, None, false),
+                Transaction::new(TransactionType::Chargeback, 3, 1, None, false),
+            ]
+            .into_iter()
+            .map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap();
+
+        assert_eq!(*accounts.get(1).unwrap().client(), 1);
+        assert!(accounts.get(4).is_none());
+        assert_eq!(
+            accounts
+                .iter_sorted()
+                .map(|acc| *acc.client())
+                .collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert_eq!(
+            accounts
+                .locked_accounts()
+                .map(|acc| *acc.client())
+                .collect::<Vec<_>>(),
+            [3]
+        );
+        assert_eq!(
+            accounts
+                .accounts_with_held_funds()
+                .map(|acc| *acc.client())
+                .collect::<Vec<_>>(),
+            [2]
+        );
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn export_history() {
+        let accounts = Accounts::from_transaction_iter(
+            vec![
+                Transaction::new(TransactionType::Deposit, 1, 2, Some(2.0), false),
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+                Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+            ]
+            .into_iter()
+            .map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap();
+        assert_eq!(
+            accounts
+                .get(1)
+                .unwrap()
+                .history()
+                .map(|tx| (*tx.tx(), *tx.disputed()))
+                .collect::<Vec<_>>(),
+            [(2, false), (1, true)]
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().join("history");
+        accounts.export_history(&dir, HistoryFormat::Csv).unwrap();
+        let history = std::fs::read_to_string(dir.join("1.csv")).unwrap();
+        assert_eq!(
+            history,
+            "\
+type,client,tx,amount,timestamp,disputed,charged_back,refunded,settled,reversed
+deposit,1,2,2.0,,false,false,false,false,false
+deposit,1,1,1.0,,true,false,false,false,false
+"
+        );
+    }
+
+    #[test]
+    fn query_account_at() {
+        let mut accounts = Accounts::with_config(Config {
+            journal: true,
+            ..Config::default()
+        });
+        accounts
+            .apply_transaction_iter(
+                vec![
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false)
+                        .with_timestamp(10),
+                    Transaction::new(TransactionType::Deposit, 1, 2, Some(1.0), false)
+                        .with_timestamp(20),
+                    Transaction::new(TransactionType::Dispute, 1, 1, None, false)
+                        .with_timestamp(30),
+                    Transaction::new(TransactionType::Chargeback, 1, 1, None, false)
+                        .with_timestamp(40),
+                    Transaction::new(TransactionType::Withdrawal, 1, 3, Some(0.5), false)
+                        .with_timestamp(50),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+
+        let account = accounts
+            .account_at(1, PointInTime::Transaction(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(*account.available(), 3.0);
+        let account = accounts
+            .account_at(1, PointInTime::Timestamp(35))
+            .unwrap()
+            .unwrap();
+        assert_eq!((*account.available(), *account.held()), (1.0, 2.0));
+        let account = accounts
+            .account_at(1, PointInTime::Transaction(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!((*account.total(), *account.locked()), (1.0, true));
+        let account = accounts
+            .account_at(1, PointInTime::Timestamp(50))
+            .unwrap()
+            .unwrap();
+        assert_eq!(*account.total(), 0.5);
+        assert!(accounts
+            .account_at(1, PointInTime::Transaction(4))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn merge_accounts() {
+        let accounts = |transactions: Vec<Transaction>| {
+            Accounts::from_transaction_iter(transactions.into_iter().map(Ok), Strictness::Strict)
+                .unwrap()
+        };
+        let shard = || {
+            accounts(vec![
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+                Transaction::new(TransactionType::Deposit, 2, 2, Some(2.0), false),
+                Transaction::new(TransactionType::Dispute, 2, 2, None, false),
+            ])
+        };
+        let other = || {
+            accounts(vec![
+                Transaction::new(TransactionType::Deposit, 2, 3, Some(3.0), false),
+                Transaction::new(TransactionType::Deposit, 3, 4, Some(4.0), false),
+            ])
+        };
+
+        let merged = shard().merge(other()).unwrap();
+        assert_eq!(merged.len(), 3);
+        let account = merged.get(2).unwrap();
+        assert_eq!(
+            (*account.available(), *account.held(), *account.total()),
+            (3.0, 2.0, 5.0)
+        );
+        assert_eq!(account.history().count(), 2);
+        assert_eq!(merged.report().deposits, 4);
+
+        assert!(matches!(
+            shard().merge(shard()),
+            Err(MergeError::TransactionClash(..))
+        ));
+    }
+
+    #[test]
+    fn summarize_funds() {
+        let mut accounts = Accounts::default();
+        for (client, available, held, locked) in [
+            (1, 2.0, 0.0, false),
+            (2, -3.0, 1.0, true),
+            (3, 1.0, 4.0, false),
+        ] {
+            accounts.insert(
+                client,
+                Account {
+                    client,
+                    available,
+                    held,
+                    total: available + held,
+                    locked,
+                    ..Account::default()
+                },
+            );
+        }
+        let summary = accounts.funds_summary();
+        assert_eq!(
+            (summary.available, summary.held, summary.total),
+            (0.0, 5.0, 5.0)
+        );
+        assert_eq!((summary.locked, summary.negative), (1, vec![2]));
+    }
+
+    #[test]
+    fn top_accounts() {
+        let mut accounts = Accounts::default();
+        for (client, available, held) in
+            [(1, 2.0, 0.0), (2, -3.0, 0.0), (3, 1.0, 4.0), (4, 2.0, 0.0)]
+        {
+            accounts.insert(
+                client,
+                Account {
+                    client,
+                    available,
+                    held,
+                    total: available + held,
+                    ..Account::default()
+                },
+            );
+        }
+        let clients = |top: Vec<&Account>| top.iter().map(|acc| acc.client).collect::<Vec<_>>();
+        assert_eq!(clients(accounts.top_by(|acc| acc.total, 3)), vec![3, 1, 4]);
+        assert_eq!(clients(accounts.top_by(|acc| acc.held, 1)), vec![3]);
+        assert_eq!(clients(accounts.top_by(|acc| -acc.available, 1)), vec![2]);
+        assert_eq!(accounts.top_by(|acc| acc.total, 10).len(), 4);
+    }
+
+    #[test]
+    fn validate_invariants() {
+        let account = Account {
+            client: 1,
+            available: 1.0,
+            held: -1.0,
+            total: 1.0,
+            ..Account::default()
+        };
+        assert_eq!(
+            account
+                .validate()
+                .into_iter()
+                .map(|v| v.violation)
+                .collect::<Vec<_>>(),
+            [
+                Violation::TotalMismatch {
+                    available: 1.0,
+                    held: -1.0,
+                    total: 1.0
+                },
+                Violation::NegativeHeld(-1.0),
+            ]
+        );
+
+        let account = Account {
+            client: 1,
+            available: -1.0,
+            total: -1.0,
+            ..Account::default()
+        };
+        assert_eq!(account.validate().len(), 1);
+        assert!(account
+            .validate_with_policy(&BalancePolicy::AllowNegative)
+            .is_empty());
+
+        // Charging back a deposit whose funds were withdrawn drives the total negative
+        let mut accounts = Accounts::with_config(Config {
+            balance: BalancePolicy::AllowNegative,
+            validate: true,
+            ..Config::default()
+        });
+        accounts
+            .apply_transaction_iter(
+                vec![
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+                    Transaction::new(TransactionType::Withdrawal, 1, 2, Some(1.0), false),
+                    Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+                    Transaction::new(TransactionType::Chargeback, 1, 1, None, false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+        assert!(accounts.validate().is_empty());
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn serialize_accounts_sorted() {
+        let accounts = Accounts::from_transaction_iter(
+            (1..=100)
+                .rev()
+                .map(|client| {
+                    Transaction::new(TransactionType::Deposit, client, 1, Some(1.0), false)
+                })
+                .map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap();
+
+        let mut wrt = vec![];
+        accounts.to_csv_writer(&mut wrt).unwrap();
+        let expected = (1..=100).fold(
+            "client,available,held,total,locked\n".to_string(),
+            |out, client| out + &format!("{client},1.0,0.0,1.0,false\n"),
+        );
+        assert_eq!(std::str::from_utf8(&wrt).unwrap(), expected);
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn serialize_accounts_to_file() {
+        let accounts = Accounts::from_transaction_iter(
+            std::iter::once(Ok(Transaction::new(
+                TransactionType::Deposit,
+                1,
+                1,
+                Some(1.0),
+                false,
+            ))),
+            Strictness::Strict,
+        )
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("accounts-to-file.csv");
+        accounts.to_csv_file(&path, false).unwrap();
+        accounts.to_csv_file(&path, true).unwrap();
+        let output = std::fs::read_to_string(&path).unwrap();
+        accounts.to_csv_file(&path, false).unwrap();
+        let output_truncated = std::fs::read_to_string(&path).unwrap();
+
+        assert_eq!(
+            output,
+            "client,available,held,total,locked\n1,1.0,0.0,1.0,false\n1,1.0,0.0,1.0,false\n"
+        );
+        assert_eq!(
+            output_truncated,
+            "client,available,held,total,locked\n1,1.0,0.0,1.0,false\n"
+        );
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn deserialize_accounts() {
+        let accounts = Accounts::from_transaction_iter(
+            [
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(2.5), false),
+                Transaction::new(TransactionType::Deposit, 1, 2, Some(1.0), false),
+                Transaction::new(TransactionType::Dispute, 1, 2, None, false),
+                Transaction::new(TransactionType::Deposit, 2, 3, Some(1.0), false),
+                Transaction::new(TransactionType::Dispute, 2, 3, None, false),
+                Transaction::new(TransactionType::Chargeback, 2, 3, None, false),
+            ]
+            .into_iter()
+            .map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap();
+        let mut wrt = vec![];
+        accounts.to_csv_writer(&mut wrt).unwrap();
+
+        let parsed = Accounts::from_csv_reader(&wrt[..]).unwrap();
+        let account = parsed.get(1).unwrap();
+        assert_eq!(
+            (*account.available(), *account.held(), *account.total()),
+            (2.5, 1.0, 3.5)
+        );
+        assert!(account.history().next().is_none());
+        assert!(*parsed.get(2).unwrap().locked());
+        let mut round_trip = vec![];
+        parsed.to_csv_writer(&mut round_trip).unwrap();
+        assert_eq!(round_trip, wrt);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn serialize_complete_state() {
+        let accounts = Accounts::from_transaction_iter(
+            [
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false),
+                Transaction::new(TransactionType::Deposit, 1, 2, Some(1.0), false)
+                    .with_idempotency_key("key"),
+                Transaction::new(TransactionType::Dispute, 1, 2, None, false),
+                Transaction::new(TransactionType::Deposit, 2, 3, Some(1.0), false),
+            ]
+            .into_iter()
+            .map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&accounts).unwrap();
+        let mut restored: Accounts = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.get(1), accounts.get(1));
+        assert_eq!(restored.get(2), accounts.get(2));
+        assert_eq!(restored.report(), accounts.report());
+        restored
+            .apply_transaction_iter(
+                std::iter::once(Ok(Transaction::new(
+                    TransactionType::Resolve,
+                    1,
+                    2,
+                    None,
+                    false,
+                ))),
+                Strictness::Strict,
+            )
+            .unwrap();
+        assert_eq!(*restored.get(1).unwrap().held(), 0.0);
+        assert_eq!(*restored.get(1).unwrap().available(), 3.0);
+    }
 }
@@ -0,0 +1,191 @@
+#[cfg(feature = "csv")]
+use std::io::Write;
+use std::{collections::BTreeMap, sync::Mutex};
+
+use serde::Serialize;
+
+#[cfg(feature = "csv")]
+use super::account::AccountError;
+use super::{
+    account::{serialize_to_decimal_precision, Account},
+    interest::SECONDS_PER_DAY,
+    observer::Observer,
+    transaction::{Transaction, TransactionType},
+    types::{ClientId, Timestamp},
+};
+
+/// Length of the periods activity is bucketed by, in UTC.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Period {
+    #[default]
+    Day,
+    Month,
+}
+
+impl Period {
+    /// Period containing `timestamp`, e.g. `2024-03-01` for a day, or `2024-03` for a month.
+    fn of(&self, timestamp: Timestamp) -> String {
+        let (year, month, day) = civil_date(timestamp);
+        match self {
+            Self::Day => format!("{year:04}-{month:02}-{day:02}"),
+            Self::Month => format!("{year:04}-{month:02}"),
+        }
+    }
+}
+
+/// Activity of a client over a period.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PeriodActivity {
+    pub period: String,
+    pub client: ClientId,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub deposited: f64,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub withdrawn: f64,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub disputed: f64,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub charged_back: f64,
+}
+
+/// Observer aggregating the amounts deposited, withdrawn, disputed and charged back by each
+/// client over periods, as per the timestamps of the transactions applied. Transactions
+/// without a timestamp are disregarded. Share it through an `Arc` to read the aggregates
+/// after processing.
+#[derive(Debug, Default)]
+pub struct ActivityReport {
+    period: Period,
+    activity: Mutex<BTreeMap<(String, ClientId), PeriodActivity>>,
+}
+
+impl ActivityReport {
+    pub fn new(period: Period) -> Self {
+        Self {
+            period,
+            activity: Mutex::default(),
+        }
+    }
+
+    /// Activity aggregated so far, ordered by period then client id, skipping periods in
+    /// which a client had no activity.
+    pub fn activity(&self) -> Vec<PeriodActivity> {
+        self.activity.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Write the activity aggregated so far to `wrt` as csv.
+    #[cfg(feature = "csv")]
+    pub fn to_csv<W: Write>(&self, wrt: W) -> Result<(), AccountError> {
+        let mut wrt = csv::Writer::from_writer(wrt);
+        for activity in self.activity.lock().unwrap().values() {
+            wrt.serialize(activity)?;
+        }
+        wrt.flush()?;
+        Ok(())
+    }
+}
+
+impl Observer for ActivityReport {
+    fn on_applied(&self, account: &Account, tx: &Transaction) {
+        let Some(timestamp) = tx.timestamp() else {
+            return;
+        };
+        // Disputes and chargebacks amount to the transaction they refer to.
+        let amount = || account.stored(*tx.tx()).map_or(0.0, Transaction::amount);
+        let period = self.period.of(*timestamp);
+        let client = *tx.client();
+        let mut activity = self.activity.lock().unwrap();
+        let activity = activity
+            .entry((period.clone(), client))
+            .or_insert_with(|| PeriodActivity {
+                period,
+                client,
+                ..PeriodActivity::default()
+            });
+        match tx.type_() {
+            TransactionType::Deposit => activity.deposited += tx.amount(),
+            TransactionType::Withdrawal => activity.withdrawn += tx.amount(),
+            TransactionType::Dispute => activity.disputed += amount(),
+            TransactionType::Chargeback => activity.charged_back += amount(),
+            _ => {}
+        }
+    }
+}
+
+/// Year, month and day of the UTC date of `timestamp`, as per the proleptic Gregorian
+/// calendar.
+fn civil_date(timestamp: Timestamp) -> (u64, u64, u64) {
+    // Count days from 0000-03-01, so that leap days end years, in 400-year eras.
+    let days = timestamp / SECONDS_PER_DAY + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(all(test, feature = "csv"))]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{ActivityReport, Period};
+    use crate::{Accounts, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn aggregate_activity() {
+        // 2024-02-29T12:00:00Z, then 2024-03-01T00:00:00Z.
+        let (leap_day, next_day) = (1_709_208_000, 1_709_251_200);
+        let days = Arc::new(ActivityReport::new(Period::Day));
+        let months = Arc::new(ActivityReport::new(Period::Month));
+        let mut accounts = Accounts::default()
+            .with_observer(days.clone())
+            .with_observer(months.clone());
+        accounts
+            .apply_transaction_iter(
+                [
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false)
+                        .with_timestamp(leap_day),
+                    Transaction::new(TransactionType::Deposit, 2, 2, Some(1.0), false)
+                        .with_timestamp(leap_day),
+                    Transaction::new(TransactionType::Withdrawal, 2, 3, Some(0.5), false)
+                        .with_timestamp(next_day),
+                    Transaction::new(TransactionType::Dispute, 1, 1, None, false)
+                        .with_timestamp(next_day),
+                    Transaction::new(TransactionType::Chargeback, 1, 1, None, false)
+                        .with_timestamp(next_day),
+                    Transaction::new(TransactionType::Deposit, 2, 4, Some(3.0), false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+
+        let mut wrt = vec![];
+        days.to_csv(&mut wrt).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&wrt).unwrap(),
+            "\
+period,client,deposited,withdrawn,disputed,charged_back
+2024-02-29,1,2.0,0.0,0.0,0.0
+2024-02-29,2,1.0,0.0,0.0,0.0
+2024-03-01,1,0.0,0.0,2.0,2.0
+2024-03-01,2,0.0,0.5,0.0,0.0
+"
+        );
+        let months = months.activity();
+        assert_eq!(months.len(), 4);
+        assert_eq!(
+            (months[0].period.as_str(), months[0].deposited),
+            ("2024-02", 2.0)
+        );
+    }
+}
@@ -0,0 +1,125 @@
+#[cfg(feature = "csv")]
+use std::io::Write;
+
+use serde::Serialize;
+
+#[cfg(feature = "csv")]
+use super::account::AccountError;
+use super::{
+    account::{serialize_to_decimal_precision, Accounts},
+    interest::SECONDS_PER_DAY,
+    types::{ClientId, Timestamp},
+};
+
+/// Funds held by the disputes of an account, by how long ago the disputes began.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct AgedHeldFunds {
+    pub client: ClientId,
+    /// Held by disputes which began less than seven days ago.
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub up_to_7_days: f64,
+    /// Held by disputes which began seven to thirty days ago.
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub up_to_30_days: f64,
+    /// Held by disputes which began thirty days ago or more.
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub over_30_days: f64,
+    /// Held by disputes without a timestamp.
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub undated: f64,
+}
+
+/// Aging of the funds held by ongoing disputes, per account, ordered by client id.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct HeldFundsAging(pub Vec<AgedHeldFunds>);
+
+impl HeldFundsAging {
+    #[cfg(feature = "csv")]
+    pub fn to_csv<W: Write>(&self, wrt: W) -> Result<(), AccountError> {
+        let mut wrt = csv::Writer::from_writer(wrt);
+        for aged in &self.0 {
+            wrt.serialize(aged)?;
+        }
+        wrt.flush()?;
+        Ok(())
+    }
+}
+
+impl Accounts {
+    /// Age the funds held by the ongoing disputes of each account as of `now`, as per the
+    /// timestamps of the disputes, leaving out accounts without any.
+    pub fn held_funds_aging(&self, now: Timestamp) -> HeldFundsAging {
+        HeldFundsAging(
+            self.iter_sorted()
+                .filter_map(|acc| {
+                    let mut aged = AgedHeldFunds {
+                        client: *acc.client(),
+                        ..AgedHeldFunds::default()
+                    };
+                    let mut disputed = acc.history().filter(|tx| *tx.disputed()).peekable();
+                    disputed.peek()?;
+                    for tx in disputed {
+                        let bucket = match tx.disputed_at() {
+                            None => &mut aged.undated,
+                            Some(at) => match now.saturating_sub(*at) / SECONDS_PER_DAY {
+                                0..7 => &mut aged.up_to_7_days,
+                                7..30 => &mut aged.up_to_30_days,
+                                _ => &mut aged.over_30_days,
+                            },
+                        };
+                        *bucket += tx.amount();
+                    }
+                    Some(aged)
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "csv"))]
+mod tests {
+    use crate::{interest::SECONDS_PER_DAY, Accounts, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn age_held_funds() {
+        let now = 100 * SECONDS_PER_DAY;
+        let days_ago = |days| now - days * SECONDS_PER_DAY;
+        let accounts = Accounts::from_transaction_iter(
+            [
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+                Transaction::new(TransactionType::Deposit, 1, 2, Some(2.0), false),
+                Transaction::new(TransactionType::Deposit, 1, 3, Some(4.0), false),
+                Transaction::new(TransactionType::Deposit, 1, 4, Some(8.0), false),
+                Transaction::new(TransactionType::Deposit, 2, 5, Some(1.0), false),
+                Transaction::new(TransactionType::Deposit, 3, 6, Some(1.0), false),
+                Transaction::new(TransactionType::Dispute, 1, 1, None, false)
+                    .with_timestamp(days_ago(40)),
+                Transaction::new(TransactionType::Dispute, 1, 2, None, false)
+                    .with_timestamp(days_ago(10)),
+                Transaction::new(TransactionType::Dispute, 1, 3, None, false)
+                    .with_timestamp(days_ago(1)),
+                Transaction::new(TransactionType::Dispute, 1, 4, None, false),
+                Transaction::new(TransactionType::Dispute, 2, 5, None, false)
+                    .with_timestamp(days_ago(7)),
+                Transaction::new(TransactionType::Dispute, 3, 6, None, false)
+                    .with_timestamp(days_ago(50)),
+                Transaction::new(TransactionType::Resolve, 3, 6, None, false),
+            ]
+            .into_iter()
+            .map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap();
+
+        let mut wrt = vec![];
+        accounts.held_funds_aging(now).to_csv(&mut wrt).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&wrt).unwrap(),
+            "\
+client,up_to_7_days,up_to_30_days,over_30_days,undated
+1,4.0,2.0,1.0,8.0
+2,0.0,1.0,0.0,0.0
+"
+        );
+    }
+}
@@ -0,0 +1,366 @@
+use std::{
+    fmt::{self, Debug, Display},
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+#[cfg(feature = "decimal")]
+use rust_decimal::{
+    prelude::{FromPrimitive, ToPrimitive},
+    Decimal,
+};
+use serde::{
+    de::{self, DeserializeOwned, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use super::{
+    account::{truncate_to_decimal_precision, DECIMAL_PRECISION},
+    policy::PrecisionPolicy,
+    transaction::TransactionError,
+};
+
+/// Numeric type amounts are parsed into and balances tracked with: `f64` by default,
+/// `MinorUnits` for exact arithmetic, or `rust_decimal::Decimal` with the `decimal` feature
+/// enabled. Downstream crates can implement it for their own numeric types. Policies, fees
+/// and interest rates are expressed as `f64` regardless, their results being converted to
+/// the amount type.
+pub trait Amount:
+    Copy
+    + Debug
+    + Default
+    + Display
+    + FromStr
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + Serialize
+    + DeserializeOwned
+    + Send
+    + Sync
+    + 'static
+{
+    const ZERO: Self;
+
+    /// Amount nearest to `amount`.
+    fn from_f64(amount: f64) -> Self;
+
+    fn to_f64(self) -> f64;
+
+    /// Sum of the amounts, or `None` if it overflows.
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+
+    /// Difference of the amounts, or `None` if it overflows.
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+
+    /// Whether the amount is neither NaN nor infinite.
+    fn is_finite(self) -> bool {
+        true
+    }
+
+    /// Amount truncated to four decimal places, as output.
+    fn truncate(self) -> Self {
+        self
+    }
+}
+
+/// Minor units per unit, i.e. amounts are tracked in ten-thousandths.
+const SCALE: i64 = 10i64.pow(DECIMAL_PRECISION as u32);
+
+/// Largest magnitude of `f64` amounts still represented to four decimal places, about 900
+/// billion, beyond which arithmetic silently loses precision.
+const MAX_EXACT_F64: f64 = (1u64 << f64::MANTISSA_DIGITS) as f64 / SCALE as f64;
+
+impl Amount for f64 {
+    const ZERO: Self = 0.0;
+
+    fn from_f64(amount: f64) -> Self {
+        amount
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    /// Sum of the amounts, or `None` if not finite or beyond `MAX_EXACT_F64`.
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        Some(self + rhs).filter(|sum| sum.abs() <= MAX_EXACT_F64)
+    }
+
+    fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.checked_add(-rhs)
+    }
+
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+
+    fn truncate(self) -> Self {
+        truncate_to_decimal_precision(self)
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl Amount for Decimal {
+    const ZERO: Self = Decimal::ZERO;
+
+    fn from_f64(amount: f64) -> Self {
+        <Decimal as FromPrimitive>::from_f64(amount).unwrap_or_default()
+    }
+
+    fn to_f64(self) -> f64 {
+        <Decimal as ToPrimitive>::to_f64(&self).unwrap_or_default()
+    }
+
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        Decimal::checked_add(self, rhs)
+    }
+
+    fn checked_sub(self, rhs: Self) -> Option<Self> {
+        Decimal::checked_sub(self, rhs)
+    }
+
+    fn truncate(self) -> Self {
+        self.trunc_with_scale(DECIMAL_PRECISION as u32)
+    }
+}
+
+/// Amount in ten-thousandths, i.e. with the four decimal places supported, tracked as an
+/// integer so that arithmetic is exact and reproducible across platforms. Amounts are parsed
+/// exactly from decimal strings, rejecting those with more than four decimal places, and
+/// output like `f64` ones, e.g. `1.5`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct MinorUnits(pub i64);
+
+impl Amount for MinorUnits {
+    const ZERO: Self = Self(0);
+
+    fn from_f64(amount: f64) -> Self {
+        Self((amount * SCALE as f64).round() as i64)
+    }
+
+    fn to_f64(self) -> f64 {
+        // Exact up to 2^53 minor units, the division being correctly rounded.
+        self.0 as f64 / SCALE as f64
+    }
+
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+}
+
+impl Add for MinorUnits {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Sub for MinorUnits {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl AddAssign for MinorUnits {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for MinorUnits {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for MinorUnits {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl FromStr for MinorUnits {
+    type Err = TransactionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TransactionError::InvalidField("amount", s.to_string());
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if (whole.is_empty() && fraction.is_empty())
+            || fraction.len() > DECIMAL_PRECISION as usize
+            || !(whole.bytes().chain(fraction.bytes())).all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let parse = |digits: &str| match digits {
+            "" => Some(0),
+            _ => digits.parse::<i64>().ok(),
+        };
+        let padding = 10i64.pow((DECIMAL_PRECISION as usize - fraction.len()) as u32);
+        let units = parse(whole)
+            .and_then(|whole| whole.checked_mul(SCALE))
+            .zip(parse(fraction))
+            .and_then(|(whole, fraction)| whole.checked_add(fraction * padding))
+            .ok_or_else(invalid)?;
+        Ok(Self(if negative { -units } else { units }))
+    }
+}
+
+impl Display for MinorUnits {
+    /// Format the amount with as few decimal places as needed, but at least one.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let (whole, fraction) = (
+            self.0.unsigned_abs() / SCALE as u64,
+            self.0.unsigned_abs() % SCALE as u64,
+        );
+        let fraction = format!("{fraction:0width$}", width = DECIMAL_PRECISION as usize);
+        let fraction = match fraction.trim_end_matches('0') {
+            "" => "0",
+            fraction => fraction,
+        };
+        write!(f, "{sign}{whole}.{fraction}")
+    }
+}
+
+impl Serialize for MinorUnits {
+    /// Serialize the amount as the `f64` nearest to it, whose shortest representation has the
+    /// same digits for amounts up to hundreds of billions.
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_f64(self.to_f64())
+    }
+}
+
+impl<'de> Deserialize<'de> for MinorUnits {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        de.deserialize_any(MinorUnitsVisitor)
+    }
+}
+
+struct MinorUnitsVisitor;
+
+impl Visitor<'_> for MinorUnitsVisitor {
+    type Value = MinorUnits;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an amount with at most four decimal places")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        v.checked_mul(SCALE)
+            .map(MinorUnits)
+            .ok_or_else(|| E::custom(format!("amount out of range: {v}")))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        i64::try_from(v)
+            .map_err(|_| E::custom(format!("amount out of range: {v}")))
+            .and_then(|v| self.visit_i64(v))
+    }
+
+    /// Numbers parsed as `f64` by the deserializer, e.g. with a fractional part in csv input,
+    /// are exactly recovered when they have at most fifteen significant digits.
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        if !v.is_finite() || v.abs() >= (i64::MAX / SCALE) as f64 {
+            return Err(E::custom(format!("amount out of range: {v}")));
+        }
+        PrecisionPolicy::Reject
+            .apply(v)
+            .map(MinorUnits::from_f64)
+            .ok_or_else(|| E::custom(format!("amount with more than four decimal places: {v}")))
+    }
+}
+
+#[cfg(all(test, any(feature = "csv", feature = "decimal")))]
+mod tests {
+    use crate::{Accounts, Strictness};
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn track_minor_units() {
+        use std::fs;
+
+        use super::MinorUnits;
+        use crate::TransactionsCsv;
+
+        assert_eq!("1.5".parse::<MinorUnits>().unwrap(), MinorUnits(15000));
+        assert_eq!("-.0001".parse::<MinorUnits>().unwrap(), MinorUnits(-1));
+        for invalid in ["1.00001", "1e3", "", ".", "1.5x"] {
+            assert!(invalid.parse::<MinorUnits>().is_err());
+        }
+        assert_eq!(MinorUnits(-15000).to_string(), "-1.5");
+        assert_eq!(MinorUnits(1).to_string(), "0.0001");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("minor-units.csv");
+        fs::write(
+            &path,
+            "type,client,tx,amount\ndeposit,1,1,0.1\ndeposit,1,2,0.2\nwithdrawal,1,3,0.3\n",
+        )
+        .unwrap();
+        let mut transactions = TransactionsCsv::from_csv(path.to_str().unwrap()).unwrap();
+        let exact = Accounts::<MinorUnits>::from_transaction_iter(
+            transactions.iter_as(),
+            Strictness::Strict,
+        )
+        .unwrap();
+        assert_eq!(*exact.get(1).unwrap().total(), MinorUnits(0));
+        let mut transactions = TransactionsCsv::from_csv(path.to_str().unwrap()).unwrap();
+        let drifted =
+            Accounts::from_transaction_iter(transactions.iter(), Strictness::Strict).unwrap();
+        assert_ne!(*drifted.get(1).unwrap().total(), 0.0);
+
+        let mut wrt = vec![];
+        exact.to_csv_writer(&mut wrt).unwrap();
+        assert_eq!(
+            String::from_utf8(wrt).unwrap(),
+            "client,available,held,total,locked\n1,0.0,0.0,0.0,false\n"
+        );
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn track_decimal_amounts() {
+        use rust_decimal::Decimal;
+
+        use super::Amount;
+        use crate::{Transaction, TransactionType};
+
+        let decimal = |amount: &str| amount.parse::<Decimal>().unwrap();
+        let transactions = [
+            (TransactionType::Deposit, "0.1"),
+            (TransactionType::Deposit, "0.2"),
+            (TransactionType::Withdrawal, "0.3"),
+        ];
+        let accounts = Accounts::from_transaction_iter(
+            (transactions.into_iter().zip(1..)).map(|((type_, amount), tx)| {
+                Ok(Transaction::new(type_, 1, tx, Some(decimal(amount)), false))
+            }),
+            Strictness::Strict,
+        )
+        .unwrap();
+        assert_eq!(*accounts.get(1).unwrap().total(), Decimal::ZERO);
+        assert_eq!(Decimal::from_f64(0.1), decimal("0.1"));
+        assert_eq!(decimal("1.23456").truncate(), decimal("1.2345"));
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::VecDeque;
+#[cfg(feature = "csv")]
+use std::io::Write;
+
+#[cfg(feature = "csv")]
+use csv::Error as CsvError;
+use serde::Serialize;
+
+use super::{
+    account::serialize_to_decimal_precision,
+    amount::Amount,
+    transaction::{Transaction, TransactionType},
+    types::{ClientId, Timestamp, TransactionId},
+};
+
+/// Number of the latest transactions of a client `ClientStats` are computed over.
+pub const STATS_WINDOW: usize = 100;
+
+/// Rolling statistics of the amounts and frequency of the latest stored transactions of a
+/// client, e.g. deposits and withdrawals, fed to `AnomalyDetector`s.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClientStats {
+    /// Timestamps and amounts of the latest transactions, up to `STATS_WINDOW` of them.
+    recent: VecDeque<(Option<Timestamp>, f64)>,
+}
+
+impl ClientStats {
+    pub(crate) fn record(&mut self, timestamp: Option<Timestamp>, amount: f64) {
+        if self.recent.len() == STATS_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((timestamp, amount));
+    }
+
+    pub fn count(&self) -> usize {
+        self.recent.len()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (!self.recent.is_empty()).then(|| self.amounts().sum::<f64>() / self.recent.len() as f64)
+    }
+
+    /// Population standard deviation of the amounts.
+    pub fn std_dev(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        let variance = self
+            .amounts()
+            .map(|amount| (amount - mean).powi(2))
+            .sum::<f64>()
+            / self.recent.len() as f64;
+        Some(variance.sqrt())
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.amounts().reduce(f64::max)
+    }
+
+    /// Average number of transactions per day, over the time spanned by the timestamped
+    /// ones, if they span any.
+    pub fn daily_frequency(&self) -> Option<f64> {
+        let mut timestamps = self.recent.iter().filter_map(|(timestamp, _)| *timestamp);
+        let first = timestamps.next()?;
+        let (count, last) = timestamps.fold((1, first), |(count, _), last| (count + 1, last));
+        let days = last.checked_sub(first).filter(|span| *span > 0)? as f64 / 86_400.0;
+        Some(f64::from(count) / days)
+    }
+
+    fn amounts(&self) -> impl Iterator<Item = f64> + '_ {
+        self.recent.iter().map(|(_, amount)| *amount)
+    }
+}
+
+/// Hook invoked before applying each deposit, withdrawal or other stored transaction, with
+/// the rolling statistics of the client, returning the reason to flag the transaction as
+/// anomalous, if any. Flagged transactions are withheld for review instead of being applied.
+pub trait AnomalyDetector<A: Amount = f64>: Send {
+    fn detect(&self, stats: &ClientStats, tx: &Transaction<A>) -> Option<String>;
+}
+
+impl<A: Amount, F: Fn(&ClientStats, &Transaction<A>) -> Option<String> + Send> AnomalyDetector<A>
+    for F
+{
+    fn detect(&self, stats: &ClientStats, tx: &Transaction<A>) -> Option<String> {
+        self(stats, tx)
+    }
+}
+
+/// Detector flagging amounts deviating from the mean amount of the client by more than a
+/// number of standard deviations, once enough transactions of the client were seen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZScoreDetector {
+    pub threshold: f64,
+    pub min_samples: usize,
+}
+
+impl Default for ZScoreDetector {
+    fn default() -> Self {
+        Self {
+            threshold: 3.0,
+            min_samples: 10,
+        }
+    }
+}
+
+impl<A: Amount> AnomalyDetector<A> for ZScoreDetector {
+    fn detect(&self, stats: &ClientStats, tx: &Transaction<A>) -> Option<String> {
+        if stats.count() < self.min_samples {
+            return None;
+        }
+        let (mean, std_dev) = (stats.mean()?, stats.std_dev()?);
+        let amount = tx.amount().to_f64();
+        ((amount - mean).abs() > self.threshold * std_dev).then(|| {
+            format!(
+                "amount deviates from the mean of {mean:.4} by more than {} standard deviations \
+                 of {std_dev:.4}",
+                self.threshold
+            )
+        })
+    }
+}
+
+/// Transaction flagged by an `AnomalyDetector`, and withheld.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Anomaly {
+    #[serde(rename = "type")]
+    pub type_: TransactionType,
+    pub client: ClientId,
+    pub tx: TransactionId,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub amount: f64,
+    pub reason: String,
+}
+
+#[cfg(feature = "csv")]
+pub fn anomalies_to_csv<W: Write>(anomalies: &[Anomaly], wrt: W) -> Result<(), CsvError> {
+    let mut wrt = csv::Writer::from_writer(wrt);
+    for anomaly in anomalies {
+        wrt.serialize(anomaly)?;
+    }
+    wrt.flush()?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "csv"))]
+mod tests {
+    use super::{anomalies_to_csv, ClientStats, ZScoreDetector};
+    use crate::{Accounts, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn withhold_anomalies() {
+        let deposit = |tx, amount, timestamp| {
+            Transaction::new(TransactionType::Deposit, 1, tx, Some(amount), false)
+                .with_timestamp(timestamp)
+        };
+        let mut accounts = Accounts::default()
+            .with_anomaly_detector(ZScoreDetector {
+                threshold: 3.0,
+                min_samples: 4,
+            })
+            .with_anomaly_detector(|stats: &ClientStats, _: &Transaction| {
+                stats
+                    .daily_frequency()
+                    .filter(|frequency| *frequency > 100.0)
+                    .map(|_| "too frequent".to_string())
+            });
+        accounts
+            .apply_transaction_iter(
+                [
+                    deposit(1, 10.0, 0),
+                    deposit(2, 12.0, 86_400),
+                    deposit(3, 11.0, 2 * 86_400),
+                    deposit(4, 9.0, 3 * 86_400),
+                    deposit(5, 500.0, 4 * 86_400),
+                    deposit(6, 10.0, 5 * 86_400),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+        assert_eq!(*accounts.get(1).unwrap().total(), 52.0);
+        assert_eq!(accounts.report().rejected, 0);
+        assert_eq!(accounts.report().withheld, 1);
+
+        let mut csv = vec![];
+        anomalies_to_csv(accounts.anomalies(), &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "\
+type,client,tx,amount,reason
+deposit,1,5,500.0,amount deviates from the mean of 10.5000 by more than 3 standard deviations of 1.1180
+"
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use super::{account::Account, amount::Amount, transaction::Transaction};
+
+/// Immediate outcome of a dispute, as decided by a `DisputeArbiter`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DisputeDecision {
+    /// Keep the dispute open, holding the disputed funds until resolved or charged back.
+    Hold,
+    /// Resolve the dispute right away.
+    Resolve,
+    /// Charge the disputed transaction back right away.
+    Chargeback,
+}
+
+/// Hook invoked when a dispute is opened, with the account and the disputed transaction,
+/// e.g. to wire a dispute-management system into the engine. Disputes decided right away
+/// are resolved or charged back by a transaction of the corresponding type, with the id
+/// and timestamp of the dispute, applied as any other.
+pub trait DisputeArbiter<A: Amount = f64>: Send {
+    fn arbitrate(&self, account: &Account<A>, disputed: &Transaction<A>) -> DisputeDecision;
+}
+
+impl<A: Amount, F: Fn(&Account<A>, &Transaction<A>) -> DisputeDecision + Send> DisputeArbiter<A>
+    for F
+{
+    fn arbitrate(&self, account: &Account<A>, disputed: &Transaction<A>) -> DisputeDecision {
+        self(account, disputed)
+    }
+}
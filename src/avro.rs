@@ -0,0 +1,163 @@
+use std::io::{Read, Write};
+
+use apache_avro::{from_value, Reader, Schema, Writer};
+
+use super::{
+    account::{AccountError, AccountWithFees, Accounts},
+    transaction::{Transaction, TransactionError, Transactions},
+};
+
+/// Schema of transactions, with the same fields as the csv input.
+pub const TRANSACTION_SCHEMA: &str = r#"{
+    "type": "record",
+    "name": "Transaction",
+    "fields": [
+        {
+            "name": "type",
+            "type": {
+                "type": "enum",
+                "name": "TransactionType",
+                "symbols": [
+                    "deposit", "withdrawal", "dispute", "resolve", "chargeback",
+                    "representment", "unlock", "refund", "auth", "capture", "void", "interest",
+                    "hold", "release", "adjustment", "reversal", "convert"
+                ]
+            }
+        },
+        {"name": "client", "type": "int"},
+        {"name": "tx", "type": "long"},
+        {"name": "amount", "type": ["null", "double"], "default": null},
+        {"name": "timestamp", "type": ["null", "long"], "default": null}
+    ]
+}"#;
+
+/// Schema of accounts, with the same fields as the csv output.
+pub const ACCOUNT_SCHEMA: &str = r#"{
+    "type": "record",
+    "name": "Account",
+    "fields": [
+        {"name": "client", "type": "int"},
+        {"name": "available", "type": "double"},
+        {"name": "held", "type": "double"},
+        {"name": "total", "type": "double"},
+        {"name": "locked", "type": "boolean"}
+    ]
+}"#;
+
+/// Schema of accounts extended with the fees charged, used when a fee schedule is configured.
+pub const ACCOUNT_WITH_FEES_SCHEMA: &str = r#"{
+    "type": "record",
+    "name": "AccountWithFees",
+    "fields": [
+        {"name": "client", "type": "int"},
+        {"name": "available", "type": "double"},
+        {"name": "held", "type": "double"},
+        {"name": "total", "type": "double"},
+        {"name": "locked", "type": "boolean"},
+        {"name": "fees", "type": "double"}
+    ]
+}"#;
+
+impl Transactions {
+    /// Read transactions from an avro object container file, decoded with the schema
+    /// embedded in the file, which must be compatible with `TRANSACTION_SCHEMA`.
+    pub fn from_avro<R: Read>(rdr: R) -> Result<Self, TransactionError> {
+        Reader::new(rdr)?
+            .map(|value| Ok(from_value::<Transaction>(&value?)?))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl Accounts {
+    /// Write accounts to `wrt` as an avro object container file embedding its schema,
+    /// ordered by client id.
+    pub fn to_avro<W: Write>(&self, wrt: W) -> Result<(), AccountError> {
+        if self.config().fees.is_some() {
+            let schema = Schema::parse_str(ACCOUNT_WITH_FEES_SCHEMA)?;
+            let mut wrt = Writer::new(&schema, wrt)?;
+            for acc in self.iter_sorted() {
+                wrt.append_ser(AccountWithFees::from(acc))?;
+            }
+            wrt.flush()?;
+        } else {
+            let schema = Schema::parse_str(ACCOUNT_SCHEMA)?;
+            let mut wrt = Writer::new(&schema, wrt)?;
+            for acc in self.iter_sorted() {
+                wrt.append_ser(acc)?;
+            }
+            wrt.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apache_avro::{
+        types::{Record, Value},
+        Reader, Schema, Writer,
+    };
+
+    use super::TRANSACTION_SCHEMA;
+    use crate::{Accounts, Strictness, Transaction, TransactionType, Transactions};
+
+    #[test]
+    fn read_transactions() {
+        let schema = Schema::parse_str(TRANSACTION_SCHEMA).unwrap();
+        let mut wrt = Writer::new(&schema, vec![]).unwrap();
+        for (symbol, type_, amount) in [(0, "deposit", Some(2.0)), (2, "dispute", None)] {
+            let mut record = Record::new(&schema).unwrap();
+            record.put("type", Value::Enum(symbol, type_.to_string()));
+            record.put("client", 1);
+            record.put("tx", Value::Long(1));
+            record.put("amount", amount);
+            record.put("timestamp", None::<i64>);
+            wrt.append_value(record).unwrap();
+        }
+
+        let transactions = Transactions::from_avro(&wrt.into_inner().unwrap()[..]).unwrap();
+        assert_eq!(
+            transactions,
+            Transactions(vec![
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false),
+                Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+            ])
+        );
+    }
+
+    #[test]
+    fn write_accounts() {
+        let accounts = Accounts::from_transaction_iter(
+            vec![Transaction::new(
+                TransactionType::Deposit,
+                1,
+                1,
+                Some(1.123456),
+                false,
+            )]
+            .into_iter()
+            .map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap();
+
+        let mut wrt = vec![];
+        accounts.to_avro(&mut wrt).unwrap();
+        let values = Reader::new(&wrt[..])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![Value::Record(vec![
+                ("client".to_string(), Value::Int(1)),
+                ("available".to_string(), Value::Double(1.1234)),
+                ("held".to_string(), Value::Double(0.0)),
+                ("total".to_string(), Value::Double(1.1234)),
+                ("locked".to_string(), Value::Boolean(false)),
+            ])]
+        );
+    }
+}
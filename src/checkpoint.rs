@@ -0,0 +1,231 @@
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    account::{Account, AccountError, AccountState, Accounts},
+    policy::Strictness,
+    report::ProcessingReport,
+    transaction::TransactionsCsv,
+};
+
+const VERSION: u32 = 1;
+
+#[derive(Deserialize, Serialize)]
+struct CheckpointState {
+    version: u32,
+    applied_files: BTreeSet<String>,
+    report: ProcessingReport,
+    accounts: Vec<AccountState>,
+    #[serde(default)]
+    dedup_keys: BTreeSet<String>,
+}
+
+/// Accounts persisted across runs, e.g. to process daily files against the state left by the
+/// previous day, along with the names of the files applied to them so that a file is never
+/// applied twice. Besides balances, the complete state of accounts is persisted, so that
+/// transactions of previous runs can still be disputed, and the keys of the deduplication
+/// store, if any, so that transactions applied after the checkpoint are applied again once
+/// re-delivered, rather than skipped. Risk flags aren't retained.
+pub struct Checkpoint {
+    accounts: Accounts,
+    applied_files: BTreeSet<String>,
+}
+
+impl Checkpoint {
+    /// Start from `accounts`, with no file applied yet.
+    pub fn new(accounts: Accounts) -> Self {
+        Self {
+            accounts,
+            applied_files: BTreeSet::new(),
+        }
+    }
+
+    /// Restore the checkpoint saved at `path` into `accounts`, which provide the configuration
+    /// and hooks to keep processing with.
+    pub fn load<P: AsRef<Path>>(path: P, mut accounts: Accounts) -> Result<Self, AccountError> {
+        let state: CheckpointState = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        if state.version != VERSION {
+            return Err(AccountError::CheckpointVersion(state.version));
+        }
+        *accounts.report_mut() = state.report;
+        if let Some(dedup) = accounts.dedup_mut() {
+            for key in &state.dedup_keys {
+                dedup.insert(key)?;
+            }
+        }
+        accounts.clear();
+        accounts.extend(state.accounts.into_iter().map(|state| {
+            let acc = Account::from(state);
+            (*acc.client(), acc)
+        }));
+        accounts.reset_tx_index();
+        Ok(Self {
+            accounts,
+            applied_files: state.applied_files,
+        })
+    }
+
+    /// Save the checkpoint to `path`, atomically replacing any previous one once written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), AccountError> {
+        write(path.as_ref(), &self.applied_files, &self.accounts)
+    }
+
+    /// Save `accounts` as a checkpoint without any applied file to `path`, atomically
+    /// replacing any previous one once written, e.g. to persist the state of stream
+    /// consumers, resumed with `Checkpoint::load`.
+    pub fn save_accounts<P: AsRef<Path>>(accounts: &Accounts, path: P) -> Result<(), AccountError> {
+        write(path.as_ref(), &BTreeSet::new(), accounts)
+    }
+
+    pub fn accounts(&self) -> &Accounts {
+        &self.accounts
+    }
+
+    pub fn accounts_mut(&mut self) -> &mut Accounts {
+        &mut self.accounts
+    }
+
+    pub fn into_accounts(self) -> Accounts {
+        self.accounts
+    }
+
+    /// Whether a file with the same name as the one at `path`, which may be a url, was
+    /// already applied.
+    pub fn is_applied(&self, path: &str) -> bool {
+        self.applied_files.contains(file_name(path))
+    }
+
+    /// Record the file at `path` as applied.
+    pub fn mark_applied(&mut self, path: &str) {
+        self.applied_files.insert(file_name(path).to_string());
+    }
+
+    /// Apply the transactions of the csv file at `path` as in non-strict processing, unless
+    /// a file with the same name was already applied. Returns whether the file was applied.
+    pub fn apply_csv(&mut self, path: &str) -> Result<bool, AccountError> {
+        if self.is_applied(path) {
+            return Ok(false);
+        }
+        let mut transactions = TransactionsCsv::from_csv(path)?;
+        self.accounts
+            .apply_transaction_iter(transactions.iter(), Strictness::Lenient)?;
+        self.mark_applied(path);
+        Ok(true)
+    }
+}
+
+/// Write the state of `accounts`, along with `applied_files`, to `path`, through a temporary
+/// file renamed once synced.
+fn write(
+    path: &Path,
+    applied_files: &BTreeSet<String>,
+    accounts: &Accounts,
+) -> Result<(), AccountError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut wrt = BufWriter::new(File::create(&tmp)?);
+    serde_json::to_writer(
+        &mut wrt,
+        &CheckpointState {
+            version: VERSION,
+            applied_files: applied_files.clone(),
+            report: accounts.report().clone(),
+            accounts: accounts.values().map(AccountState::from).collect(),
+            dedup_keys: (accounts.dedup())
+                .map(|dedup| dedup.keys().map(str::to_string).collect())
+                .unwrap_or_default(),
+        },
+    )?;
+    wrt.flush()?;
+    wrt.get_ref().sync_all()?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::once;
+
+    use super::Checkpoint;
+    use crate::{Accounts, MemoryDedupStore, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn resume_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        let mut checkpoint = Checkpoint::new(Accounts::default());
+        assert!(checkpoint.apply_csv("src/test_utils/test_txs.csv").unwrap());
+        checkpoint
+            .accounts_mut()
+            .apply_transaction_iter(
+                once(Ok(Transaction::new(
+                    TransactionType::Deposit,
+                    1,
+                    3,
+                    Some(1.0),
+                    false,
+                ))),
+                Strictness::Lenient,
+            )
+            .unwrap();
+        checkpoint.save(&path).unwrap();
+
+        let mut checkpoint = Checkpoint::load(&path, Accounts::default()).unwrap();
+        assert!(!checkpoint.apply_csv("src/test_utils/test_txs.csv").unwrap());
+        assert_eq!(checkpoint.accounts().report().deposits, 2);
+        // Transactions of the previous run can still be disputed.
+        let accounts = checkpoint.accounts_mut();
+        accounts
+            .apply_transaction_iter(
+                once(Ok(Transaction::new(
+                    TransactionType::Dispute,
+                    1,
+                    3,
+                    None,
+                    false,
+                ))),
+                Strictness::Strict,
+            )
+            .unwrap();
+        let account = accounts.get(1).unwrap();
+        assert_eq!((*account.available(), *account.held()), (0.5, 1.0));
+    }
+
+    #[test]
+    fn redeliver_transactions_applied_after_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        let deposit = |tx| Transaction::new(TransactionType::Deposit, 1, tx, Some(1.0), false);
+        let mut accounts = Accounts::default().with_dedup(MemoryDedupStore::default());
+        accounts
+            .apply_transaction_iter(once(Ok(deposit(1))), Strictness::Lenient)
+            .unwrap();
+        Checkpoint::save_accounts(&accounts, &path).unwrap();
+        // Crash after applying a transaction, before the next checkpoint.
+        accounts
+            .apply_transaction_iter(once(Ok(deposit(2))), Strictness::Lenient)
+            .unwrap();
+        drop(accounts);
+
+        let accounts = Accounts::default().with_dedup(MemoryDedupStore::default());
+        let mut accounts = Checkpoint::load(&path, accounts).unwrap().into_accounts();
+        accounts
+            .apply_transaction_iter(
+                [deposit(1), deposit(2)].into_iter().map(Ok),
+                Strictness::Lenient,
+            )
+            .unwrap();
+        assert_eq!(*accounts.get(1).unwrap().total(), 2.0);
+        assert_eq!(accounts.report().duplicates, 1);
+    }
+}
@@ -0,0 +1,245 @@
+use std::sync::Arc;
+#[cfg(feature = "parquet")]
+use std::{fs::File, path::Path};
+
+use arrow_array::{
+    cast::AsArray,
+    types::{Float64Type, UInt16Type, UInt32Type, UInt64Type},
+    Array, ArrayRef, BooleanArray, Decimal128Array, RecordBatch, UInt16Array,
+};
+use arrow_cast::cast;
+use arrow_schema::{ArrowError, DataType, Field, Schema, DECIMAL128_MAX_PRECISION};
+#[cfg(feature = "parquet")]
+use parquet::arrow::ArrowWriter;
+
+use super::{
+    account::{truncate_to_decimal_precision, Account, AccountError, Accounts, DECIMAL_PRECISION},
+    transaction::{Transaction, TransactionError, Transactions},
+};
+
+/// Convert an amount to a decimal with `DECIMAL_PRECISION` decimal places.
+fn to_decimal(num: f64) -> i128 {
+    (truncate_to_decimal_precision(num) * 10.0f64.powi(DECIMAL_PRECISION)).round() as i128
+}
+
+fn decimal_column<'a>(
+    accounts: &[&'a Account],
+    field: impl Fn(&'a Account) -> &'a f64,
+) -> Result<ArrayRef, AccountError> {
+    let array = accounts
+        .iter()
+        .map(|acc| to_decimal(*field(acc)))
+        .collect::<Decimal128Array>()
+        .with_precision_and_scale(DECIMAL128_MAX_PRECISION, DECIMAL_PRECISION as i8)?;
+    Ok(Arc::new(array))
+}
+
+impl Accounts {
+    /// Convert accounts to an arrow record batch, ordered by client id, with the same
+    /// columns as the csv output and amounts as decimals.
+    pub fn to_record_batch(&self) -> Result<RecordBatch, AccountError> {
+        let decimal = DataType::Decimal128(DECIMAL128_MAX_PRECISION, DECIMAL_PRECISION as i8);
+        let accounts = self.iter_sorted().collect::<Vec<_>>();
+
+        let mut fields = vec![
+            Field::new("client", DataType::UInt16, false),
+            Field::new("available", decimal.clone(), false),
+            Field::new("held", decimal.clone(), false),
+            Field::new("total", decimal.clone(), false),
+            Field::new("locked", DataType::Boolean, false),
+        ];
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt16Array::from_iter_values(
+                accounts.iter().map(|acc| *acc.client()),
+            )),
+            decimal_column(&accounts, Account::available)?,
+            decimal_column(&accounts, Account::held)?,
+            decimal_column(&accounts, Account::total)?,
+            Arc::new(BooleanArray::from_iter(
+                accounts.iter().map(|acc| Some(*acc.locked())),
+            )),
+        ];
+        if self.config().fees.is_some() {
+            fields.push(Field::new("fees", decimal, false));
+            columns.push(decimal_column(&accounts, Account::fees)?);
+        }
+
+        Ok(RecordBatch::try_new(
+            Arc::new(Schema::new(fields)),
+            columns,
+        )?)
+    }
+
+    /// Write accounts to the parquet file at `path`, ordered by client id.
+    #[cfg(feature = "parquet")]
+    pub fn to_parquet<P: AsRef<Path>>(&self, path: P) -> Result<(), AccountError> {
+        let batch = self.to_record_batch()?;
+        let mut wrt = ArrowWriter::try_new(File::create(path)?, batch.schema(), None)?;
+        wrt.write(&batch)?;
+        wrt.close()?;
+
+        Ok(())
+    }
+}
+
+/// Look up the column named `name` and cast it to `to`.
+fn column(batch: &RecordBatch, name: &str, to: &DataType) -> Result<Option<ArrayRef>, ArrowError> {
+    batch
+        .column_by_name(name)
+        .map(|col| cast(col, to))
+        .transpose()
+}
+
+fn required_column(batch: &RecordBatch, name: &str, to: &DataType) -> Result<ArrayRef, ArrowError> {
+    column(batch, name, to)?
+        .ok_or_else(|| ArrowError::SchemaError(format!("missing column: {name}")))
+}
+
+impl Transactions {
+    /// Build transactions from arrow record batches with the same columns as the csv input,
+    /// i.e. `type`, `client`, `tx` and the optional `amount` and `timestamp`. Columns are
+    /// cast to the expected types, e.g. decimal amounts to floats.
+    pub fn from_record_batches<I>(batches: I) -> Result<Self, TransactionError>
+    where
+        I: IntoIterator<Item = RecordBatch>,
+    {
+        let mut transactions = vec![];
+        for batch in batches {
+            let types = required_column(&batch, "type", &DataType::Utf8)?;
+            let types = types.as_string::<i32>();
+            let clients = required_column(&batch, "client", &DataType::UInt16)?;
+            let clients = clients.as_primitive::<UInt16Type>();
+            let txs = required_column(&batch, "tx", &DataType::UInt32)?;
+            let txs = txs.as_primitive::<UInt32Type>();
+            let amounts = column(&batch, "amount", &DataType::Float64)?;
+            let amounts = amounts
+                .as_ref()
+                .map(|col| col.as_primitive::<Float64Type>());
+            let timestamps = column(&batch, "timestamp", &DataType::UInt64)?;
+            let timestamps = timestamps
+                .as_ref()
+                .map(|col| col.as_primitive::<UInt64Type>());
+
+            for row in 0..batch.num_rows() {
+                if types.is_null(row) || clients.is_null(row) || txs.is_null(row) {
+                    return Err(ArrowError::InvalidArgumentError(format!(
+                        "missing transaction type, client or id in row {row}"
+                    ))
+                    .into());
+                }
+                let amount = amounts
+                    .filter(|col| col.is_valid(row))
+                    .map(|col| col.value(row));
+                let mut tx = Transaction::new(
+                    types.value(row).parse()?,
+                    clients.value(row),
+                    txs.value(row),
+                    amount,
+                    false,
+                );
+                if let Some(timestamps) = timestamps.filter(|col| col.is_valid(row)) {
+                    tx = tx.with_timestamp(timestamps.value(row));
+                }
+                transactions.push(tx);
+            }
+        }
+        Ok(Self(transactions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{
+        Array, ArrayRef, BooleanArray, Decimal128Array, Float64Array, Int64Array, RecordBatch,
+        StringArray, UInt16Array,
+    };
+
+    use crate::{Accounts, Strictness, Transaction, TransactionType, Transactions};
+
+    fn accounts() -> Accounts {
+        Accounts::from_transaction_iter(
+            vec![
+                Transaction::new(TransactionType::Deposit, 2, 1, Some(1.123456), false),
+                Transaction::new(TransactionType::Deposit, 1, 2, Some(10.0), false),
+                Transaction::new(TransactionType::Dispute, 1, 2, None, false),
+            ]
+            .into_iter()
+            .map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn convert_to_record_batch() {
+        let batch = accounts().to_record_batch().unwrap();
+
+        let clients = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<UInt16Array>()
+            .unwrap();
+        assert_eq!(clients.values(), &[1, 2]);
+        let available = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Decimal128Array>()
+            .unwrap();
+        assert_eq!(available.value_as_string(0), "0.0000");
+        assert_eq!(available.value_as_string(1), "1.1234");
+        let held = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<Decimal128Array>()
+            .unwrap();
+        assert_eq!(held.value_as_string(0), "10.0000");
+        let locked = batch
+            .column(4)
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .unwrap();
+        assert_eq!(locked.len(), 2);
+        assert!(!locked.value(0));
+    }
+
+    #[test]
+    fn transactions_from_record_batch() {
+        let batch = RecordBatch::try_from_iter([
+            (
+                "type",
+                Arc::new(StringArray::from(vec!["deposit", "withdrawal", "dispute"])) as ArrayRef,
+            ),
+            ("client", Arc::new(Int64Array::from(vec![1, 1, 1]))),
+            ("tx", Arc::new(Int64Array::from(vec![1, 2, 1]))),
+            (
+                "amount",
+                Arc::new(Float64Array::from(vec![Some(2.0), Some(1.5), None])),
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            Transactions::from_record_batches([batch]).unwrap(),
+            Transactions(vec![
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false),
+                Transaction::new(TransactionType::Withdrawal, 1, 2, Some(1.5), false),
+                Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+            ])
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn write_parquet() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("accounts.parquet");
+        accounts().to_parquet(&path).unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+    }
+}
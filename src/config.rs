@@ -0,0 +1,60 @@
+use super::{
+    fee::FeeSchedule,
+    fx::FxRates,
+    interest::InterestPolicy,
+    kyc::KycTiers,
+    policy::{
+        BalanceCap, BalancePolicy, DailyWithdrawalLimits, DisputeTimeout, FreezePolicy,
+        PrecisionPolicy, VelocityLimits,
+    },
+    rules::RuleSet,
+};
+
+/// Engine behaviour shared by all accounts.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Lower bound on available funds, enforced on every transaction debiting them.
+    pub balance: BalancePolicy,
+    /// Upper bound on the total funds of accounts, enforced on deposits, if any.
+    pub balance_cap: Option<BalanceCap>,
+    /// Handling of amounts with more than four decimal places.
+    pub precision: PrecisionPolicy,
+    /// Fees to charge on applied transactions, if any.
+    pub fees: Option<FeeSchedule>,
+    /// Interest to accrue on available funds, based on transaction timestamps.
+    pub interest: Option<InterestPolicy>,
+    /// Conditions upon which accounts are frozen.
+    pub freeze: FreezePolicy,
+    /// Withdrawal limits over rolling time windows, based on transaction timestamps.
+    pub velocity: VelocityLimits,
+    /// Caps on the amount withdrawn by a client per day, based on transaction timestamps.
+    pub daily_withdrawals: DailyWithdrawalLimits,
+    /// KYC tiers of clients, limiting their balance and withdrawals.
+    pub tiers: KycTiers,
+    /// Rules rejecting or flagging transactions, or freezing accounts, before applying them.
+    pub rules: RuleSet,
+    /// Check balance invariants after applying each transaction, failing on violations.
+    pub validate: bool,
+    /// Retain every transaction applied to each account, enabling point-in-time queries.
+    pub journal: bool,
+    /// Settle disputes left open for too long, as of the timestamps of later transactions.
+    pub dispute_timeout: Option<DisputeTimeout>,
+    /// Apply adjustments, crediting or debiting accounts by their signed amount, rather than
+    /// rejecting them.
+    pub allow_adjustments: bool,
+    /// Exchange rates applied by conversions between the assets of an account, if any.
+    pub fx: Option<FxRates>,
+    /// Keep the funds of disputed deposits available, tracked as provisional credit, rather
+    /// than holding them, only clawing them back on chargeback.
+    pub provisional_credit: bool,
+    /// Unlock accounts once a chargeback on them is successfully represented.
+    pub unfreeze_on_representment: bool,
+    /// Reject deposits, withdrawals and authorizations reusing the id of a transaction stored
+    /// by any account, and transactions referring to one stored by another account, e.g.
+    /// disputes.
+    pub unique_tx_ids: bool,
+    /// Number of times disputes, resolves and chargebacks referring to transactions not seen
+    /// or disputed yet are retried, after the rest of the transactions are applied, before
+    /// being rejected, e.g. for out-of-order feeds.
+    pub quarantine_retries: usize,
+}
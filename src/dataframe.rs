@@ -0,0 +1,140 @@
+use polars::prelude::{Column, DataFrame, DataType, PolarsError, PolarsResult, Series};
+
+use super::{
+    account::{truncate_to_decimal_precision, Account, Accounts},
+    transaction::{Transaction, TransactionError, Transactions},
+};
+
+impl From<&Accounts> for DataFrame {
+    /// Convert accounts to a data frame, ordered by client id, with the same columns as the
+    /// csv output and amounts truncated to four decimal places.
+    fn from(accounts: &Accounts) -> Self {
+        let sorted = accounts.iter_sorted().collect::<Vec<_>>();
+        let amounts = |name: &str, field: fn(&Account) -> &f64| {
+            Column::new(
+                name.into(),
+                sorted
+                    .iter()
+                    .map(|acc| truncate_to_decimal_precision(*field(acc)))
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        let mut columns = vec![
+            Column::new(
+                "client".into(),
+                sorted.iter().map(|acc| *acc.client()).collect::<Vec<_>>(),
+            ),
+            amounts("available", Account::available),
+            amounts("held", Account::held),
+            amounts("total", Account::total),
+            Column::new(
+                "locked".into(),
+                sorted.iter().map(|acc| *acc.locked()).collect::<Vec<_>>(),
+            ),
+        ];
+        if accounts.config().fees.is_some() {
+            columns.push(amounts("fees", Account::fees));
+        }
+
+        DataFrame::new(sorted.len(), columns).expect("columns have the same length and names")
+    }
+}
+
+/// Look up the column named `name` and cast it to `to`.
+fn column(df: &DataFrame, name: &str, to: &DataType) -> PolarsResult<Option<Series>> {
+    df.column(name)
+        .ok()
+        .map(|col| col.as_materialized_series().cast(to))
+        .transpose()
+}
+
+fn required_column(df: &DataFrame, name: &str, to: &DataType) -> PolarsResult<Series> {
+    column(df, name, to)?
+        .ok_or_else(|| PolarsError::ColumnNotFound(format!("missing column: {name}").into()))
+}
+
+impl Transactions {
+    /// Build transactions from a data frame with the same columns as the csv input, i.e.
+    /// `type`, `client`, `tx` and the optional `amount` and `timestamp`. Columns are cast to
+    /// the expected types.
+    pub fn from_dataframe(df: &DataFrame) -> Result<Self, TransactionError> {
+        let types = required_column(df, "type", &DataType::String)?;
+        let clients = required_column(df, "client", &DataType::UInt16)?;
+        let txs = required_column(df, "tx", &DataType::UInt32)?;
+        let amounts = column(df, "amount", &DataType::Float64)?;
+        let timestamps = column(df, "timestamp", &DataType::UInt64)?;
+
+        let (types, clients, txs) = (types.str()?, clients.u16()?, txs.u32()?);
+        let amounts = amounts.as_ref().map(|col| col.f64()).transpose()?;
+        let timestamps = timestamps.as_ref().map(|col| col.u64()).transpose()?;
+
+        let mut transactions = Vec::with_capacity(df.height());
+        for row in 0..df.height() {
+            let (Some(type_), Some(client), Some(tx)) =
+                (types.get(row), clients.get(row), txs.get(row))
+            else {
+                return Err(PolarsError::NoData(
+                    format!("missing transaction type, client or id in row {row}").into(),
+                )
+                .into());
+            };
+            let amount = amounts.and_then(|col| col.get(row));
+            let mut transaction = Transaction::new(type_.parse()?, client, tx, amount, false);
+            if let Some(timestamp) = timestamps.and_then(|col| col.get(row)) {
+                transaction = transaction.with_timestamp(timestamp);
+            }
+            transactions.push(transaction);
+        }
+        Ok(Self(transactions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::{df, DataFrame};
+
+    use crate::{Accounts, Strictness, Transaction, TransactionType, Transactions};
+
+    #[test]
+    fn accounts_to_dataframe() {
+        let accounts = Accounts::from_transaction_iter(
+            vec![
+                Transaction::new(TransactionType::Deposit, 2, 1, Some(1.123456), false),
+                Transaction::new(TransactionType::Deposit, 1, 2, Some(10.0), false),
+            ]
+            .into_iter()
+            .map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap();
+
+        let df = DataFrame::from(&accounts);
+        assert_eq!(df.shape(), (2, 5));
+        assert_eq!(df.column("client").unwrap().u16().unwrap().get(0), Some(1));
+        assert_eq!(
+            df.column("available").unwrap().f64().unwrap().get(1),
+            Some(1.1234)
+        );
+    }
+
+    #[test]
+    fn transactions_from_dataframe() {
+        let df = df! {
+            "type" => ["deposit", "withdrawal", "dispute"],
+            "client" => [1i64, 1, 1],
+            "tx" => [1i64, 2, 1],
+            "amount" => [Some(2.0), Some(1.5), None],
+        }
+        .unwrap();
+
+        assert_eq!(
+            Transactions::from_dataframe(&df).unwrap(),
+            Transactions(vec![
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false),
+                Transaction::new(TransactionType::Withdrawal, 1, 2, Some(1.5), false),
+                Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+            ])
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::Path,
+};
+
+use super::{account::AccountError, amount::Amount, transaction::Transaction};
+
+/// Keys of the transactions processed so far, so that re-delivered ones, e.g. by a message
+/// broker after a restart, are skipped rather than applied twice.
+/// Keys are only recorded once their transaction is applied, so that transactions rejected
+/// e.g. for lack of funds can be applied once re-delivered.
+pub trait DedupStore: Send {
+    /// Whether `key` was recorded, i.e. the transaction was already applied.
+    fn contains(&self, key: &str) -> bool;
+
+    /// Record `key`, returning whether it is new.
+    fn insert(&mut self, key: &str) -> Result<bool, AccountError>;
+
+    /// Keys recorded so far, e.g. to persist them along with the state of accounts.
+    fn keys(&self) -> Box<dyn Iterator<Item = &str> + '_>;
+}
+
+/// Key identifying `tx` across re-deliveries: its idempotency key if any, or else its type,
+/// client and id. The latter can't tell apart repeated disputes of the same transaction, so
+/// producers expecting those should provide idempotency keys.
+pub(crate) fn dedup_key<A: Amount>(tx: &Transaction<A>) -> String {
+    match tx.idempotency_key() {
+        Some(key) => key.clone(),
+        None => format!("{}:{}:{}", tx.type_(), tx.client(), tx.tx()),
+    }
+}
+
+/// Store keeping keys in memory, for the lifetime of the process.
+#[derive(Debug, Default)]
+pub struct MemoryDedupStore(HashSet<String>);
+
+impl DedupStore for MemoryDedupStore {
+    fn contains(&self, key: &str) -> bool {
+        self.0.contains(key)
+    }
+
+    fn insert(&mut self, key: &str) -> Result<bool, AccountError> {
+        Ok(self.0.insert(key.to_string()))
+    }
+
+    fn keys(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.0.iter().map(String::as_str))
+    }
+}
+
+/// Store persisting keys to a file, one per line, so that deduplication survives restarts.
+pub struct FileDedupStore {
+    keys: MemoryDedupStore,
+    file: File,
+}
+
+impl FileDedupStore {
+    /// Open the store at `path`, loading the keys recorded in it, if any.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, AccountError> {
+        let keys = match fs::read_to_string(&path) {
+            Ok(contents) => contents.lines().map(str::to_string).collect(),
+            Err(e) if e.kind() == ErrorKind::NotFound => HashSet::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let file = OpenOptions::new().append(true).create(true).open(path)?;
+        Ok(Self {
+            keys: MemoryDedupStore(keys),
+            file,
+        })
+    }
+}
+
+impl DedupStore for FileDedupStore {
+    fn contains(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+
+    fn insert(&mut self, key: &str) -> Result<bool, AccountError> {
+        if !self.keys.insert(key)? {
+            return Ok(false);
+        }
+        writeln!(self.file, "{key}")?;
+        self.file.sync_data()?;
+        Ok(true)
+    }
+
+    fn keys(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        self.keys.keys()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::once;
+
+    use super::FileDedupStore;
+    use crate::{Accounts, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn skip_redelivered_transactions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dedup.keys");
+        let deposit = Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false);
+        let keyed = Transaction::new(TransactionType::Withdrawal, 1, 2, Some(0.5), false)
+            .with_idempotency_key("withdrawal-1");
+
+        let mut accounts = Accounts::default().with_dedup(FileDedupStore::open(&path).unwrap());
+        let overdrawing = Transaction::new(TransactionType::Withdrawal, 1, 3, Some(5.0), false)
+            .with_idempotency_key("withdrawal-2");
+        let txs = [overdrawing.clone(), deposit.clone(), keyed, deposit];
+        accounts
+            .apply_transaction_iter(txs.into_iter().map(Ok), Strictness::Lenient)
+            .unwrap();
+        assert_eq!(*accounts.get(1).unwrap().available(), 1.5);
+        assert_eq!(accounts.report().duplicates, 1);
+        // Rejected transactions aren't recorded, applying once re-delivered.
+        let funding = Transaction::new(TransactionType::Deposit, 1, 4, Some(5.0), false);
+        accounts
+            .apply_transaction_iter(
+                [funding, overdrawing].into_iter().map(Ok),
+                Strictness::Lenient,
+            )
+            .unwrap();
+        assert_eq!(*accounts.get(1).unwrap().available(), 1.5);
+        assert_eq!(accounts.report().duplicates, 1);
+        // Deduplication survives restarts.
+        let mut accounts = Accounts::default().with_dedup(FileDedupStore::open(&path).unwrap());
+        let redelivered = Transaction::new(TransactionType::Withdrawal, 1, 3, Some(0.5), false)
+            .with_idempotency_key("withdrawal-1");
+        accounts
+            .apply_transaction_iter(once(Ok(redelivered)), Strictness::Strict)
+            .unwrap();
+        assert!(accounts.is_empty());
+        assert_eq!(accounts.report().duplicates, 1);
+    }
+}
@@ -0,0 +1,148 @@
+#[cfg(any(feature = "csv", feature = "json"))]
+use std::io::Write;
+
+use serde::Serialize;
+
+#[cfg(any(feature = "csv", feature = "json"))]
+use super::account::AccountError;
+use super::{
+    account::{serialize_to_decimal_precision, Account, Accounts},
+    types::ClientId,
+};
+
+/// Balance differences smaller than this are disregarded, being below output precision.
+const TOLERANCE: f64 = 0.00005;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountChange {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Difference between the states of an account, as balance deltas from the original
+/// account to the compared one.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AccountDelta {
+    pub client: ClientId,
+    pub change: AccountChange,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub available: f64,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub held: f64,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub total: f64,
+    /// Whether the compared account is locked.
+    pub locked: bool,
+}
+
+impl AccountDelta {
+    fn new(client: ClientId, from: Option<&Account>, to: Option<&Account>) -> Option<Self> {
+        let balances = |acc: Option<&Account>| {
+            acc.map_or((0.0, 0.0, 0.0, false), |acc| {
+                (*acc.available(), *acc.held(), *acc.total(), *acc.locked())
+            })
+        };
+        let (from_available, from_held, from_total, from_locked) = balances(from);
+        let (available, held, total, locked) = balances(to);
+        let delta = Self {
+            client,
+            change: match (from, to) {
+                (None, _) => AccountChange::Added,
+                (_, None) => AccountChange::Removed,
+                _ => AccountChange::Changed,
+            },
+            available: available - from_available,
+            held: held - from_held,
+            total: total - from_total,
+            locked,
+        };
+        let unchanged = delta.change == AccountChange::Changed
+            && locked == from_locked
+            && [delta.available, delta.held, delta.total]
+                .iter()
+                .all(|d| d.abs() < TOLERANCE);
+        (!unchanged).then_some(delta)
+    }
+}
+
+/// Accounts added, removed or changed between two `Accounts`, ordered by client id.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct AccountsDiff(pub Vec<AccountDelta>);
+
+impl AccountsDiff {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[cfg(feature = "csv")]
+    pub fn to_csv<W: Write>(&self, wrt: W) -> Result<(), AccountError> {
+        let mut wrt = csv::Writer::from_writer(wrt);
+        for delta in &self.0 {
+            wrt.serialize(delta)?;
+        }
+        wrt.flush()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json<W: Write>(&self, wrt: W) -> Result<(), AccountError> {
+        serde_json::to_writer(wrt, &self.0)?;
+        Ok(())
+    }
+}
+
+impl Accounts {
+    /// Differences from these accounts to `other`.
+    pub fn diff(&self, other: &Accounts) -> AccountsDiff {
+        let mut clients = self.keys().chain(other.keys()).collect::<Vec<_>>();
+        clients.sort_unstable();
+        clients.dedup();
+        AccountsDiff(
+            clients
+                .into_iter()
+                .filter_map(|client| {
+                    AccountDelta::new(*client, self.get(*client), other.get(*client))
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "csv"))]
+mod tests {
+    use crate::{Accounts, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn diff_accounts() {
+        let accounts = |transactions: Vec<Transaction>| {
+            Accounts::from_transaction_iter(transactions.into_iter().map(Ok), Strictness::Strict)
+                .unwrap()
+        };
+        let before = accounts(vec![
+            Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+            Transaction::new(TransactionType::Deposit, 2, 2, Some(1.0), false),
+            Transaction::new(TransactionType::Deposit, 3, 3, Some(1.0), false),
+        ]);
+        let after = accounts(vec![
+            Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+            Transaction::new(TransactionType::Deposit, 2, 2, Some(1.5), false),
+            Transaction::new(TransactionType::Dispute, 2, 2, None, false),
+            Transaction::new(TransactionType::Deposit, 4, 4, Some(1.0), false),
+        ]);
+
+        let mut wrt = vec![];
+        before.diff(&after).to_csv(&mut wrt).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&wrt).unwrap(),
+            "\
+client,change,available,held,total,locked
+2,changed,-1.0,1.5,0.5,false
+3,removed,-1.0,0.0,-1.0,false
+4,added,1.0,0.0,1.0,false
+"
+        );
+        assert!(after.diff(&after).is_empty());
+    }
+}
@@ -0,0 +1,572 @@
+#[cfg(feature = "metrics")]
+use std::sync::Arc;
+use std::{collections::BTreeSet, iter::once, sync::mpsc, thread};
+
+use serde::Serialize;
+use tokio::sync::{broadcast, oneshot};
+
+#[cfg(feature = "metrics")]
+use super::metrics::Metrics;
+use super::{
+    account::{Account, AccountError, Accounts},
+    observer::Observer,
+    policy::Strictness,
+    reconcile::AccountRecord,
+    recurring::Recurrence,
+    report::ProcessingReport,
+    transaction::{Transaction, TransactionType},
+    types::{ClientId, Timestamp, TransactionId},
+    wal::WriteAheadLog,
+};
+
+/// Account state after a transaction was applied to it, as broadcast to subscribers.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AccountEvent {
+    pub client: ClientId,
+    pub tx: TransactionId,
+    #[serde(rename = "type")]
+    pub type_: TransactionType,
+    pub available: f64,
+    pub held: f64,
+    pub total: f64,
+    pub locked: bool,
+}
+
+/// Store of account states shared by engines, e.g. by multiple instances serving the same
+/// clients, or persisting accounts across restarts. Engines load the account of a client from
+/// the store before applying a transaction to it, and save it back once applied. Accounts
+/// aren't locked across instances, so transactions of a client should still be submitted to
+/// one instance at a time, e.g. by routing clients to instances.
+pub trait StateStore: Send {
+    /// Complete state of the account of `client`, including its disputes, if stored.
+    fn load(&mut self, client: ClientId) -> Result<Option<Account>, AccountError>;
+    /// Store the complete state of `account`, replacing any previous one.
+    fn save(&mut self, account: &Account) -> Result<(), AccountError>;
+}
+
+/// Replace the account of `client` by its state in `store`, if stored.
+fn refresh(
+    accounts: &mut Accounts,
+    store: &mut dyn StateStore,
+    client: ClientId,
+) -> Result<(), AccountError> {
+    if let Some(account) = store.load(client)? {
+        accounts.insert(client, account);
+        accounts.reset_tx_index();
+    }
+    Ok(())
+}
+
+/// Advance `accounts` to `now`, or apply all their scheduled transactions without a date,
+/// refreshing the accounts they apply to from `store` beforehand and saving them afterwards,
+/// keeping the clients not saved yet in `unsaved`.
+fn tick(
+    accounts: &mut Accounts,
+    mut store: Option<&mut (dyn StateStore + 'static)>,
+    now: Option<Timestamp>,
+    unsaved: &mut BTreeSet<ClientId>,
+) -> Result<(), AccountError> {
+    let mut clients: Vec<_> = accounts
+        .scheduled()
+        .filter(|tx| now.is_none_or(|now| tx.effective().is_some_and(|at| at <= now)))
+        .map(|tx| *tx.client())
+        .collect();
+    clients.sort_unstable();
+    clients.dedup();
+    if let Some(store) = store.as_deref_mut() {
+        for &client in &clients {
+            refresh(accounts, store, client)?;
+        }
+    }
+    // Rejected transactions are skipped, as in non-strict processing.
+    match now {
+        Some(now) => accounts.tick(now, Strictness::Lenient)?,
+        None => accounts.flush(Strictness::Lenient)?,
+    }
+    if let Some(store) = store {
+        unsaved.extend(&clients);
+        for client in clients {
+            if let Some(account) = accounts.get(client) {
+                store.save(account)?;
+            }
+            unsaved.remove(&client);
+        }
+    }
+    Ok(())
+}
+
+/// Truncate `wal` once `store` holds everything replaying it would recover, that is once the
+/// accounts of the `unsaved` clients are saved, retrying them, and no transaction is still
+/// scheduled.
+fn compact(
+    accounts: &Accounts,
+    store: &mut dyn StateStore,
+    wal: &mut WriteAheadLog,
+    unsaved: &mut BTreeSet<ClientId>,
+) -> Result<(), AccountError> {
+    while let Some(&client) = unsaved.first() {
+        if let Some(account) = accounts.get(client) {
+            store.save(account)?;
+        }
+        unsaved.remove(&client);
+    }
+    if accounts.scheduled().next().is_none() {
+        wal.truncate()?;
+    }
+    Ok(())
+}
+
+struct EventBroadcaster(broadcast::Sender<AccountEvent>);
+
+impl Observer for EventBroadcaster {
+    fn on_applied(&self, account: &Account, tx: &Transaction) {
+        // Sending only fails when nobody is subscribed.
+        let _ = self.0.send(AccountEvent {
+            client: *account.client(),
+            tx: *tx.tx(),
+            type_: *tx.type_(),
+            available: *account.available(),
+            held: *account.held(),
+            total: *account.total(),
+            locked: *account.locked(),
+        });
+    }
+}
+
+enum Request {
+    Submit(
+        Transaction,
+        oneshot::Sender<Result<AccountRecord, AccountError>>,
+    ),
+    Get(ClientId, oneshot::Sender<Option<AccountRecord>>),
+    List(oneshot::Sender<Vec<AccountRecord>>),
+    Report(oneshot::Sender<ProcessingReport>),
+    Tick(Option<Timestamp>, oneshot::Sender<Result<(), AccountError>>),
+    Recur(Recurrence, oneshot::Sender<()>),
+    Tier(ClientId, String, oneshot::Sender<()>),
+}
+
+/// Handle to accounts owned by a dedicated engine thread, shared by servers exposing the
+/// engine. Requests are processed one at a time, in the order they are received, by the
+/// same `Accounts` logic as batch processing.
+#[derive(Clone)]
+pub struct EngineHandle {
+    requests: mpsc::Sender<Request>,
+    events: broadcast::Sender<AccountEvent>,
+    #[cfg(feature = "metrics")]
+    metrics: Arc<Metrics>,
+}
+
+impl EngineHandle {
+    /// Spawn the engine thread, owning the accounts returned by `init`.
+    pub fn spawn<F: FnOnce() -> Accounts + Send + 'static>(init: F) -> Self {
+        Self::spawn_with_store(init, None, None).expect("accounts are recovered without a log")
+    }
+
+    /// Spawn the engine thread as with `spawn`, recovering the accounts returned by `init` by
+    /// replaying `wal` into them, then logging every submitted transaction to it before
+    /// applying it, so that no acknowledged transaction is lost on a crash.
+    pub fn spawn_with_wal<F: FnOnce() -> Accounts + Send + 'static>(
+        init: F,
+        wal: WriteAheadLog,
+    ) -> Result<Self, AccountError> {
+        Self::spawn_with_store(init, Some(wal), None)
+    }
+
+    /// Spawn the engine thread as with `spawn_with_wal` if given a log, sharing accounts
+    /// through `store` if any, as per `StateStore`. Given both, the log is truncated whenever
+    /// the store holds all accounts and no transaction is scheduled, so that it only keeps
+    /// the transactions the store can't recover.
+    pub fn spawn_with_store<F: FnOnce() -> Accounts + Send + 'static>(
+        init: F,
+        mut wal: Option<WriteAheadLog>,
+        mut store: Option<Box<dyn StateStore>>,
+    ) -> Result<Self, AccountError> {
+        let (requests, rx) = mpsc::channel();
+        let (events, _) = broadcast::channel(1024);
+        let broadcaster = EventBroadcaster(events.clone());
+        let (recovered_tx, recovered) = mpsc::channel();
+        #[cfg(feature = "metrics")]
+        let metrics = Arc::new(Metrics::default());
+        #[cfg(feature = "metrics")]
+        let observed = metrics.clone();
+        thread::spawn(move || {
+            let accounts = init().with_observer(broadcaster);
+            #[cfg(feature = "metrics")]
+            let accounts = accounts.with_observer(observed);
+            let mut accounts = accounts;
+            let mut unsaved = BTreeSet::new();
+            let recovery = wal.as_ref().map_or(Ok(0), |wal| wal.replay(&mut accounts));
+            let failed = recovery.is_err();
+            let _ = recovered_tx.send(recovery);
+            if failed {
+                return;
+            }
+            for req in rx {
+                // Responses are dropped when the requester went away meanwhile.
+                match req {
+                    Request::Submit(tx, resp) => {
+                        let client = *tx.client();
+                        if let Some(Err(e)) = store
+                            .as_mut()
+                            .map(|store| refresh(&mut accounts, store.as_mut(), client))
+                        {
+                            let _ = resp.send(Err(e));
+                            continue;
+                        }
+                        if let Some(Err(e)) = wal.as_mut().map(|wal| wal.append(&tx)) {
+                            let _ = resp.send(Err(e));
+                            continue;
+                        }
+                        // Apply strictly to get hold of the error, yet keep serving as
+                        // in non-strict processing.
+                        let res = accounts.apply_transaction_iter(once(Ok(tx)), Strictness::Strict);
+                        if let Err(e) = &res {
+                            if e.is_recoverable() {
+                                accounts.report_mut().record_rejected();
+                            }
+                        }
+                        // Transactions withheld, deduplicated or scheduled for later leave
+                        // clients without an account, reported with empty balances.
+                        let res = res.and_then(|()| match accounts.get(client) {
+                            Some(account) => {
+                                if let Some(store) = store.as_mut() {
+                                    unsaved.insert(client);
+                                    store.save(account)?;
+                                    unsaved.remove(&client);
+                                }
+                                Ok(AccountRecord::from(account))
+                            }
+                            None => Ok(AccountRecord::from(&Account::new(client))),
+                        });
+                        if let (Ok(_), Some(store), Some(wal)) =
+                            (&res, store.as_mut(), wal.as_mut())
+                        {
+                            // The log is kept on failure, to be truncated on a later request.
+                            let _ = compact(&accounts, store.as_mut(), wal, &mut unsaved);
+                        }
+                        let _ = resp.send(res);
+                    }
+                    Request::Get(client, resp) => {
+                        // Serve the last known state when the store is unavailable.
+                        if let Some(store) = store.as_mut() {
+                            let _ = refresh(&mut accounts, store.as_mut(), client);
+                        }
+                        let _ = resp.send(accounts.get(client).map(AccountRecord::from));
+                    }
+                    Request::List(resp) => {
+                        let _ =
+                            resp.send(accounts.iter_sorted().map(AccountRecord::from).collect());
+                    }
+                    Request::Report(resp) => {
+                        let _ = resp.send(accounts.report().clone());
+                    }
+                    Request::Tick(now, resp) => {
+                        let res = tick(&mut accounts, store.as_deref_mut(), now, &mut unsaved);
+                        if let (Ok(()), Some(store), Some(wal)) =
+                            (&res, store.as_mut(), wal.as_mut())
+                        {
+                            let _ = compact(&accounts, store.as_mut(), wal, &mut unsaved);
+                        }
+                        let _ = resp.send(res);
+                    }
+                    Request::Recur(recurrence, resp) => {
+                        accounts.add_recurrence(recurrence);
+                        let _ = resp.send(());
+                    }
+                    Request::Tier(client, tier, resp) => {
+                        accounts.set_tier(client, tier);
+                        let _ = resp.send(());
+                    }
+                }
+            }
+        });
+        recovered.recv().expect("engine thread reports recovery")?;
+        Ok(Self {
+            requests,
+            events,
+            #[cfg(feature = "metrics")]
+            metrics,
+        })
+    }
+
+    /// Metrics of the transactions processed by the engine.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    async fn request<T>(&self, req: impl FnOnce(oneshot::Sender<T>) -> Request) -> T {
+        let (resp, rx) = oneshot::channel();
+        self.requests
+            .send(req(resp))
+            .expect("engine thread outlives its handles");
+        rx.await.expect("engine thread outlives its handles")
+    }
+
+    /// Apply `tx`, returning the updated account, or the error rejecting it. Transactions
+    /// which aren't applied right away, e.g. withheld by anomaly detectors, return the
+    /// account as it is, with empty balances if it doesn't exist yet.
+    pub async fn submit(&self, tx: Transaction) -> Result<AccountRecord, AccountError> {
+        self.request(|resp| Request::Submit(tx, resp)).await
+    }
+
+    pub async fn account(&self, client: ClientId) -> Option<AccountRecord> {
+        self.request(|resp| Request::Get(client, resp)).await
+    }
+
+    /// All accounts, ordered by client id.
+    pub async fn accounts(&self) -> Vec<AccountRecord> {
+        self.request(Request::List).await
+    }
+
+    pub async fn report(&self) -> ProcessingReport {
+        self.request(Request::Report).await
+    }
+
+    /// Advance processing to `now`, applying the transactions scheduled to take effect by
+    /// then and settling stale disputes, as per `Accounts::tick`.
+    pub async fn tick(&self, now: Timestamp) -> Result<(), AccountError> {
+        self.request(|resp| Request::Tick(Some(now), resp)).await
+    }
+
+    /// Apply the transactions of `recurrence` as processing reaches each of its occurrences.
+    pub async fn add_recurrence(&self, recurrence: Recurrence) {
+        self.request(|resp| Request::Recur(recurrence, resp)).await
+    }
+
+    /// Assign `client` to KYC tier `tier`, as per `Accounts::set_tier`.
+    pub async fn set_tier(&self, client: ClientId, tier: String) {
+        self.request(|resp| Request::Tier(client, tier, resp)).await
+    }
+
+    /// Apply all the scheduled transactions, as per `Accounts::flush`.
+    pub async fn flush(&self) -> Result<(), AccountError> {
+        self.request(|resp| Request::Tick(None, resp)).await
+    }
+
+    /// Receive an event for every transaction applied from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<AccountEvent> {
+        self.events.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fs,
+        sync::{Arc, Mutex},
+    };
+
+    use tokio::runtime::Builder;
+
+    use super::{EngineHandle, StateStore};
+    use crate::{
+        account::AccountState, Account, AccountError, AccountRecord, Accounts, ClientId,
+        ClientStats, Transaction, TransactionType, WriteAheadLog,
+    };
+
+    #[derive(Clone, Default)]
+    struct SharedStore(Arc<Mutex<HashMap<ClientId, AccountState>>>);
+
+    impl StateStore for SharedStore {
+        fn load(&mut self, client: ClientId) -> Result<Option<Account>, AccountError> {
+            let mut states = self.0.lock().unwrap();
+            Ok(states.remove(&client).map(|state| {
+                let account = Account::from(state);
+                states.insert(client, AccountState::from(&account));
+                account
+            }))
+        }
+
+        fn save(&mut self, account: &Account) -> Result<(), AccountError> {
+            let state = AccountState::from(account);
+            self.0.lock().unwrap().insert(*account.client(), state);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn submit_transactions() {
+        Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(submit_transactions_async());
+    }
+
+    async fn submit_transactions_async() {
+        let engine = EngineHandle::spawn(Accounts::default);
+        let mut events = engine.subscribe();
+
+        let account = engine
+            .submit(Transaction::new(
+                TransactionType::Deposit,
+                1,
+                1,
+                Some(2.0),
+                false,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            account,
+            AccountRecord {
+                client: 1,
+                available: 2.0,
+                held: 0.0,
+                total: 2.0,
+                locked: false,
+            }
+        );
+        assert_eq!(events.recv().await.unwrap().total, 2.0);
+        assert!(matches!(
+            engine
+                .submit(Transaction::new(
+                    TransactionType::Withdrawal,
+                    1,
+                    2,
+                    Some(3.0),
+                    false
+                ))
+                .await,
+            Err(AccountError::Withdrawal(1, 2))
+        ));
+        assert_eq!(engine.accounts().await, vec![account]);
+        assert_eq!(engine.report().await.rejected, 1);
+    }
+
+    #[test]
+    fn submit_withheld_transactions() {
+        Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(submit_withheld_transactions_async());
+    }
+
+    async fn submit_withheld_transactions_async() {
+        let engine = EngineHandle::spawn(|| {
+            Accounts::default()
+                .with_anomaly_detector(|_: &ClientStats, _: &Transaction| Some("review".into()))
+        });
+        let account = engine
+            .submit(Transaction::new(
+                TransactionType::Deposit,
+                1,
+                1,
+                Some(2.0),
+                false,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(account, AccountRecord::from(&Account::new(1)));
+        assert!(engine.accounts().await.is_empty());
+        let report = engine.report().await;
+        assert_eq!(
+            (report.deposits, report.rejected, report.withheld),
+            (0, 0, 1)
+        );
+    }
+
+    #[test]
+    fn share_accounts_through_store() {
+        Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(share_accounts_through_store_async());
+    }
+
+    async fn share_accounts_through_store_async() {
+        let store = SharedStore::default();
+        let spawn = || {
+            EngineHandle::spawn_with_store(Accounts::default, None, Some(Box::new(store.clone())))
+                .unwrap()
+        };
+        let (first, second) = (spawn(), spawn());
+
+        first
+            .submit(Transaction::new(
+                TransactionType::Deposit,
+                1,
+                1,
+                Some(2.0),
+                false,
+            ))
+            .await
+            .unwrap();
+        let account = second
+            .submit(Transaction::new(
+                TransactionType::Dispute,
+                1,
+                1,
+                None,
+                false,
+            ))
+            .await
+            .unwrap();
+        assert_eq!((account.available, account.held), (0.0, 2.0));
+        let account = first
+            .submit(Transaction::new(
+                TransactionType::Resolve,
+                1,
+                1,
+                None,
+                false,
+            ))
+            .await
+            .unwrap();
+        assert_eq!((account.available, account.held), (2.0, 0.0));
+        assert_eq!(second.account(1).await, Some(account));
+    }
+
+    #[test]
+    fn recover_after_truncating_log() {
+        Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(recover_after_truncating_log_async());
+    }
+
+    async fn recover_after_truncating_log_async() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("engine.wal");
+        let store = SharedStore::default();
+        let spawn = || {
+            EngineHandle::spawn_with_store(
+                Accounts::default,
+                Some(WriteAheadLog::open(&path).unwrap()),
+                Some(Box::new(store.clone())),
+            )
+            .unwrap()
+        };
+        let engine = spawn();
+
+        engine
+            .submit(Transaction::new(
+                TransactionType::Deposit,
+                1,
+                1,
+                Some(2.0),
+                false,
+            ))
+            .await
+            .unwrap();
+        // The store holds the deposit.
+        assert!(fs::read_to_string(&path).unwrap().is_empty());
+        engine
+            .submit(
+                Transaction::new(TransactionType::Deposit, 1, 2, Some(1.0), false)
+                    .with_effective(100),
+            )
+            .await
+            .unwrap();
+        // Only the log holds the scheduled deposit.
+        assert!(!fs::read_to_string(&path).unwrap().is_empty());
+        drop(engine);
+
+        let engine = spawn();
+        engine.tick(100).await.unwrap();
+        assert_eq!(engine.account(1).await.unwrap().total, 3.0);
+        assert!(fs::read_to_string(&path).unwrap().is_empty());
+    }
+}
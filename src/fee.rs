@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use super::transaction::TransactionType;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fee {
+    /// Fixed amount charged per transaction.
+    Flat(f64),
+    /// Percentage of the transaction amount, e.g. `1.5` charges 1.5%.
+    Percentage(f64),
+}
+
+impl Fee {
+    pub fn compute(&self, amount: f64) -> f64 {
+        match self {
+            Self::Flat(fee) => *fee,
+            Self::Percentage(pct) => amount * pct / 100.0,
+        }
+    }
+}
+
+/// Fees charged per transaction type, deducted from the client's available funds when a
+/// transaction is applied.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeeSchedule(HashMap<TransactionType, Fee>);
+
+impl FeeSchedule {
+    pub fn with_fee(mut self, type_: TransactionType, fee: Fee) -> Self {
+        self.0.insert(type_, fee);
+        self
+    }
+
+    pub fn fee(&self, type_: &TransactionType, amount: f64) -> f64 {
+        self.0.get(type_).map_or(0.0, |fee| fee.compute(amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fee, FeeSchedule, TransactionType};
+
+    #[test]
+    fn compute_fees() {
+        let schedule = FeeSchedule::default()
+            .with_fee(TransactionType::Deposit, Fee::Flat(0.5))
+            .with_fee(TransactionType::Withdrawal, Fee::Percentage(2.0));
+
+        assert_eq!(schedule.fee(&TransactionType::Deposit, 10.0), 0.5);
+        assert_eq!(schedule.fee(&TransactionType::Withdrawal, 10.0), 0.2);
+        assert_eq!(schedule.fee(&TransactionType::Dispute, 10.0), 0.0);
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+#[cfg(feature = "csv")]
+use std::{fs::File, io::Read, path::Path};
+
+#[cfg(feature = "csv")]
+use csv::Error as CsvError;
+#[cfg(feature = "csv")]
+use serde::Deserialize;
+
+/// Exchange rates applied by conversions between the assets of an account.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FxRates {
+    /// Symbol the default asset is referred to by in rates, if not an empty one.
+    default_asset: String,
+    /// Units of the second asset per unit of the first one.
+    rates: HashMap<(String, String), f64>,
+}
+
+/// Record of a rates csv.
+#[cfg(feature = "csv")]
+#[derive(Deserialize)]
+struct RateRecord {
+    from: String,
+    to: String,
+    rate: f64,
+}
+
+#[cfg(feature = "csv")]
+impl FxRates {
+    /// Parse rates from the csv at `path`, with columns `from`, `to` and `rate`.
+    pub fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, CsvError> {
+        Self::from_csv_reader(File::open(path)?)
+    }
+
+    /// Parse the rates of csv read from `rdr`, e.g. a string in memory.
+    pub fn from_csv_reader<R: Read>(rdr: R) -> Result<Self, CsvError> {
+        csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(rdr)
+            .deserialize()
+            .try_fold(Self::default(), |rates, record| {
+                let RateRecord { from, to, rate } = record?;
+                Ok(rates.with_rate(from, to, rate))
+            })
+    }
+}
+
+impl FxRates {
+    /// Refer to the default asset by `asset` in rates, e.g. `USD`.
+    pub fn with_default_asset<S: Into<String>>(mut self, asset: S) -> Self {
+        self.default_asset = asset.into();
+        self
+    }
+
+    /// Convert one unit of `from` into `rate` units of `to`, and conversely unless a rate
+    /// the other way round is given too.
+    pub fn with_rate<S: Into<String>>(mut self, from: S, to: S, rate: f64) -> Self {
+        self.rates.insert((from.into(), to.into()), rate);
+        self
+    }
+
+    /// Units of `to` per unit of `from`, either being the default asset if `None`, if known.
+    pub fn rate(&self, from: Option<&str>, to: Option<&str>) -> Option<f64> {
+        let from = from.unwrap_or(&self.default_asset);
+        let to = to.unwrap_or(&self.default_asset);
+        if from == to {
+            return Some(1.0);
+        }
+        let rate = |from: &str, to: &str| self.rates.get(&(from.to_string(), to.to_string()));
+        rate(from, to).copied().or_else(|| {
+            rate(to, from)
+                .filter(|rate| **rate != 0.0)
+                .map(|rate| 1.0 / rate)
+        })
+    }
+}
+
+#[cfg(all(test, feature = "csv"))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::FxRates;
+    use crate::{
+        Account, AccountError, Accounts, Config, Observer, Strictness, Transaction, TransactionType,
+    };
+
+    /// Records the rates of applied conversions.
+    #[derive(Default)]
+    struct RateLog(Mutex<Vec<Option<f64>>>);
+
+    impl Observer for RateLog {
+        fn on_applied(&self, _account: &Account, tx: &Transaction) {
+            if *tx.type_() == TransactionType::Convert {
+                self.0.lock().unwrap().push(*tx.rate());
+            }
+        }
+    }
+
+    #[test]
+    fn convert_between_assets() {
+        let fx = FxRates::from_csv_reader("from,to,rate\nEUR,USD,1.1\nUSD,JPY,150\n".as_bytes())
+            .unwrap()
+            .with_default_asset("USD");
+        assert_eq!(fx.rate(Some("USD"), None), Some(1.0));
+        assert_eq!(fx.rate(Some("JPY"), None), Some(1.0 / 150.0));
+        assert_eq!(fx.rate(Some("EUR"), Some("JPY")), None);
+
+        let convert = |tx, amount, from: Option<&str>, to: Option<&str>| {
+            let convert = Transaction::new(TransactionType::Convert, 1, tx, Some(amount), false);
+            let convert = match from {
+                Some(from) => convert.with_asset(from),
+                None => convert,
+            };
+            match to {
+                Some(to) => convert.with_to_asset(to),
+                None => convert,
+            }
+        };
+        let log = Arc::new(RateLog::default());
+        let mut accounts = Accounts::with_config(Config {
+            fx: Some(fx),
+            ..Config::default()
+        })
+        .with_observer(log.clone());
+        accounts
+            .apply_transaction_iter(
+                [
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(10.0), false)
+                        .with_asset("EUR"),
+                    convert(2, 4.0, Some("EUR"), None),
+                    convert(3, 0.4, None, Some("JPY")),
+                    convert(4, 1.0, Some("EUR"), Some("JPY")),
+                    convert(5, 7.0, Some("EUR"), None),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Warn,
+            )
+            .unwrap();
+        assert!(matches!(
+            accounts.warnings(),
+            [
+                AccountError::Conversion(1, 4),
+                AccountError::Overdrawn(1, 5),
+            ]
+        ));
+
+        let account = accounts.get(1).unwrap();
+        assert_eq!(*account.total(), 4.0);
+        assert_eq!(*account.asset("EUR").unwrap().total(), 6.0);
+        assert_eq!(*account.asset("JPY").unwrap().total(), 60.0);
+        assert_eq!(*log.0.lock().unwrap(), [Some(1.1), Some(150.0)]);
+    }
+}
@@ -0,0 +1,288 @@
+use std::io::Write;
+
+use super::{
+    account::{truncate_to_decimal_precision, AccountError},
+    amount::Amount,
+    transaction::{Transaction, TransactionType},
+    types::{ClientId, Timestamp, TransactionId},
+};
+
+/// Output format of a `TransactionGraph`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GraphFormat {
+    /// Graphviz DOT.
+    Dot,
+    GraphMl,
+}
+
+/// Node of a `TransactionGraph`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphNode {
+    Client(ClientId),
+    /// Transaction stored by the account, e.g. a deposit, along with its lifecycle state.
+    Transaction {
+        type_: TransactionType,
+        tx: TransactionId,
+        amount: f64,
+        /// Asset of the transaction, if not the default one.
+        asset: Option<String>,
+        /// Lifecycle state of the transaction, e.g. `charged_back`, if settled otherwise than
+        /// by applying it.
+        status: Option<&'static str>,
+    },
+    /// Transaction referring to a stored one, e.g. a dispute, as journaled.
+    Event {
+        type_: TransactionType,
+        tx: TransactionId,
+        timestamp: Option<Timestamp>,
+    },
+}
+
+/// Directed edge of a `TransactionGraph`, between the nodes at the given indices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub label: &'static str,
+}
+
+/// Relationships between a client, the transactions stored by its account and the ones
+/// referring to them, e.g. to visualize dispute chains, as returned by `Accounts::graph`.
+/// The client links to each stored transaction, which in turn starts the chain of the
+/// disputes, resolves, chargebacks and other transactions referring to it, in the order they
+/// were applied.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionGraph {
+    pub client: ClientId,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+impl TransactionGraph {
+    /// Graph of `client`, with the transactions it stores in `stored`, along with their
+    /// asset, and the transactions applied to it in `journal`.
+    pub(crate) fn new<'a, A: Amount + 'a>(
+        client: ClientId,
+        stored: impl IntoIterator<Item = (&'a Transaction<A>, Option<&'a str>)>,
+        journal: &[Transaction<A>],
+    ) -> Self {
+        let mut graph = Self {
+            client,
+            nodes: vec![GraphNode::Client(client)],
+            edges: Vec::new(),
+        };
+        // Index of the last node of the chain of each stored transaction.
+        let mut chains = Vec::new();
+        for (tx, asset) in stored {
+            graph.edges.push(GraphEdge {
+                from: 0,
+                to: graph.nodes.len(),
+                label: tx.type_().as_str(),
+            });
+            chains.push((*tx.tx(), graph.nodes.len()));
+            graph.nodes.push(GraphNode::Transaction {
+                type_: *tx.type_(),
+                tx: *tx.tx(),
+                amount: tx.amount().to_f64(),
+                asset: asset.map(str::to_string),
+                status: status(tx),
+            });
+        }
+        for tx in journal.iter().filter(|tx| tx.type_().is_reference()) {
+            let Some((_, last)) = chains.iter_mut().find(|(id, _)| id == tx.tx()) else {
+                continue;
+            };
+            graph.edges.push(GraphEdge {
+                from: *last,
+                to: graph.nodes.len(),
+                label: tx.type_().as_str(),
+            });
+            *last = graph.nodes.len();
+            graph.nodes.push(GraphNode::Event {
+                type_: *tx.type_(),
+                tx: *tx.tx(),
+                timestamp: *tx.timestamp(),
+            });
+        }
+        graph
+    }
+
+    pub fn write<W: Write>(&self, format: GraphFormat, wrt: W) -> Result<(), AccountError> {
+        match format {
+            GraphFormat::Dot => self.to_dot(wrt),
+            GraphFormat::GraphMl => self.to_graphml(wrt),
+        }
+    }
+
+    /// Write the graph to `wrt` in Graphviz DOT, e.g. to render it with `dot -Tsvg`.
+    pub fn to_dot<W: Write>(&self, mut wrt: W) -> Result<(), AccountError> {
+        writeln!(wrt, "digraph client_{} {{", self.client)?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            let shape = match node {
+                GraphNode::Client(_) => "box",
+                GraphNode::Transaction { .. } => "ellipse",
+                GraphNode::Event { .. } => "diamond",
+            };
+            let label = label(node).replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(wrt, "  n{i} [label=\"{label}\", shape={shape}];")?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                wrt,
+                "  n{} -> n{} [label=\"{}\"];",
+                edge.from, edge.to, edge.label
+            )?;
+        }
+        writeln!(wrt, "}}")?;
+        wrt.flush()?;
+        Ok(())
+    }
+
+    /// Write the graph to `wrt` in GraphML, e.g. to import it in Gephi or yEd.
+    pub fn to_graphml<W: Write>(&self, mut wrt: W) -> Result<(), AccountError> {
+        writeln!(wrt, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            wrt,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        writeln!(
+            wrt,
+            r#"  <key id="kind" for="node" attr.name="kind" attr.type="string"/>"#
+        )?;
+        writeln!(
+            wrt,
+            r#"  <key id="label" for="all" attr.name="label" attr.type="string"/>"#
+        )?;
+        writeln!(
+            wrt,
+            r#"  <graph id="client_{}" edgedefault="directed">"#,
+            self.client
+        )?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            let kind = match node {
+                GraphNode::Client(_) => "client",
+                GraphNode::Transaction { .. } => "transaction",
+                GraphNode::Event { .. } => "event",
+            };
+            let label = label(node)
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;");
+            writeln!(
+                wrt,
+                r#"    <node id="n{i}"><data key="kind">{kind}</data><data key="label">{label}</data></node>"#
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                wrt,
+                r#"    <edge source="n{}" target="n{}"><data key="label">{}</data></edge>"#,
+                edge.from, edge.to, edge.label
+            )?;
+        }
+        writeln!(wrt, "  </graph>\n</graphml>")?;
+        wrt.flush()?;
+        Ok(())
+    }
+}
+
+/// Lifecycle state of stored transaction `tx`, if settled otherwise than by applying it.
+fn status<A: Amount>(tx: &Transaction<A>) -> Option<&'static str> {
+    [
+        (*tx.disputed(), "disputed"),
+        (*tx.charged_back(), "charged_back"),
+        (*tx.refunded(), "refunded"),
+        (*tx.reversed(), "reversed"),
+        (*tx.settled(), "settled"),
+    ]
+    .into_iter()
+    .find_map(|(set, status)| set.then_some(status))
+}
+
+fn label(node: &GraphNode) -> String {
+    match node {
+        GraphNode::Client(client) => format!("client {client}"),
+        GraphNode::Transaction {
+            type_,
+            tx,
+            amount,
+            asset,
+            status,
+        } => {
+            let mut label = format!("{type_} {tx}: {:?}", truncate_to_decimal_precision(*amount));
+            if let Some(asset) = asset {
+                label = format!("{label} {asset}");
+            }
+            match status {
+                Some(status) => format!("{label} ({status})"),
+                None => label,
+            }
+        }
+        GraphNode::Event {
+            type_,
+            tx,
+            timestamp,
+        } => match timestamp {
+            Some(timestamp) => format!("{type_} {tx} at {timestamp}"),
+            None => format!("{type_} {tx}"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GraphFormat;
+    use crate::{Accounts, Config, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn export_dispute_chains() {
+        let mut accounts = Accounts::with_config(Config {
+            journal: true,
+            ..Config::default()
+        });
+        accounts
+            .apply_transaction_iter(
+                [
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false),
+                    Transaction::new(TransactionType::Deposit, 1, 2, Some(1.5), false),
+                    Transaction::new(TransactionType::Dispute, 1, 2, None, false)
+                        .with_timestamp(10),
+                    Transaction::new(TransactionType::Chargeback, 1, 2, None, false),
+                    Transaction::new(TransactionType::Deposit, 2, 3, Some(1.0), false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+
+        let graph = accounts.graph(1).unwrap();
+        let mut dot = vec![];
+        graph.write(GraphFormat::Dot, &mut dot).unwrap();
+        assert_eq!(
+            String::from_utf8(dot).unwrap(),
+            "\
+digraph client_1 {
+  n0 [label=\"client 1\", shape=box];
+  n1 [label=\"deposit 1: 2.0\", shape=ellipse];
+  n2 [label=\"deposit 2: 1.5 (charged_back)\", shape=ellipse];
+  n3 [label=\"dispute 2 at 10\", shape=diamond];
+  n4 [label=\"chargeback 2\", shape=diamond];
+  n0 -> n1 [label=\"deposit\"];
+  n0 -> n2 [label=\"deposit\"];
+  n2 -> n3 [label=\"dispute\"];
+  n3 -> n4 [label=\"chargeback\"];
+}
+"
+        );
+
+        let mut graphml = vec![];
+        graph.write(GraphFormat::GraphMl, &mut graphml).unwrap();
+        let graphml = String::from_utf8(graphml).unwrap();
+        assert!(graphml.contains(r#"<graph id="client_1" edgedefault="directed">"#));
+        assert!(graphml.contains(
+            r#"<edge source="n3" target="n4"><data key="label">chargeback</data></edge>"#
+        ));
+        assert!(accounts.graph(3).is_none());
+    }
+}
@@ -0,0 +1,224 @@
+use std::{net::SocketAddr, pin::Pin};
+
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream},
+    Stream, StreamExt,
+};
+use tonic::{transport::Server, Request, Response, Status};
+
+use super::{
+    account::AccountError,
+    engine::{AccountEvent, EngineHandle},
+    reconcile::AccountRecord,
+    transaction::Transaction,
+};
+
+mod proto {
+    tonic::include_proto!("tx_engine");
+}
+
+use proto::{
+    engine_server::{Engine, EngineServer},
+    Account, GetAccountRequest, ListAccountsRequest, ListAccountsResponse, StreamEventsRequest,
+};
+
+impl From<AccountRecord> for Account {
+    fn from(rec: AccountRecord) -> Self {
+        Self {
+            client: rec.client.into(),
+            available: rec.available,
+            held: rec.held,
+            total: rec.total,
+            locked: rec.locked,
+        }
+    }
+}
+
+impl From<AccountEvent> for proto::AccountEvent {
+    fn from(event: AccountEvent) -> Self {
+        Self {
+            client: event.client.into(),
+            tx: event.tx,
+            r#type: event.type_.to_string(),
+            available: event.available,
+            held: event.held,
+            total: event.total,
+            locked: event.locked,
+        }
+    }
+}
+
+impl TryFrom<proto::Transaction> for Transaction {
+    type Error = Status;
+
+    fn try_from(tx: proto::Transaction) -> Result<Self, Self::Error> {
+        let type_ = tx
+            .r#type
+            .parse()
+            .map_err(|e| Status::invalid_argument(format!("{e}")))?;
+        let client = tx
+            .client
+            .try_into()
+            .map_err(|_| Status::invalid_argument(format!("invalid client: {}", tx.client)))?;
+        let transaction = Self::new(type_, client, tx.tx, tx.amount, false);
+        Ok(match tx.timestamp {
+            Some(timestamp) => transaction.with_timestamp(timestamp),
+            None => transaction,
+        })
+    }
+}
+
+impl From<AccountError> for Status {
+    fn from(e: AccountError) -> Self {
+        if e.is_recoverable() {
+            Status::failed_precondition(e.to_string())
+        } else {
+            Status::internal(e.to_string())
+        }
+    }
+}
+
+struct EngineService(EngineHandle);
+
+#[tonic::async_trait]
+impl Engine for EngineService {
+    async fn submit_transaction(
+        &self,
+        req: Request<proto::Transaction>,
+    ) -> Result<Response<Account>, Status> {
+        let account = self.0.submit(req.into_inner().try_into()?).await?;
+        Ok(Response::new(account.into()))
+    }
+
+    async fn get_account(
+        &self,
+        req: Request<GetAccountRequest>,
+    ) -> Result<Response<Account>, Status> {
+        let client = req.into_inner().client;
+        let account = match client.try_into() {
+            Ok(client) => self.0.account(client).await,
+            Err(_) => None,
+        };
+        account
+            .map(|acc| Response::new(acc.into()))
+            .ok_or_else(|| Status::not_found(format!("account not found: {client}")))
+    }
+
+    async fn list_accounts(
+        &self,
+        _req: Request<ListAccountsRequest>,
+    ) -> Result<Response<ListAccountsResponse>, Status> {
+        Ok(Response::new(ListAccountsResponse {
+            accounts: self
+                .0
+                .accounts()
+                .await
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }))
+    }
+
+    type StreamEventsStream =
+        Pin<Box<dyn Stream<Item = Result<proto::AccountEvent, Status>> + Send>>;
+
+    async fn stream_events(
+        &self,
+        _req: Request<StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        let events = BroadcastStream::new(self.0.subscribe()).map(|event| match event {
+            Ok(event) => Ok(event.into()),
+            Err(BroadcastStreamRecvError::Lagged(missed)) => Err(Status::data_loss(format!(
+                "subscriber lagged behind, {missed} events missed"
+            ))),
+        });
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+/// Serve the `tx_engine.Engine` gRPC service, defined in `proto/tx_engine.proto`, on `addr`
+/// until the server fails.
+pub async fn serve_grpc(engine: EngineHandle, addr: SocketAddr) -> Result<(), AccountError> {
+    Server::builder()
+        .add_service(EngineServer::new(EngineService(engine)))
+        .serve(addr)
+        .await
+        .map_err(std::io::Error::other)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{net::TcpListener, runtime::Builder};
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{transport::Server, Code};
+
+    use super::{
+        proto::{
+            engine_client::EngineClient, engine_server::EngineServer, GetAccountRequest,
+            ListAccountsRequest, Transaction,
+        },
+        EngineService,
+    };
+    use crate::{Accounts, EngineHandle};
+
+    #[test]
+    fn serve_requests() {
+        Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(serve_requests_async());
+    }
+
+    async fn serve_requests_async() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let engine = EngineHandle::spawn(Accounts::default);
+        tokio::spawn(
+            Server::builder()
+                .add_service(EngineServer::new(EngineService(engine)))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        let mut client = EngineClient::connect(format!("http://{addr}"))
+            .await
+            .unwrap();
+        let deposit = Transaction {
+            r#type: "deposit".to_string(),
+            client: 1,
+            tx: 1,
+            amount: Some(2.0),
+            timestamp: None,
+        };
+        let account = client
+            .submit_transaction(deposit)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(account.available, 2.0);
+
+        let withdrawal = Transaction {
+            r#type: "withdrawal".to_string(),
+            client: 1,
+            tx: 2,
+            amount: Some(3.0),
+            timestamp: None,
+        };
+        let status = client.submit_transaction(withdrawal).await.unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        let status = client
+            .get_account(GetAccountRequest { client: 2 })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+        let accounts = client
+            .list_accounts(ListAccountsRequest {})
+            .await
+            .unwrap()
+            .into_inner()
+            .accounts;
+        assert_eq!(accounts, vec![account]);
+    }
+}
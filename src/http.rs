@@ -0,0 +1,145 @@
+use std::{
+    io::{self, Read},
+    thread::sleep,
+    time::Duration,
+};
+
+/// Reader streaming the body of a http(s) url. When the connection fails midway, the
+/// download is resumed from the last byte read with a `Range` request, up to `max_retries`
+/// times in a row, waiting with exponential backoff in between.
+pub struct HttpReader {
+    url: String,
+    body: Box<dyn Read + Send + Sync>,
+    offset: u64,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl HttpReader {
+    pub fn new<U: Into<String>>(url: U) -> io::Result<Self> {
+        let url = url.into();
+        let body = Self::request(&url, 0)?;
+        Ok(Self {
+            url,
+            body,
+            offset: 0,
+            max_retries: 3,
+            backoff: Duration::from_millis(100),
+        })
+    }
+
+    /// Retry failed reads up to `max_retries` times, waiting `backoff` before the first retry
+    /// and doubling the wait on every subsequent one.
+    pub fn with_retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.backoff = backoff;
+        self
+    }
+
+    fn request(url: &str, offset: u64) -> io::Result<Box<dyn Read + Send + Sync>> {
+        let mut req = ureq::get(url);
+        if offset > 0 {
+            req = req.set("Range", &format!("bytes={offset}-"));
+        }
+        let resp = req.call().map_err(io::Error::other)?;
+        if offset > 0 && resp.status() != 206 {
+            return Err(io::Error::other(format!(
+                "server doesn't support resuming the download of {url}"
+            )));
+        }
+        Ok(resp.into_reader())
+    }
+}
+
+impl Read for HttpReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut backoff = self.backoff;
+        let mut attempt = 0;
+        loop {
+            match self.body.read(buf) {
+                Ok(len) => {
+                    self.offset += len as u64;
+                    return Ok(len);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) if attempt == self.max_retries => return Err(e),
+                Err(_) => {
+                    sleep(backoff);
+                    backoff *= 2;
+                    attempt += 1;
+                    if let Ok(body) = Self::request(&self.url, self.offset) {
+                        self.body = body;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{Shutdown, TcpListener},
+        thread,
+        time::Duration,
+    };
+
+    use super::HttpReader;
+
+    #[test]
+    fn resume_interrupted_download() {
+        let body = "type,client,tx,amount\ndeposit,1,1,1.0\n";
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/txs.csv", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            // First response is cut midway, the second one serves the remaining range.
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut rdr = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while rdr.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                &body[..10]
+            )
+            .unwrap();
+            stream.shutdown(Shutdown::Both).unwrap();
+
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut rdr = BufReader::new(stream.try_clone().unwrap());
+            let mut range = None;
+            loop {
+                let mut line = String::new();
+                rdr.read_line(&mut line).unwrap();
+                if line.len() <= 2 {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("range: ") {
+                    range = Some(value.trim().to_string());
+                }
+            }
+            assert_eq!(range.as_deref(), Some("bytes=10-"));
+            write!(
+                stream,
+                "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\n\r\n{}",
+                body.len() - 10,
+                &body[10..]
+            )
+            .unwrap();
+        });
+
+        let mut downloaded = String::new();
+        HttpReader::new(url)
+            .unwrap()
+            .with_retries(1, Duration::ZERO)
+            .read_to_string(&mut downloaded)
+            .unwrap();
+        server.join().unwrap();
+        assert_eq!(downloaded, body);
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use super::types::Timestamp;
+
+pub const SECONDS_PER_DAY: Timestamp = 24 * 60 * 60;
+
+/// Daily interest accrual on available funds, posted to accounts as `interest` transactions
+/// once every `posting_period_days`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterestPolicy {
+    /// Interest accrued per day, e.g. `0.0001` accrues 0.01% of available funds daily.
+    pub daily_rate: f64,
+    pub posting_period_days: u64,
+}
+
+impl InterestPolicy {
+    pub fn new(daily_rate: f64, posting_period_days: u64) -> Self {
+        Self {
+            daily_rate,
+            posting_period_days: posting_period_days.max(1),
+        }
+    }
+
+    pub fn daily_interest(&self, available: f64) -> f64 {
+        // Overdrawn accounts don't accrue negative interest.
+        available.max(0.0) * self.daily_rate
+    }
+}
+
+/// Interest accrual state of an account.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct InterestAccrual {
+    /// Start of the day up to which interest was accrued.
+    pub accrued_until: Option<Timestamp>,
+    /// Interest accrued but not yet posted.
+    pub accrued: f64,
+    /// Days elapsed since interest was last posted.
+    pub days_since_posting: u64,
+}
@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+use kafka::consumer::{Consumer, FetchOffset, GroupOffsetStorage};
+
+use super::{
+    account::{AccountError, Accounts},
+    payload::PayloadFormat,
+};
+
+/// Long-running consumer applying the transactions of a Kafka topic to `Accounts` as they
+/// arrive. Offsets are committed to the consumer group only once the state they lead to is
+/// persisted by a snapshot, so that processing is resumed from there after a restart, from
+/// the state of the last snapshot.
+pub struct KafkaIngest {
+    consumer: Consumer,
+    format: PayloadFormat,
+    snapshot_interval: Duration,
+}
+
+impl KafkaIngest {
+    pub fn new(
+        brokers: Vec<String>,
+        topic: &str,
+        group: &str,
+        format: PayloadFormat,
+    ) -> Result<Self, AccountError> {
+        let consumer = Consumer::from_hosts(brokers)
+            .with_topic(topic.to_string())
+            .with_group(group.to_string())
+            .with_fallback_offset(FetchOffset::Earliest)
+            .with_offset_storage(Some(GroupOffsetStorage::Kafka))
+            .create()?;
+        Ok(Self {
+            consumer,
+            format,
+            snapshot_interval: Duration::from_secs(60),
+        })
+    }
+
+    /// Take snapshots every `interval` rather than every minute.
+    pub fn with_snapshot_interval(mut self, interval: Duration) -> Self {
+        self.snapshot_interval = interval;
+        self
+    }
+
+    /// Consume the topic indefinitely, applying transactions to `accounts` as in non-strict
+    /// processing, skipping undecodable messages, and calling `snapshot` with the accounts
+    /// every snapshot interval to persist their complete state, e.g. with
+    /// `Checkpoint::save_accounts`, before committing the offsets consumed so far.
+    pub fn run<F>(&mut self, accounts: &mut Accounts, mut snapshot: F) -> Result<(), AccountError>
+    where
+        F: FnMut(&Accounts) -> Result<(), AccountError>,
+    {
+        let mut last_snapshot = Instant::now();
+        loop {
+            let message_sets = self.consumer.poll()?;
+            for message_set in message_sets.iter() {
+                accounts.apply_payloads(
+                    message_set.messages().iter().map(|msg| msg.value),
+                    self.format,
+                )?;
+                self.consumer.consume_messageset(message_set)?;
+            }
+
+            if last_snapshot.elapsed() >= self.snapshot_interval {
+                snapshot(accounts)?;
+                self.consumer.commit_consumed()?;
+                last_snapshot = Instant::now();
+            }
+        }
+    }
+}
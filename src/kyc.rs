@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+#[cfg(feature = "csv")]
+use std::{fs::File, io::Read, path::Path};
+
+#[cfg(feature = "csv")]
+use csv::Error as CsvError;
+#[cfg(feature = "csv")]
+use serde::Deserialize;
+
+use super::types::ClientId;
+
+/// Limits applying to the clients of a KYC tier.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TierLimits {
+    /// Maximum total funds, enforced on deposits.
+    pub max_balance: Option<f64>,
+    /// Maximum amount of a single withdrawal.
+    pub max_withdrawal: Option<f64>,
+}
+
+impl TierLimits {
+    /// Whether a deposit of `amount` is allowed, given `total` funds beforehand.
+    pub fn allows_deposit(&self, total: f64, amount: f64) -> bool {
+        self.max_balance.is_none_or(|max| total + amount <= max)
+    }
+
+    pub fn allows_withdrawal(&self, amount: f64) -> bool {
+        self.max_withdrawal.is_none_or(|max| amount <= max)
+    }
+}
+
+/// KYC tiers, by name, along with the tier each client is assigned to. Clients without a
+/// tier fall in the default one, if any, and aren't limited otherwise.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KycTiers {
+    pub tiers: HashMap<String, TierLimits>,
+    pub clients: HashMap<ClientId, String>,
+    pub default: Option<String>,
+}
+
+/// Record of a tiers csv.
+#[cfg(feature = "csv")]
+#[derive(Deserialize)]
+struct TierRecord {
+    tier: String,
+    max_balance: Option<f64>,
+    max_withdrawal: Option<f64>,
+}
+
+/// Record of a csv assigning clients to tiers.
+#[cfg(feature = "csv")]
+#[derive(Deserialize)]
+struct ClientTierRecord {
+    client: ClientId,
+    tier: String,
+}
+
+#[cfg(feature = "csv")]
+impl KycTiers {
+    /// Parse tiers from the csv at `tiers`, with columns `tier`, `max_balance` and
+    /// `max_withdrawal`, and the tiers of clients from the one at `clients`, with columns
+    /// `client` and `tier`.
+    pub fn from_csv<P: AsRef<Path>, Q: AsRef<Path>>(
+        tiers: P,
+        clients: Q,
+    ) -> Result<Self, CsvError> {
+        Self::from_csv_readers(File::open(tiers)?, File::open(clients)?)
+    }
+
+    /// Parse the tiers and tiers of clients of csv read from `tiers` and `clients`, e.g.
+    /// strings in memory.
+    pub fn from_csv_readers<R: Read, S: Read>(tiers: R, clients: S) -> Result<Self, CsvError> {
+        let tiers = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(tiers)
+            .deserialize()
+            .map(|record| {
+                let TierRecord {
+                    tier,
+                    max_balance,
+                    max_withdrawal,
+                } = record?;
+                Ok((
+                    tier,
+                    TierLimits {
+                        max_balance,
+                        max_withdrawal,
+                    },
+                ))
+            })
+            .collect::<Result<_, CsvError>>()?;
+        let clients = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(clients)
+            .deserialize()
+            .map(|record| record.map(|ClientTierRecord { client, tier }| (client, tier)))
+            .collect::<Result<_, CsvError>>()?;
+        Ok(Self {
+            tiers,
+            clients,
+            default: None,
+        })
+    }
+}
+
+impl KycTiers {
+    pub fn with_tier<S: Into<String>>(mut self, tier: S, limits: TierLimits) -> Self {
+        self.tiers.insert(tier.into(), limits);
+        self
+    }
+
+    /// Assign `client` to `tier`, replacing any previous tier.
+    pub fn with_client_tier<S: Into<String>>(mut self, client: ClientId, tier: S) -> Self {
+        self.clients.insert(client, tier.into());
+        self
+    }
+
+    /// Tier of `client`, along with its limits, if any.
+    pub fn tier(&self, client: ClientId) -> Option<(&str, &TierLimits)> {
+        let tier = self.clients.get(&client).or(self.default.as_ref())?;
+        self.tiers
+            .get_key_value(tier)
+            .map(|(tier, limits)| (tier.as_str(), limits))
+    }
+}
+
+#[cfg(all(test, feature = "csv"))]
+mod tests {
+    use super::KycTiers;
+    use crate::{AccountError, Accounts, Config, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn apply_tier_limits() {
+        let tiers = KycTiers::from_csv_readers(
+            "tier,max_balance,max_withdrawal\nbasic,100.0,10.0\nverified,,50.0\n".as_bytes(),
+            "client,tier\n2,verified\n".as_bytes(),
+        )
+        .unwrap();
+        let mut accounts = Accounts::with_config(Config {
+            tiers: KycTiers {
+                default: Some("basic".to_string()),
+                ..tiers
+            },
+            ..Config::default()
+        });
+        let tx =
+            |type_, client, tx, amount| Transaction::new(type_, client, tx, Some(amount), false);
+        accounts
+            .apply_transaction_iter(
+                [
+                    tx(TransactionType::Deposit, 1, 1, 80.0),
+                    tx(TransactionType::Deposit, 1, 2, 30.0),
+                    tx(TransactionType::Withdrawal, 1, 3, 20.0),
+                    tx(TransactionType::Deposit, 2, 4, 200.0),
+                    tx(TransactionType::Withdrawal, 2, 5, 20.0),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Warn,
+            )
+            .unwrap();
+        assert!(matches!(
+            accounts.warnings(),
+            [
+                AccountError::TierBalance(1, 2, tier),
+                AccountError::TierWithdrawal(1, 3, _),
+            ] if tier == "basic"
+        ));
+        assert_eq!(*accounts.get(2).unwrap().total(), 180.0);
+
+        accounts.set_tier(1, "verified");
+        accounts
+            .apply_transaction_iter(
+                [tx(TransactionType::Withdrawal, 1, 6, 20.0)]
+                    .into_iter()
+                    .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+        assert_eq!(*accounts.get(1).unwrap().total(), 60.0);
+    }
+}
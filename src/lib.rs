@@ -1,11 +1,169 @@
 mod account;
+mod aggregate;
+mod aging;
+mod amount;
+mod anomaly;
+mod arbitration;
+#[cfg(feature = "avro")]
+mod avro;
+#[cfg(feature = "checkpoint")]
+mod checkpoint;
+#[cfg(feature = "arrow")]
+mod columnar;
+mod config;
+#[cfg(feature = "polars")]
+mod dataframe;
+mod dedup;
+mod diff;
+#[cfg(feature = "server")]
+mod engine;
+mod fee;
+mod fx;
+mod graph;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "http")]
+mod http;
+mod interest;
+#[cfg(feature = "kafka")]
+mod kafka;
+mod kyc;
+#[cfg(feature = "csv")]
+mod line;
+mod memory;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "nats")]
+mod nats;
+mod observer;
+#[cfg(feature = "json")]
+mod payload;
+#[cfg(feature = "csv")]
+mod pipeline;
+mod policy;
+#[cfg(feature = "postgres")]
+mod postgres;
+mod rates;
+mod reconcile;
+mod recurring;
+#[cfg(feature = "redis")]
+mod redis;
+mod rejection;
+mod report;
+#[cfg(feature = "rest")]
+mod rest;
+mod risk;
+mod rules;
+#[cfg(feature = "s3")]
+mod s3;
+#[cfg(feature = "fast-parse")]
+mod scan;
+mod shard;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod statement;
+mod table;
+#[cfg(feature = "testing")]
+mod testing;
 mod transaction;
 mod types;
+mod validate;
+#[cfg(feature = "csv")]
+mod wal;
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "http")]
+mod webhook;
 
 pub use self::{
-    account::{Account, AccountError, Accounts},
+    account::{Account, AccountError, Accounts, MergeError, PointInTime},
+    aggregate::{ActivityReport, Period, PeriodActivity},
+    aging::{AgedHeldFunds, HeldFundsAging},
+    amount::{Amount, MinorUnits},
+    anomaly::{Anomaly, AnomalyDetector, ClientStats, ZScoreDetector, STATS_WINDOW},
+    arbitration::{DisputeArbiter, DisputeDecision},
+    config::Config,
+    dedup::{DedupStore, FileDedupStore, MemoryDedupStore},
+    diff::{AccountChange, AccountDelta, AccountsDiff},
+    fee::{Fee, FeeSchedule},
+    fx::FxRates,
+    graph::{GraphEdge, GraphFormat, GraphNode, TransactionGraph},
+    interest::{InterestAccrual, InterestPolicy},
+    kyc::{KycTiers, TierLimits},
+    memory::{AccountFootprint, MemoryFootprint},
+    observer::Observer,
+    policy::{
+        BalanceCap, BalancePolicy, CapAction, DailyWithdrawalLimits, DisputeTimeout, FreezePolicy,
+        PrecisionPolicy, Strictness, TimeoutAction, VelocityLimit, VelocityLimits,
+    },
+    rates::{ChargebackMonitor, ChargebackRates, ChargebackThresholds},
+    reconcile::{reconcile, AccountRecord, Mismatch},
+    recurring::Recurrence,
+    rejection::{ErrorDecision, ErrorHandler, RejectedTx},
+    report::{FundsSummary, MalformedRow, ParseReport, ProcessingReport, Progress},
+    risk::{RiskDecision, RiskFlag, RiskScorer},
+    rules::{Condition, Rule, RuleAction, RuleSet, VelocityCondition},
+    shard::ShardedAccounts,
+    statement::{DisputeStatus, Statement, StatementEntry},
+    transaction::{Transaction, TransactionType, Transactions},
+    types::{ClientId, Timestamp, TransactionId},
+    validate::{InvariantViolation, Violation},
+};
+
+#[cfg(feature = "csv")]
+pub use self::{
+    anomaly::anomalies_to_csv,
+    line::LineIngest,
+    pipeline::Pipeline,
+    reconcile::mismatches_to_csv,
     transaction::{
-        Transaction, TransactionCsvIterator, TransactionType, Transactions, TransactionsCsv,
+        Column, CsvOptions, TransactionCsvFastIterator, TransactionCsvIterator,
+        TransactionCsvSkippingIterator, TransactionsCsv,
     },
-    types::{ClientId, TransactionId},
+    wal::WriteAheadLog,
 };
+
+#[cfg(any(feature = "csv", feature = "json"))]
+pub use self::account::HistoryFormat;
+#[cfg(feature = "avro")]
+pub use self::avro::{ACCOUNT_SCHEMA, ACCOUNT_WITH_FEES_SCHEMA, TRANSACTION_SCHEMA};
+#[cfg(feature = "checkpoint")]
+pub use self::checkpoint::Checkpoint;
+#[cfg(feature = "server")]
+pub use self::engine::{AccountEvent, EngineHandle, StateStore};
+#[cfg(feature = "grpc")]
+pub use self::grpc::serve_grpc;
+#[cfg(feature = "http")]
+pub use self::http::HttpReader;
+#[cfg(feature = "kafka")]
+pub use self::kafka::KafkaIngest;
+#[cfg(feature = "metrics")]
+pub use self::metrics::Metrics;
+#[cfg(feature = "mmap")]
+pub use self::mmap::MappedCsv;
+#[cfg(feature = "nats")]
+pub use self::nats::NatsIngest;
+#[cfg(feature = "json")]
+pub use self::payload::PayloadFormat;
+#[cfg(feature = "postgres")]
+pub use self::postgres::{PostgresConfig, PostgresSink};
+#[cfg(feature = "redis")]
+pub use self::redis::RedisStateStore;
+#[cfg(feature = "rest")]
+pub use self::rest::serve_rest;
+#[cfg(feature = "s3")]
+pub use self::s3::{S3Credentials, S3Reader};
+#[cfg(feature = "fast-parse")]
+pub use self::scan::scan_transactions;
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::SqliteSink;
+#[cfg(feature = "testing")]
+pub use self::testing::{arb_account, arb_amount, arb_transaction, arb_transaction_sequence};
+#[cfg(feature = "watch")]
+pub use self::watch::DirectoryWatch;
+#[cfg(feature = "http")]
+pub use self::webhook::{WebhookEvent, WebhookNotifier};
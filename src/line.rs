@@ -0,0 +1,245 @@
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    iter::once,
+    net::TcpListener,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use csv::{ReaderBuilder, StringRecord, Trim};
+
+use super::{
+    account::{AccountError, Accounts},
+    policy::Strictness,
+    transaction::{Transaction, TransactionError},
+};
+
+pub(crate) const COLUMNS: [&str; 10] = [
+    "type",
+    "client",
+    "tx",
+    "amount",
+    "timestamp",
+    "idempotency_key",
+    "effective",
+    "reason",
+    "asset",
+    "to_asset",
+];
+
+impl Transaction {
+    /// Decode a transaction from a single line: a json object when starting with `{`, or a
+    /// csv record with the columns of the csv input, in the same order.
+    pub fn from_line(line: &str) -> Result<Self, TransactionError> {
+        #[cfg(feature = "json")]
+        if line.starts_with('{') {
+            return Ok(serde_json::from_str(line)?);
+        }
+        let mut record = StringRecord::new();
+        ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(Trim::All)
+            .from_reader(line.as_bytes())
+            .read_record(&mut record)?;
+        Ok(record.deserialize(Some(&StringRecord::from(&COLUMNS[..])))?)
+    }
+}
+
+/// Long-running server applying the transactions streamed by its tcp or unix socket
+/// connections, one per line, to `Accounts` as they arrive. Lines are either csv records,
+/// optionally preceded by a header, or json objects with the `json` feature. Malformed lines
+/// are skipped, replying to the sender with the error.
+pub struct LineIngest {
+    transactions: Receiver<Transaction>,
+    snapshot_interval: Duration,
+}
+
+impl LineIngest {
+    /// Accept tcp connections from `listener`, each on its own thread.
+    pub fn from_tcp(listener: TcpListener) -> Self {
+        Self::accept(
+            move || listener.accept().map(|(stream, _)| stream),
+            |stream| stream.try_clone(),
+        )
+    }
+
+    /// Accept unix socket connections from `listener`, each on its own thread, e.g. from
+    /// co-located producers.
+    #[cfg(unix)]
+    pub fn from_unix(listener: UnixListener) -> Self {
+        Self::accept(
+            move || listener.accept().map(|(stream, _)| stream),
+            |stream| stream.try_clone(),
+        )
+    }
+
+    fn accept<S, A, C>(mut accept: A, try_clone: C) -> Self
+    where
+        S: Read + Write + Send + 'static,
+        A: FnMut() -> io::Result<S> + Send + 'static,
+        C: Fn(&S) -> io::Result<S> + Send + 'static,
+    {
+        let (sender, transactions) = mpsc::channel();
+        thread::spawn(move || loop {
+            // Failing connections are dropped, as with `incoming`.
+            if let Ok(stream) = accept() {
+                let sender = sender.clone();
+                let Ok(reader) = try_clone(&stream) else {
+                    continue;
+                };
+                thread::spawn(move || read_lines(reader, stream, sender));
+            }
+        });
+        Self {
+            transactions,
+            snapshot_interval: Duration::from_secs(60),
+        }
+    }
+
+    /// Take snapshots every `interval` rather than every minute.
+    pub fn with_snapshot_interval(mut self, interval: Duration) -> Self {
+        self.snapshot_interval = interval;
+        self
+    }
+
+    /// Serve indefinitely, applying transactions to `accounts` as in non-strict processing,
+    /// and calling `snapshot` with the accounts every snapshot interval, e.g. to persist them.
+    pub fn run<F>(&mut self, accounts: &mut Accounts, mut snapshot: F) -> Result<(), AccountError>
+    where
+        F: FnMut(&Accounts) -> Result<(), AccountError>,
+    {
+        let mut last_snapshot = Instant::now();
+        loop {
+            let timeout = self
+                .snapshot_interval
+                .saturating_sub(last_snapshot.elapsed());
+            match self.transactions.recv_timeout(timeout) {
+                Ok(tx) => accounts.apply_transaction_iter(
+                    once(tx).chain(self.transactions.try_iter()).map(Ok),
+                    Strictness::Lenient,
+                )?,
+                Err(RecvTimeoutError::Timeout) => (),
+                // The listener failed, no more connections will be accepted.
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+
+            if last_snapshot.elapsed() >= self.snapshot_interval {
+                snapshot(accounts)?;
+                last_snapshot = Instant::now();
+            }
+        }
+    }
+}
+
+/// Forward the transactions read from a connection, until it is closed.
+fn read_lines<R: Read, W: Write>(reader: R, mut writer: W, sender: Sender<Transaction>) {
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(COLUMNS[0]) {
+            continue;
+        }
+        match Transaction::from_line(line) {
+            Ok(tx) => {
+                if sender.send(tx).is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                if writeln!(writer, "error: {e}").is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::{TcpListener, TcpStream},
+        time::Duration,
+    };
+
+    use super::LineIngest;
+    use crate::{AccountError, Accounts, Transaction, TransactionType};
+
+    #[test]
+    fn decode_csv_line() {
+        assert_eq!(
+            Transaction::from_line("withdrawal, 1, 2, 1.5").unwrap(),
+            Transaction::new(TransactionType::Withdrawal, 1, 2, Some(1.5), false)
+        );
+        assert_eq!(
+            Transaction::from_line("dispute,1,2,").unwrap(),
+            Transaction::new(TransactionType::Dispute, 1, 2, None, false)
+        );
+    }
+
+    #[test]
+    fn encode_csv_line() {
+        let tx = Transaction::new(TransactionType::Deposit, 1, 2, Some(1.5), false)
+            .with_idempotency_key("recon, 17")
+            .with_reason("manual correction");
+        assert_eq!(
+            tx.to_line(),
+            "deposit,1,2,1.5,,\"recon, 17\",,manual correction"
+        );
+        assert_eq!(Transaction::from_line(&tx.to_line()).unwrap(), tx);
+    }
+
+    #[test]
+    fn ingest_tcp_lines() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut ingest = LineIngest::from_tcp(listener).with_snapshot_interval(Duration::ZERO);
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"type,client,tx,amount\ndeposit,1,1,2.0\nbogus\ndeposit,1,2,3.0\n")
+            .unwrap();
+        let mut reply = String::new();
+        BufReader::new(&stream).read_line(&mut reply).unwrap();
+        assert!(reply.starts_with("error: csv error"));
+
+        let mut accounts = Accounts::default();
+        // Stop serving as soon as both deposits are applied.
+        let res = ingest.run(&mut accounts, |accounts| {
+            match accounts.get(1).map(|acc| *acc.total()) {
+                Some(5.0) => Err(std::io::Error::other("done").into()),
+                _ => Ok(()),
+            }
+        });
+        assert!(matches!(res, Err(AccountError::Io(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ingest_unix_lines() {
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ingest.sock");
+        let mut ingest = LineIngest::from_unix(UnixListener::bind(&path).unwrap())
+            .with_snapshot_interval(Duration::ZERO);
+
+        UnixStream::connect(&path)
+            .unwrap()
+            .write_all(b"deposit,1,1,2.0\n")
+            .unwrap();
+        let mut accounts = Accounts::default();
+        let res = ingest.run(&mut accounts, |accounts| match accounts.get(1) {
+            Some(_) => Err(std::io::Error::other("done").into()),
+            None => Ok(()),
+        });
+        assert!(matches!(res, Err(AccountError::Io(_))));
+        assert_eq!(*accounts.get(1).unwrap().available(), 2.0);
+    }
+}
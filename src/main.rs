@@ -1,15 +1,1374 @@
-use tx_engine::{Accounts, TransactionsCsv};
+use tx_engine::{
+    anomalies_to_csv, mismatches_to_csv, reconcile, AccountError, AccountRecord, Accounts,
+    ActivityReport, BalanceCap, CapAction, ChargebackMonitor, ChargebackThresholds, ClientId,
+    Column, Config, CsvOptions, DailyWithdrawalLimits, DisputeTimeout, FileDedupStore, FxRates,
+    GraphFormat, KycTiers, LineIngest, MemoryDedupStore, ParseReport, Period, Pipeline, Recurrence,
+    Strictness, TimeoutAction, Timestamp, TransactionsCsv, WriteAheadLog, ZScoreDetector,
+};
 
-use std::{env, error::Error};
+#[cfg(any(feature = "grpc", feature = "rest"))]
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::os::unix::{fs::FileTypeExt, net::UnixListener};
+#[cfg(feature = "checkpoint")]
+use std::path::Path;
+#[cfg(feature = "server")]
+use std::sync::mpsc;
+#[cfg(feature = "mmap")]
+use std::thread;
+use std::{
+    error::Error,
+    fs::{self, File, OpenOptions},
+    io::{stderr, stdout, IsTerminal},
+    net::TcpListener,
+    path::PathBuf,
+    process::ExitCode,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let transactions = env::args()
-        .nth(1)
-        .expect("provide a csv file with transactions to parse");
-    let mut transactions = TransactionsCsv::from_csv(&transactions)?;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+#[cfg(feature = "server")]
+use tokio::{runtime::Builder, task::JoinSet};
+#[cfg(feature = "grpc")]
+use tx_engine::serve_grpc;
+#[cfg(feature = "rest")]
+use tx_engine::serve_rest;
+#[cfg(feature = "checkpoint")]
+use tx_engine::Checkpoint;
+#[cfg(feature = "watch")]
+use tx_engine::DirectoryWatch;
+#[cfg(feature = "server")]
+use tx_engine::EngineHandle;
+#[cfg(feature = "kafka")]
+use tx_engine::KafkaIngest;
+#[cfg(feature = "mmap")]
+use tx_engine::MappedCsv;
+#[cfg(feature = "metrics")]
+use tx_engine::Metrics;
+#[cfg(feature = "nats")]
+use tx_engine::NatsIngest;
+#[cfg(any(feature = "kafka", feature = "nats"))]
+use tx_engine::PayloadFormat;
+#[cfg(feature = "redis")]
+use tx_engine::RedisStateStore;
+#[cfg(any(feature = "json", feature = "toml"))]
+use tx_engine::RuleSet;
+#[cfg(feature = "sqlite")]
+use tx_engine::SqliteSink;
+#[cfg(feature = "redis")]
+use tx_engine::StateStore;
+#[cfg(feature = "avro")]
+use tx_engine::Transactions;
+#[cfg(feature = "postgres")]
+use tx_engine::{PostgresConfig, PostgresSink};
 
-    let accounts = Accounts::from_transaction_iter(transactions.iter(), false)?;
-    accounts.to_csv()?;
+#[derive(Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    process: Option<ProcessArgs>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Process transactions and output the resulting accounts (default)
+    Process(Box<ProcessArgs>),
+    /// Compare produced accounts against expected ones, exiting with an error on mismatches
+    Reconcile {
+        /// Accounts csv produced by the engine
+        produced: PathBuf,
+        /// Accounts csv to compare against
+        expected: PathBuf,
+    },
+    /// Reconstruct the accounts from a write-ahead log, comparing them against a snapshot of
+    /// the accounts if given, exiting with an error on mismatches, or else outputting them
+    Replay {
+        /// Write-ahead log of the transactions
+        log: PathBuf,
+        /// Accounts csv to compare against
+        #[arg(long)]
+        snapshot: Option<PathBuf>,
+        /// Options of the accounts the log was applied to by `serve`
+        #[command(flatten)]
+        config: Box<ConfigArgs>,
+    },
+    /// Output a chronological statement of the transactions of a client, with the balances
+    /// after each and the dispute state of the ones disputed
+    Statement {
+        /// Csv file or url with transactions
+        transactions: String,
+        /// Client to output the statement of
+        #[arg(long)]
+        client: ClientId,
+        /// Output format of the statement
+        #[arg(long, value_enum, default_value_t = StatementFormat::Csv)]
+        format: StatementFormat,
+    },
+    /// Output the relationships between a client, its transactions and the disputes,
+    /// resolves and chargebacks referring to them, as a graph
+    Graph {
+        /// Csv file or url with transactions
+        transactions: String,
+        /// Client to output the graph of
+        #[arg(long)]
+        client: ClientId,
+        /// Output format of the graph
+        #[arg(long, value_enum, default_value_t = GraphOutput::Dot)]
+        format: GraphOutput,
+    },
+    /// Accept transactions streamed one per line over tcp or unix socket connections,
+    /// periodically writing the accounts to a file
+    Ingest(IngestArgs),
+    /// Consume transactions from a Kafka topic, periodically writing the accounts to a file
+    #[cfg(feature = "kafka")]
+    Kafka(KafkaArgs),
+    /// Consume transactions from a NATS JetStream subject, periodically writing the accounts
+    /// to a file
+    #[cfg(feature = "nats")]
+    Nats(NatsArgs),
+    /// Process csv files as they land in a directory, outputting the accounts after each
+    #[cfg(feature = "watch")]
+    Watch {
+        /// Directory to watch
+        dir: PathBuf,
+        /// Write accounts as csv to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Serve the engine over the network, keeping accounts in memory
+    #[cfg(feature = "server")]
+    Serve(ServeArgs),
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Table,
+    #[cfg(feature = "avro")]
+    Avro,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+/// Balance to rank accounts by
+#[derive(Clone, Copy, ValueEnum)]
+enum TopBy {
+    /// Total funds
+    Total,
+    /// Held funds, if any
+    Held,
+    /// Available funds below zero, the most negative first
+    Negative,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ActivityPeriod {
+    Day,
+    Month,
+}
+
+impl From<ActivityPeriod> for Period {
+    fn from(period: ActivityPeriod) -> Self {
+        match period {
+            ActivityPeriod::Day => Period::Day,
+            ActivityPeriod::Month => Period::Month,
+        }
+    }
+}
+
+/// Settlement of disputes open for longer than `--dispute-timeout`
+#[derive(Clone, Copy, ValueEnum)]
+enum StaleDisputeAction {
+    Resolve,
+    Chargeback,
+}
+
+impl From<StaleDisputeAction> for TimeoutAction {
+    fn from(action: StaleDisputeAction) -> Self {
+        match action {
+            StaleDisputeAction::Resolve => TimeoutAction::Resolve,
+            StaleDisputeAction::Chargeback => TimeoutAction::Chargeback,
+        }
+    }
+}
+
+/// Handling of deposits exceeding `--max-balance`
+#[derive(Clone, Copy, ValueEnum)]
+enum CapExcess {
+    /// Reject them
+    Reject,
+    /// Credit them up to the cap, leaving the excess unapplied
+    Partial,
+}
+
+impl From<CapExcess> for CapAction {
+    fn from(action: CapExcess) -> Self {
+        match action {
+            CapExcess::Reject => CapAction::Reject,
+            CapExcess::Partial => CapAction::Partial,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GraphOutput {
+    Dot,
+    Graphml,
+}
+
+impl From<GraphOutput> for GraphFormat {
+    fn from(format: GraphOutput) -> Self {
+        match format {
+            GraphOutput::Dot => GraphFormat::Dot,
+            GraphOutput::Graphml => GraphFormat::GraphMl,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StatementFormat {
+    Csv,
+    #[cfg(feature = "json")]
+    Json,
+}
+
+/// Handling of transactions breaking business rules
+#[derive(Clone, Copy, ValueEnum)]
+enum StrictnessLevel {
+    /// Skip them
+    Lenient,
+    /// Skip them, reporting them to stderr
+    Warn,
+    /// Abort processing
+    Strict,
+}
+
+impl From<StrictnessLevel> for Strictness {
+    fn from(level: StrictnessLevel) -> Self {
+        match level {
+            StrictnessLevel::Lenient => Strictness::Lenient,
+            StrictnessLevel::Warn => Strictness::Warn,
+            StrictnessLevel::Strict => Strictness::Strict,
+        }
+    }
+}
+
+#[cfg(any(feature = "kafka", feature = "nats"))]
+#[derive(Clone, Copy, ValueEnum)]
+enum Payload {
+    Json,
+    #[cfg(feature = "avro")]
+    Avro,
+}
+
+#[cfg(any(feature = "kafka", feature = "nats"))]
+impl From<Payload> for PayloadFormat {
+    fn from(payload: Payload) -> Self {
+        match payload {
+            Payload::Json => PayloadFormat::Json,
+            #[cfg(feature = "avro")]
+            Payload::Avro => PayloadFormat::Avro,
+        }
+    }
+}
+
+/// Options of the accounts, shared by the commands applying transactions.
+#[derive(Args)]
+struct ConfigArgs {
+    /// Settle disputes open for longer than this many days, as of the timestamps of later
+    /// transactions
+    #[arg(long, value_name = "DAYS")]
+    dispute_timeout: Option<u64>,
+    /// Settlement of disputes open for longer than `--dispute-timeout`
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = StaleDisputeAction::Resolve,
+        requires = "dispute_timeout"
+    )]
+    dispute_timeout_action: StaleDisputeAction,
+    /// Maximum amount withdrawn by a client per calendar day, based on transaction
+    /// timestamps
+    #[arg(long, value_name = "AMOUNT")]
+    daily_withdrawal_limit: Option<f64>,
+    /// Maximum total funds of accounts, enforced on deposits
+    #[arg(long, value_name = "AMOUNT")]
+    max_balance: Option<f64>,
+    /// Handling of deposits exceeding `--max-balance`
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = CapExcess::Reject,
+        requires = "max_balance"
+    )]
+    balance_cap_action: CapExcess,
+    /// Apply adjustments, crediting or debiting accounts by their signed amount, e.g. for
+    /// manual corrections during reconciliation, rather than rejecting them
+    #[arg(long)]
+    allow_adjustments: bool,
+    /// Csv file of KYC tiers, with columns `tier`, `max_balance` and `max_withdrawal`,
+    /// limiting the balance and withdrawals of the clients assigned to them
+    #[arg(long, value_name = "FILE", requires = "kyc_clients")]
+    kyc_tiers: Option<PathBuf>,
+    /// Csv file assigning clients to KYC tiers, with columns `client` and `tier`
+    #[arg(long, value_name = "FILE", requires = "kyc_tiers")]
+    kyc_clients: Option<PathBuf>,
+    /// KYC tier of the clients not assigned to any, unlimited otherwise
+    #[arg(long, value_name = "TIER", requires = "kyc_tiers")]
+    default_tier: Option<String>,
+    /// Csv file of exchange rates applied by conversions, with columns `from`, `to` and
+    /// `rate`, the units of `to` per unit of `from`
+    #[arg(long, value_name = "FILE")]
+    fx_rates: Option<PathBuf>,
+    /// Symbol the default asset is referred to by in `--fx-rates`, e.g. USD
+    #[arg(long, value_name = "SYMBOL", requires = "fx_rates")]
+    default_asset: Option<String>,
+    /// JSON or TOML file of rules rejecting or flagging transactions, or freezing accounts,
+    /// before applying them, as per its extension
+    #[cfg(any(feature = "json", feature = "toml"))]
+    #[arg(long, value_name = "FILE")]
+    rules: Option<PathBuf>,
+}
+
+impl ConfigArgs {
+    fn config(&self) -> Result<Config, Box<dyn Error>> {
+        let fx = match &self.fx_rates {
+            Some(path) => Some(FxRates::from_csv(path).map_err(|e| {
+                format!("failed to read exchange rates from {}: {e}", path.display())
+            })?),
+            None => None,
+        };
+        let tiers = match (&self.kyc_tiers, &self.kyc_clients) {
+            (Some(tiers), Some(clients)) => KycTiers::from_csv(tiers, clients).map_err(|e| {
+                format!(
+                    "failed to read KYC tiers from {} and {}: {e}",
+                    tiers.display(),
+                    clients.display()
+                )
+            })?,
+            _ => KycTiers::default(),
+        };
+        #[cfg(any(feature = "json", feature = "toml"))]
+        let rules = match &self.rules {
+            Some(path) => RuleSet::from_file(path)
+                .map_err(|e| format!("failed to read rules from {}: {e}", path.display()))?,
+            None => RuleSet::default(),
+        };
+        Ok(Config {
+            dispute_timeout: self.dispute_timeout.map(|days| DisputeTimeout {
+                after: days.saturating_mul(24 * 60 * 60),
+                action: self.dispute_timeout_action.into(),
+            }),
+            allow_adjustments: self.allow_adjustments,
+            daily_withdrawals: DailyWithdrawalLimits {
+                default: self.daily_withdrawal_limit,
+                ..DailyWithdrawalLimits::default()
+            },
+            balance_cap: self.max_balance.map(|max_total| BalanceCap {
+                max_total,
+                action: self.balance_cap_action.into(),
+            }),
+            tiers: KycTiers {
+                default: self.default_tier.clone(),
+                ..tiers
+            },
+            fx: fx.map(|fx| match &self.default_asset {
+                Some(asset) => fx.with_default_asset(asset.as_str()),
+                None => fx,
+            }),
+            #[cfg(any(feature = "json", feature = "toml"))]
+            rules,
+            ..Config::default()
+        })
+    }
+}
+
+#[derive(Args)]
+struct ProcessArgs {
+    /// Csv file or url with transactions to parse
+    transactions: String,
+    /// Field delimiter of the csv input, a single ascii character or `tab`
+    #[arg(long, value_parser = parse_delimiter, default_value = ",")]
+    delimiter: u8,
+    /// Read a transaction field from a column not named after it, given by name or position
+    /// starting from zero, e.g. `tx=txid` or `amount=3`; can be repeated
+    #[arg(long = "column", value_name = "FIELD=COLUMN", value_parser = parse_column)]
+    columns: Vec<(Column, String)>,
+    /// Skip malformed rows rather than failing, unless more than this many, reporting them
+    /// to stderr
+    #[arg(long, value_name = "N")]
+    max_malformed: Option<usize>,
+    /// Handling of transactions breaking business rules, e.g. overdrawing accounts
+    #[arg(long, value_enum, default_value_t = StrictnessLevel::Lenient)]
+    strictness: StrictnessLevel,
+    /// Output format of the accounts
+    #[arg(long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
+    /// Write accounts to this file instead of stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+    /// Append accounts to the output file instead of overwriting it
+    #[arg(long, requires = "output")]
+    append: bool,
+    /// Print a summary of the processed transactions and of the funds across accounts to
+    /// stderr
+    #[arg(long)]
+    summary: bool,
+    /// Print this many largest accounts to stderr, as per `--top-by`
+    #[arg(long, value_name = "N")]
+    top: Option<usize>,
+    /// Balance to rank accounts by for `--top`
+    #[arg(long, value_enum, default_value_t = TopBy::Total, requires = "top")]
+    top_by: TopBy,
+    /// Also write the amounts deposited, withdrawn, disputed and charged back by each client
+    /// over each period to this csv file, as per the timestamps of the transactions
+    #[arg(long, value_name = "FILE")]
+    activity: Option<PathBuf>,
+    /// Period to aggregate activity by
+    #[arg(long, value_enum, default_value_t = ActivityPeriod::Day, requires = "activity")]
+    activity_period: ActivityPeriod,
+    /// Also write dispute and chargeback counts and amounts, and their rates as percentages
+    /// of deposits, per client and for all clients to this csv file
+    #[arg(long, value_name = "FILE")]
+    chargeback_rates: Option<PathBuf>,
+    /// Flag clients with chargebacks above this percentage of their deposits, by count
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = 1.0,
+        requires = "chargeback_rates"
+    )]
+    max_chargeback_rate: f64,
+    /// Flag clients with disputes above this percentage of their deposits, by count
+    #[arg(long, value_name = "PCT", requires = "chargeback_rates")]
+    max_dispute_rate: Option<f64>,
+    /// Also write the funds held by ongoing disputes of each account, by how long ago the
+    /// disputes began, to this csv file
+    #[arg(long, value_name = "FILE")]
+    aging: Option<PathBuf>,
+    /// Unix timestamp to age held funds as of, instead of now
+    #[arg(long, value_name = "TIMESTAMP", requires = "aging")]
+    aging_at: Option<Timestamp>,
+    /// Withhold deposits, withdrawals and other stored transactions deviating from the mean
+    /// amount of the client by more than this many standard deviations
+    #[arg(long, value_name = "SIGMAS")]
+    anomaly_zscore: Option<f64>,
+    /// Also write the transactions withheld as anomalies to this csv file
+    #[arg(long, value_name = "FILE", requires = "anomaly_zscore")]
+    anomalies: Option<PathBuf>,
+    #[command(flatten)]
+    config: ConfigArgs,
+    /// Apply the transactions scheduled past the end of the input, as per their effective
+    /// date, rather than leaving them pending
+    #[arg(long)]
+    flush_scheduled: bool,
+    /// Csv file of recurring transactions to apply as processing reaches each occurrence,
+    /// with columns `type`, `client`, `amount`, `start`, `every`, `count` and `first_tx`
+    #[arg(long, value_name = "FILE", conflicts_with = "workers")]
+    recurring: Option<PathBuf>,
+    /// Apply the transactions of csv input on this many threads, reading and parsing them on
+    /// threads of their own
+    #[arg(long, value_name = "N", conflicts_with = "max_malformed")]
+    workers: Option<usize>,
+    /// Map the transactions file into memory, parsing chunks of it in parallel
+    #[cfg(feature = "mmap")]
+    #[arg(long, conflicts_with_all = ["workers", "delimiter", "columns", "max_malformed"])]
+    #[cfg_attr(feature = "checkpoint", arg(conflicts_with = "checkpoint"))]
+    mmap: bool,
+    /// Resume from the accounts checkpointed to this file, if any, and checkpoint them back
+    /// once the transactions are applied, skipping files already applied
+    #[cfg(feature = "checkpoint")]
+    #[arg(long, conflicts_with = "workers")]
+    checkpoint: Option<PathBuf>,
+    /// Dump Prometheus metrics to this file once processing completes
+    #[cfg(feature = "metrics")]
+    #[arg(long)]
+    metrics_file: Option<PathBuf>,
+    /// Also write the run, the accounts and the rejected transactions to this SQLite
+    /// database, creating it if missing
+    #[cfg(feature = "sqlite")]
+    #[arg(long, value_name = "DB")]
+    sqlite: Option<PathBuf>,
+    /// Also upsert the accounts into a Postgres table, connecting with this connection
+    /// string, e.g. `host=localhost user=postgres`
+    #[cfg(feature = "postgres")]
+    #[arg(long, value_name = "CONN")]
+    postgres: Option<String>,
+    /// Postgres table to upsert the accounts into, created if missing
+    #[cfg(feature = "postgres")]
+    #[arg(
+        long,
+        value_name = "TABLE",
+        default_value = "accounts",
+        requires = "postgres"
+    )]
+    postgres_table: String,
+}
+
+impl ProcessArgs {
+    fn csv_options(&self) -> CsvOptions {
+        CsvOptions {
+            delimiter: self.delimiter,
+            columns: self.columns.clone(),
+        }
+    }
+}
+
+fn parse_delimiter(delimiter: &str) -> Result<u8, String> {
+    match delimiter.as_bytes() {
+        b"tab" | b"\\t" => Ok(b'\t'),
+        // Quotes and line breaks would be taken for csv syntax rather than delimiters.
+        b"\"" | b"\n" | b"\r" => {
+            Err("expected a delimiter other than quotes or line breaks".to_string())
+        }
+        &[delimiter] if delimiter.is_ascii() => Ok(delimiter),
+        _ => Err("expected a single ascii character or `tab`".to_string()),
+    }
+}
+
+/// Transaction fields read from csv input, in the order of its columns.
+const TRANSACTION_FIELDS: [&str; 10] = [
+    "type",
+    "client",
+    "tx",
+    "amount",
+    "timestamp",
+    "idempotency_key",
+    "effective",
+    "reason",
+    "asset",
+    "to_asset",
+];
+
+fn parse_column(mapping: &str) -> Result<(Column, String), String> {
+    let (field, column) = mapping
+        .split_once('=')
+        .ok_or("expected a mapping like `tx=txid`")?;
+    if !TRANSACTION_FIELDS.contains(&field) {
+        return Err(format!("unknown transaction field `{field}`"));
+    }
+    let column = match column.parse() {
+        Ok(position) => Column::Position(position),
+        Err(_) => Column::Name(column.to_string()),
+    };
+    Ok((column, field.to_string()))
+}
+
+/// Apply the transactions read from `path`, laid out as per `options`, to `accounts`, handling
+/// the ones breaking business rules as per `strictness`, and skipping malformed rows up to
+/// `max_malformed` if given.
+fn apply_transactions(
+    accounts: &mut Accounts,
+    path: &str,
+    options: &CsvOptions,
+    max_malformed: Option<usize>,
+    strictness: Strictness,
+) -> Result<(), Box<dyn Error>> {
+    #[cfg(feature = "avro")]
+    if path.ends_with(".avro") {
+        let transactions = Transactions::from_avro(File::open(path)?)?;
+        accounts.apply_transaction_iter(transactions.0.into_iter().map(Ok), strictness)?;
+        return Ok(());
+    }
+    let bar = progress_bar(path);
+    let mut transactions = TransactionsCsv::from_csv_with_options(path, options)?;
+    if let Some(bar) = bar.clone() {
+        transactions = transactions.with_progress(move |progress| {
+            // Updating the bar on every record would slow processing down.
+            if progress.records % 1024 == 0 {
+                bar.set_position(progress.bytes);
+                bar.set_message(format!("{} records", progress.records));
+            }
+        });
+    }
+    let mut report = ParseReport::default();
+    let res = match max_malformed {
+        Some(max_malformed) => accounts.apply_transaction_iter(
+            transactions.iter_skip_malformed(&mut report, max_malformed),
+            strictness,
+        ),
+        None => accounts.apply_transaction_iter(transactions.iter(), strictness),
+    };
+    if let Some(bar) = bar {
+        bar.finish_and_clear();
+    }
+    if !report.malformed.is_empty() {
+        eprintln!("{report}");
+    }
+    Ok(res?)
+}
+
+/// Progress bar over the input at `path`, when stderr is a terminal, bounded by the size of
+/// the input if a local file.
+fn progress_bar(path: &str) -> Option<ProgressBar> {
+    if !stderr().is_terminal() {
+        return None;
+    }
+    let (bar, template) = match fs::metadata(path) {
+        Ok(metadata) => (
+            ProgressBar::new(metadata.len()),
+            "{wide_bar} {bytes}/{total_bytes} {msg}",
+        ),
+        Err(_) => (ProgressBar::new_spinner(), "{spinner} {bytes} {msg}"),
+    };
+    Some(bar.with_style(ProgressStyle::with_template(template).ok()?))
+}
+
+#[derive(Args)]
+struct IngestArgs {
+    /// Tcp address to listen on, or unix socket path prefixed with `unix:`
+    #[arg(long, default_value = "127.0.0.1:7878")]
+    listen: String,
+    /// Write accounts as csv to this file on every snapshot
+    #[arg(long, short)]
+    output: PathBuf,
+    /// Seconds between snapshots
+    #[arg(long, default_value_t = 60)]
+    snapshot_interval: u64,
+    /// Skip re-delivered transactions, recording the processed ones to this file if given,
+    /// or else in memory
+    #[arg(long, value_name = "PATH")]
+    dedup: Option<Option<PathBuf>>,
+}
+
+#[cfg(feature = "kafka")]
+#[derive(Args)]
+struct KafkaArgs {
+    /// Address of a Kafka broker, can be repeated
+    #[arg(long = "broker", required = true)]
+    brokers: Vec<String>,
+    /// Topic to consume transactions from
+    #[arg(long)]
+    topic: String,
+    /// Consumer group to commit the consumed offsets to
+    #[arg(long, default_value = "tx-engine")]
+    group: String,
+    /// Encoding of the transaction messages
+    #[arg(long, value_enum, default_value_t = Payload::Json)]
+    payload: Payload,
+    /// Write accounts as csv to this file on every snapshot
+    #[arg(long, short)]
+    output: PathBuf,
+    /// Persist the complete state of accounts to this file on every snapshot, before
+    /// committing offsets, resuming from it on start if present
+    #[arg(long, value_name = "PATH")]
+    checkpoint: PathBuf,
+    /// Seconds between snapshots
+    #[arg(long, default_value_t = 60)]
+    snapshot_interval: u64,
+    /// Skip re-delivered transactions, recording the processed ones in the checkpoint, so
+    /// that the ones applied after it are applied again once re-delivered
+    #[arg(long)]
+    dedup: bool,
+}
+
+#[cfg(feature = "nats")]
+#[derive(Args)]
+struct NatsArgs {
+    /// Url of the NATS server
+    #[arg(long, default_value = "nats://localhost:4222")]
+    server: String,
+    /// Subject to consume transactions from, bound to a JetStream stream
+    #[arg(long)]
+    subject: String,
+    /// Name of the durable consumer tracking the acknowledged messages
+    #[arg(long, default_value = "tx-engine")]
+    durable: String,
+    /// Encoding of the transaction messages
+    #[arg(long, value_enum, default_value_t = Payload::Json)]
+    payload: Payload,
+    /// Write accounts as csv to this file after each batch of messages
+    #[arg(long, short)]
+    output: PathBuf,
+    /// Persist the complete state of accounts to this file after each batch of messages,
+    /// before acknowledging them, resuming from it on start if present
+    #[arg(long, value_name = "PATH")]
+    checkpoint: PathBuf,
+    /// Skip re-delivered transactions, recording the processed ones in the checkpoint, so
+    /// that the ones applied after it are applied again once re-delivered
+    #[arg(long)]
+    dedup: bool,
+}
+
+#[cfg(feature = "server")]
+#[derive(Args)]
+struct ServeArgs {
+    /// Serve the gRPC api on this address
+    #[cfg(feature = "grpc")]
+    #[arg(
+        long,
+        value_name = "ADDR",
+        num_args = 0..=1,
+        default_missing_value = "127.0.0.1:50051"
+    )]
+    grpc: Option<SocketAddr>,
+    /// Serve the REST api on this address
+    #[cfg(feature = "rest")]
+    #[arg(
+        long,
+        value_name = "ADDR",
+        num_args = 0..=1,
+        default_missing_value = "127.0.0.1:8080"
+    )]
+    rest: Option<SocketAddr>,
+    /// Csv file or url with transactions to apply before serving
+    #[arg(long)]
+    transactions: Option<String>,
+    /// Log submitted transactions to this file before applying them, recovering the
+    /// accounts by replaying it on start
+    #[arg(long)]
+    wal: Option<PathBuf>,
+    /// Share accounts with other instances through the Redis server at this url, e.g.
+    /// redis://127.0.0.1/
+    #[cfg(feature = "redis")]
+    #[arg(long, value_name = "URL")]
+    redis: Option<String>,
+    /// Prefix of the keys of accounts stored in Redis
+    #[cfg(feature = "redis")]
+    #[arg(long, default_value = "tx-engine")]
+    redis_prefix: String,
+    #[command(flatten)]
+    config: Box<ConfigArgs>,
+}
+
+/// Apply the transactions read from `path` to the accounts checkpointed to `checkpoint`, if
+/// any, unless already applied to them, checkpointing the resulting accounts.
+#[cfg(feature = "checkpoint")]
+fn apply_checkpointed(
+    accounts: Accounts,
+    checkpoint: &Path,
+    path: &str,
+    options: &CsvOptions,
+    max_malformed: Option<usize>,
+    strictness: Strictness,
+) -> Result<Accounts, Box<dyn Error>> {
+    let mut resumed = if checkpoint.exists() {
+        Checkpoint::load(checkpoint, accounts)
+            .map_err(|e| format!("failed to load checkpoint {}: {e}", checkpoint.display()))?
+    } else {
+        Checkpoint::new(accounts)
+    };
+    if resumed.is_applied(path) {
+        eprintln!("skipping {path}: already applied");
+        return Ok(resumed.into_accounts());
+    }
+    apply_transactions(
+        resumed.accounts_mut(),
+        path,
+        options,
+        max_malformed,
+        strictness,
+    )?;
+    resumed.mark_applied(path);
+    resumed
+        .save(checkpoint)
+        .map_err(|e| format!("failed to save checkpoint {}: {e}", checkpoint.display()))?;
+    Ok(resumed.into_accounts())
+}
+
+/// Apply the transactions of the input to `accounts`, as per the processing arguments.
+fn apply_input(mut accounts: Accounts, args: &ProcessArgs) -> Result<Accounts, Box<dyn Error>> {
+    #[cfg(feature = "mmap")]
+    if args.mmap {
+        let threads = thread::available_parallelism().map_or(1, usize::from);
+        MappedCsv::open(&args.transactions)
+            .map_err(|e| format!("failed to map {}: {e}", args.transactions))?
+            .apply_parallel(&mut accounts, threads, args.strictness.into())?;
+        return Ok(accounts);
+    }
+    #[cfg(feature = "checkpoint")]
+    if let Some(path) = &args.checkpoint {
+        return apply_checkpointed(
+            accounts,
+            path,
+            &args.transactions,
+            &args.csv_options(),
+            args.max_malformed,
+            args.strictness.into(),
+        );
+    }
+    apply_transactions(
+        &mut accounts,
+        &args.transactions,
+        &args.csv_options(),
+        args.max_malformed,
+        args.strictness.into(),
+    )?;
+    Ok(accounts)
+}
+
+fn process(args: ProcessArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let activity = Arc::new(ActivityReport::new(args.activity_period.into()));
+    let chargebacks = Arc::new(ChargebackMonitor::new(ChargebackThresholds {
+        max_dispute_rate: args.max_dispute_rate,
+        max_chargeback_rate: Some(args.max_chargeback_rate),
+    }));
+    #[cfg(feature = "metrics")]
+    let metrics = Arc::new(Metrics::default());
+    #[cfg(feature = "sqlite")]
+    let sink = match &args.sqlite {
+        Some(path) => Some(Arc::new(SqliteSink::open(path).map_err(|e| {
+            format!("failed to open sqlite database {}: {e}", path.display())
+        })?)),
+        None => None,
+    };
+    let config = args.config.config()?;
+    let recurrences = match &args.recurring {
+        Some(path) => Recurrence::from_csv(path)
+            .map_err(|e| format!("failed to read recurrences from {}: {e}", path.display()))?,
+        None => Vec::new(),
+    };
+    let new_accounts = || {
+        let accounts = Accounts::with_config(config.clone());
+        let accounts = recurrences
+            .iter()
+            .cloned()
+            .fold(accounts, Accounts::with_recurrence);
+        let accounts = match args.activity {
+            Some(_) => accounts.with_observer(activity.clone()),
+            None => accounts,
+        };
+        let accounts = match args.chargeback_rates {
+            Some(_) => accounts.with_observer(chargebacks.clone()),
+            None => accounts,
+        };
+        let accounts = match args.anomaly_zscore {
+            Some(threshold) => accounts.with_anomaly_detector(ZScoreDetector {
+                threshold,
+                ..ZScoreDetector::default()
+            }),
+            None => accounts,
+        };
+        #[cfg(feature = "metrics")]
+        let accounts = accounts.with_observer(metrics.clone());
+        #[cfg(feature = "sqlite")]
+        let accounts = match &sink {
+            Some(sink) => accounts.with_observer(sink.clone()),
+            None => accounts,
+        };
+        accounts
+    };
+
+    let mut accounts = if let Some(workers) = args.workers {
+        Pipeline::default()
+            .with_csv_options(args.csv_options())
+            .with_appliers(workers)
+            .run(&args.transactions, new_accounts, args.strictness.into())?
+    } else {
+        apply_input(new_accounts(), &args)?
+    };
+    let pending = accounts.scheduled().count();
+    if args.flush_scheduled {
+        accounts.flush(args.strictness.into())?;
+    } else if pending > 0 {
+        eprintln!("scheduled transactions left pending: {pending}, see --flush-scheduled");
+    }
+    match (&args.output, args.format) {
+        (Some(path), Format::Csv) => accounts
+            .to_csv_file(path, args.append)
+            .map_err(|e| format!("failed to write output to {}: {e}", path.display()))?,
+        (Some(path), Format::Table) => OpenOptions::new()
+            .create(true)
+            .append(args.append)
+            .write(true)
+            .truncate(!args.append)
+            .open(path)
+            .and_then(|file| accounts.to_table(file))
+            .map_err(|e| format!("failed to write output to {}: {e}", path.display()))?,
+        #[cfg(feature = "avro")]
+        (Some(path), Format::Avro) => File::create(path)
+            .map_err(AccountError::from)
+            .and_then(|file| accounts.to_avro(file))
+            .map_err(|e| format!("failed to write output to {}: {e}", path.display()))?,
+        #[cfg(feature = "parquet")]
+        (Some(path), Format::Parquet) => accounts
+            .to_parquet(path)
+            .map_err(|e| format!("failed to write output to {}: {e}", path.display()))?,
+        (None, Format::Csv) => accounts.to_csv()?,
+        (None, Format::Table) => accounts.to_table(stdout())?,
+        #[cfg(feature = "avro")]
+        (None, Format::Avro) => accounts.to_avro(stdout())?,
+        #[cfg(feature = "parquet")]
+        (None, Format::Parquet) => return Err("parquet output requires --output".into()),
+    }
+    for warning in accounts.warnings() {
+        eprintln!("skipped transaction: {warning}");
+    }
+    if args.summary {
+        eprintln!("{}", accounts.report());
+        eprintln!("{}", accounts.funds_summary());
+        eprintln!(
+            "memory: {} bytes",
+            accounts.memory_footprint().total_bytes()
+        );
+    }
+    if let Some(n) = args.top {
+        let top = match args.top_by {
+            TopBy::Total => accounts.top_by(|acc| *acc.total(), n),
+            TopBy::Held => accounts
+                .top_by(|acc| *acc.held(), n)
+                .into_iter()
+                .filter(|acc| *acc.held() > 0.0)
+                .collect(),
+            TopBy::Negative => accounts
+                .top_by(|acc| -acc.available(), n)
+                .into_iter()
+                .filter(|acc| *acc.available() < 0.0)
+                .collect(),
+        };
+        accounts.to_table_of(top, stderr())?;
+    }
+
+    if let Some(path) = &args.activity {
+        File::create(path)
+            .map_err(AccountError::from)
+            .and_then(|file| activity.to_csv(file))
+            .map_err(|e| format!("failed to write activity to {}: {e}", path.display()))?;
+    }
+    if let Some(path) = &args.aging {
+        let now = args.aging_at.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs())
+        });
+        File::create(path)
+            .map_err(AccountError::from)
+            .and_then(|file| accounts.held_funds_aging(now).to_csv(file))
+            .map_err(|e| format!("failed to write aging to {}: {e}", path.display()))?;
+    }
+    if let Some(path) = &args.anomalies {
+        File::create(path)
+            .map_err(csv::Error::from)
+            .and_then(|file| anomalies_to_csv(accounts.anomalies(), file))
+            .map_err(|e| format!("failed to write anomalies to {}: {e}", path.display()))?;
+    }
+    if let Some(path) = &args.chargeback_rates {
+        File::create(path)
+            .map_err(AccountError::from)
+            .and_then(|file| chargebacks.to_csv(file))
+            .map_err(|e| {
+                format!(
+                    "failed to write chargeback rates to {}: {e}",
+                    path.display()
+                )
+            })?;
+    }
+    #[cfg(feature = "metrics")]
+    if let Some(path) = args.metrics_file {
+        std::fs::write(path, metrics.render())?;
+    }
+    #[cfg(feature = "sqlite")]
+    if let Some(sink) = sink {
+        sink.write(&accounts, &args.transactions)?;
+    }
+    #[cfg(feature = "postgres")]
+    if let Some(url) = args.postgres {
+        let config = PostgresConfig {
+            url,
+            table: args.postgres_table,
+            ..PostgresConfig::default()
+        };
+        PostgresSink::connect(&config)
+            .and_then(|sink| sink.upsert(&accounts))
+            .map_err(|e| format!("failed to upsert accounts into postgres: {e}"))?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn reconcile_accounts(produced: PathBuf, expected: PathBuf) -> Result<ExitCode, Box<dyn Error>> {
+    let mismatches = reconcile(
+        &AccountRecord::from_csv(produced)?,
+        &AccountRecord::from_csv(expected)?,
+    );
+    if mismatches.is_empty() {
+        return Ok(ExitCode::SUCCESS);
+    }
+    mismatches_to_csv(&mismatches, stdout())?;
+    Ok(ExitCode::FAILURE)
+}
+
+fn replay_log(
+    log: PathBuf,
+    snapshot: Option<PathBuf>,
+    config: &ConfigArgs,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let mut accounts = Accounts::with_config(config.config()?);
+    WriteAheadLog::replay_file(&log, &mut accounts)
+        .map_err(|e| format!("failed to replay {}: {e}", log.display()))?;
+    let Some(snapshot) = snapshot else {
+        accounts.to_csv()?;
+        return Ok(ExitCode::SUCCESS);
+    };
+    let replayed = accounts
+        .iter_sorted()
+        .map(AccountRecord::from)
+        .collect::<Vec<_>>();
+    let mismatches = reconcile(&replayed, &AccountRecord::from_csv(snapshot)?);
+    if mismatches.is_empty() {
+        return Ok(ExitCode::SUCCESS);
+    }
+    mismatches_to_csv(&mismatches, stdout())?;
+    Ok(ExitCode::FAILURE)
+}
+
+fn output_statement(
+    transactions: String,
+    client: ClientId,
+    format: StatementFormat,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let mut accounts = Accounts::with_config(Config {
+        journal: true,
+        ..Config::default()
+    });
+    apply_transactions(
+        &mut accounts,
+        &transactions,
+        &CsvOptions::default(),
+        None,
+        Strictness::Lenient,
+    )?;
+    let statement = accounts
+        .statement(client)?
+        .ok_or_else(|| format!("no transactions of client {client}"))?;
+    match format {
+        StatementFormat::Csv => statement.to_csv(stdout())?,
+        #[cfg(feature = "json")]
+        StatementFormat::Json => statement.to_json(stdout())?,
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn output_graph(
+    transactions: String,
+    client: ClientId,
+    format: GraphOutput,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let mut accounts = Accounts::with_config(Config {
+        journal: true,
+        ..Config::default()
+    });
+    apply_transactions(
+        &mut accounts,
+        &transactions,
+        &CsvOptions::default(),
+        None,
+        Strictness::Lenient,
+    )?;
+    accounts
+        .graph(client)
+        .ok_or_else(|| format!("no transactions of client {client}"))?
+        .write(format.into(), stdout())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Empty accounts deduplicating transactions in a file or in memory, as per `--dedup`.
+fn accounts_with_dedup(dedup: &Option<Option<PathBuf>>) -> Result<Accounts, Box<dyn Error>> {
+    Ok(match dedup {
+        Some(Some(path)) => Accounts::default().with_dedup(
+            FileDedupStore::open(path)
+                .map_err(|e| format!("failed to open {}: {e}", path.display()))?,
+        ),
+        Some(None) => Accounts::default().with_dedup(MemoryDedupStore::default()),
+        None => Accounts::default(),
+    })
+}
+
+/// Empty accounts deduplicating transactions in memory if `dedup`, the keys being persisted
+/// along with checkpoints rather than to a file of their own, which would get ahead of them.
+#[cfg(any(feature = "kafka", feature = "nats"))]
+fn stream_accounts(dedup: bool) -> Accounts {
+    match dedup {
+        true => Accounts::default().with_dedup(MemoryDedupStore::default()),
+        false => Accounts::default(),
+    }
+}
+
+/// Restore `accounts` from the checkpoint at `path`, if any, e.g. to resume consuming a
+/// stream from the state persisted along with the last committed offsets.
+#[cfg(any(feature = "kafka", feature = "nats"))]
+fn resume(accounts: Accounts, path: &Path) -> Result<Accounts, Box<dyn Error>> {
+    if !path.exists() {
+        return Ok(accounts);
+    }
+    let checkpoint = Checkpoint::load(path, accounts)
+        .map_err(|e| format!("failed to load checkpoint {}: {e}", path.display()))?;
+    Ok(checkpoint.into_accounts())
+}
+
+fn ingest_lines(args: IngestArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let ingest = match args.listen.strip_prefix("unix:") {
+        #[cfg(unix)]
+        Some(path) => {
+            // Remove the socket left behind by a previous run, as binding would fail.
+            if fs::metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+                fs::remove_file(path)?;
+            }
+            UnixListener::bind(path).map(LineIngest::from_unix)
+        }
+        #[cfg(not(unix))]
+        Some(_) => return Err("unix sockets are not supported on this platform".into()),
+        None => TcpListener::bind(&args.listen).map(LineIngest::from_tcp),
+    };
+    let mut ingest = ingest
+        .map_err(|e| format!("failed to listen on {}: {e}", args.listen))?
+        .with_snapshot_interval(Duration::from_secs(args.snapshot_interval));
+    ingest.run(&mut accounts_with_dedup(&args.dedup)?, |accounts| {
+        accounts.to_csv_file(&args.output, false)
+    })?;
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(feature = "kafka")]
+fn consume_kafka(args: KafkaArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let mut ingest = KafkaIngest::new(args.brokers, &args.topic, &args.group, args.payload.into())?
+        .with_snapshot_interval(Duration::from_secs(args.snapshot_interval));
+    let mut accounts = resume(stream_accounts(args.dedup), &args.checkpoint)?;
+    ingest.run(&mut accounts, |accounts| {
+        Checkpoint::save_accounts(accounts, &args.checkpoint)?;
+        accounts.to_csv_file(&args.output, false)
+    })?;
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(feature = "nats")]
+fn consume_nats(args: NatsArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let mut ingest = NatsIngest::new(
+        &args.server,
+        &args.subject,
+        &args.durable,
+        args.payload.into(),
+    )?;
+    let mut accounts = resume(stream_accounts(args.dedup), &args.checkpoint)?;
+    ingest.run(&mut accounts, |accounts| {
+        Checkpoint::save_accounts(accounts, &args.checkpoint)?;
+        accounts.to_csv_file(&args.output, false)
+    })?;
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(feature = "watch")]
+fn watch_dir(dir: PathBuf, output: Option<PathBuf>) -> Result<ExitCode, Box<dyn Error>> {
+    let mut watch =
+        DirectoryWatch::new(&dir).map_err(|e| format!("failed to watch {}: {e}", dir.display()))?;
+    watch.run(&mut Accounts::default(), |_, accounts| match &output {
+        Some(path) => accounts.to_csv_file(path, false),
+        None => accounts.to_csv(),
+    })?;
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(feature = "server")]
+fn serve(args: ServeArgs) -> Result<ExitCode, Box<dyn Error>> {
+    // Accounts are built on the engine thread owning them, loading the given transactions
+    // there before serving, with any error reported back.
+    let (loaded_tx, loaded) = mpsc::channel();
+    let transactions = args.transactions;
+    let config = args.config.config()?;
+    let init = move || {
+        let mut accounts = Accounts::with_config(config);
+        let res = match transactions {
+            Some(path) => apply_transactions(
+                &mut accounts,
+                &path,
+                &CsvOptions::default(),
+                None,
+                Strictness::Lenient,
+            )
+            .map_err(|e| e.to_string()),
+            None => Ok(()),
+        };
+        let _ = loaded_tx.send(res);
+        accounts
+    };
+    #[cfg(feature = "redis")]
+    let store = match &args.redis {
+        Some(url) => Some(Box::new(
+            RedisStateStore::connect(url, &args.redis_prefix)
+                .map_err(|e| format!("failed to connect to {url}: {e}"))?,
+        ) as Box<dyn StateStore>),
+        None => None,
+    };
+    #[cfg(not(feature = "redis"))]
+    let store = None;
+    #[cfg_attr(not(any(feature = "grpc", feature = "rest")), allow(unused_variables))]
+    let engine = match &args.wal {
+        Some(path) => WriteAheadLog::open(path)
+            .and_then(|wal| EngineHandle::spawn_with_store(init, Some(wal), store))
+            .map_err(|e| format!("failed to recover from {}: {e}", path.display()))?,
+        None => EngineHandle::spawn_with_store(init, None, store)?,
+    };
+    loaded.recv()??;
+    Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(async {
+            let mut servers = JoinSet::<Result<(), AccountError>>::new();
+            #[cfg(feature = "grpc")]
+            if let Some(addr) = args.grpc {
+                servers.spawn(serve_grpc(engine.clone(), addr));
+            }
+            #[cfg(feature = "rest")]
+            if let Some(addr) = args.rest {
+                servers.spawn(serve_rest(engine.clone(), addr));
+            }
+            // Servers only return on failure.
+            match servers.join_next().await {
+                Some(res) => res?.map_err(Into::into),
+                None => Err("select at least one server to run".into()),
+            }
+        })
+        .map(|()| ExitCode::SUCCESS)
+}
+
+fn run() -> Result<ExitCode, Box<dyn Error>> {
+    let cli = Cli::parse();
+    match (cli.command, cli.process) {
+        (Some(Command::Process(args)), _) => process(*args),
+        (None, Some(args)) => process(args),
+        (Some(Command::Reconcile { produced, expected }), _) => {
+            reconcile_accounts(produced, expected)
+        }
+        (
+            Some(Command::Replay {
+                log,
+                snapshot,
+                config,
+            }),
+            _,
+        ) => replay_log(log, snapshot, &config),
+        (
+            Some(Command::Statement {
+                transactions,
+                client,
+                format,
+            }),
+            _,
+        ) => output_statement(transactions, client, format),
+        (
+            Some(Command::Graph {
+                transactions,
+                client,
+                format,
+            }),
+            _,
+        ) => output_graph(transactions, client, format),
+        (Some(Command::Ingest(args)), _) => ingest_lines(args),
+        #[cfg(feature = "kafka")]
+        (Some(Command::Kafka(args)), _) => consume_kafka(args),
+        #[cfg(feature = "nats")]
+        (Some(Command::Nats(args)), _) => consume_nats(args),
+        #[cfg(feature = "watch")]
+        (Some(Command::Watch { dir, output }), _) => watch_dir(dir, output),
+        #[cfg(feature = "server")]
+        (Some(Command::Serve(args)), _) => serve(args),
+        (None, None) => Err("provide a csv file with transactions to parse".into()),
+    }
+}
+
+fn main() -> ExitCode {
+    run().unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        ExitCode::FAILURE
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use tx_engine::{
+        Accounts, CsvOptions, Transaction, TransactionType, TransactionsCsv, WriteAheadLog,
+    };
+
+    use super::{parse_column, parse_delimiter, Cli, Command, TRANSACTION_FIELDS};
+
+    #[test]
+    fn read_space_delimited_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transactions.txt");
+        std::fs::write(&path, "type client tx amount\ndeposit 1 1 2.0\n").unwrap();
+        let options = CsvOptions {
+            delimiter: parse_delimiter(" ").unwrap(),
+            ..CsvOptions::default()
+        };
+        let mut transactions_csv =
+            TransactionsCsv::from_csv_with_options(path.to_str().unwrap(), &options).unwrap();
+        assert_eq!(
+            transactions_csv
+                .iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![Transaction::new(
+                TransactionType::Deposit,
+                1,
+                1,
+                Some(2.0),
+                false
+            )]
+        );
+        assert!(parse_delimiter("\"").is_err());
+    }
+
+    #[test]
+    fn replay_with_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("tx.log");
+        std::fs::write(&log, "adjustment,1,1,2.0\n").unwrap();
+        let cli = Cli::try_parse_from([
+            "tx-engine",
+            "replay",
+            log.to_str().unwrap(),
+            "--allow-adjustments",
+        ])
+        .unwrap();
+        let Some(Command::Replay { config, .. }) = cli.command else {
+            panic!("expected the replay command");
+        };
+        let mut accounts = Accounts::with_config(config.config().unwrap());
+        WriteAheadLog::replay_file(&log, &mut accounts).unwrap();
+        assert_eq!(*accounts.get(1).unwrap().total(), 2.0);
+    }
 
-    Ok(())
+    #[test]
+    fn map_every_transaction_field() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cli-mapped.csv");
+        let header = TRANSACTION_FIELDS
+            .map(|field| format!("in_{field}"))
+            .join(",");
+        std::fs::write(
+            &path,
+            format!("{header}\nconvert,1,2,3.0,10,key,20,R1,EUR,USD\n"),
+        )
+        .unwrap();
+        let options = CsvOptions {
+            columns: (TRANSACTION_FIELDS.iter())
+                .map(|field| parse_column(&format!("{field}=in_{field}")).unwrap())
+                .collect(),
+            ..CsvOptions::default()
+        };
+        let mut transactions_csv =
+            TransactionsCsv::from_csv_with_options(path.to_str().unwrap(), &options).unwrap();
+        assert_eq!(
+            transactions_csv
+                .iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![
+                Transaction::new(TransactionType::Convert, 1, 2, Some(3.0), false)
+                    .with_timestamp(10)
+                    .with_idempotency_key("key")
+                    .with_effective(20)
+                    .with_reason("R1")
+                    .with_asset("EUR")
+                    .with_to_asset("USD")
+            ]
+        );
+        // Rates are recorded as conversions apply, rather than read from input.
+        assert!(parse_column("rate=fx_rate").is_err());
+    }
 }
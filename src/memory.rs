@@ -0,0 +1,51 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    mem::size_of,
+};
+
+use super::types::ClientId;
+
+/// Approximate memory used by `Accounts`, in bytes, as allocated by their collections,
+/// whether or not in use, along with the counts of each account.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemoryFootprint {
+    /// Bytes of the accounts themselves, excluding what they store.
+    pub account_bytes: usize,
+    /// Bytes of the transactions stored or journaled by the accounts, along with their
+    /// history and the withdrawals tracked for velocity limits.
+    pub transaction_bytes: usize,
+    /// Bytes of the index of transaction ids across clients, when enforcing unique ids.
+    pub index_bytes: usize,
+    /// Footprint of each account, ordered by client id.
+    pub per_account: Vec<AccountFootprint>,
+}
+
+impl MemoryFootprint {
+    pub fn total_bytes(&self) -> usize {
+        self.account_bytes + self.transaction_bytes + self.index_bytes
+    }
+}
+
+/// Approximate memory used by the transactions of an account.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccountFootprint {
+    pub client: ClientId,
+    /// Transactions stored, i.e. deposits, withdrawals and authorizations.
+    pub transactions: usize,
+    /// Transactions journaled, when journaling is enabled.
+    pub journaled: usize,
+    pub bytes: usize,
+}
+
+/// Bytes allocated by `map`, counting a control byte per bucket as hashbrown does.
+pub(crate) fn hash_map_bytes<K, V>(map: &HashMap<K, V>) -> usize {
+    map.capacity() * (size_of::<(K, V)>() + 1)
+}
+
+pub(crate) fn vec_bytes<T>(vec: &Vec<T>) -> usize {
+    vec.capacity() * size_of::<T>()
+}
+
+pub(crate) fn vec_deque_bytes<T>(vec: &VecDeque<T>) -> usize {
+    vec.capacity() * size_of::<T>()
+}
@@ -0,0 +1,153 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::{
+    account::{Account, AccountError},
+    observer::Observer,
+    transaction::{Transaction, TransactionType},
+};
+
+#[derive(Debug, Default)]
+struct Counters {
+    applied: BTreeMap<&'static str, u64>,
+    rejected: BTreeMap<&'static str, u64>,
+    frozen: u64,
+}
+
+/// Observer counting processed transactions, rendered in the Prometheus text exposition
+/// format. Share it through an `Arc` to render it while or after processing.
+#[derive(Debug)]
+pub struct Metrics {
+    counters: Mutex<Counters>,
+    started: Instant,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            counters: Mutex::default(),
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn applied(&self, type_: TransactionType) -> u64 {
+        let counters = self.counters.lock().unwrap();
+        counters.applied.get(type_.as_str()).copied().unwrap_or(0)
+    }
+
+    pub fn rejected(&self, kind: &str) -> u64 {
+        let counters = self.counters.lock().unwrap();
+        counters.rejected.get(kind).copied().unwrap_or(0)
+    }
+
+    pub fn frozen(&self) -> u64 {
+        self.counters.lock().unwrap().frozen
+    }
+
+    pub fn render(&self) -> String {
+        self.render_with_elapsed(self.started.elapsed())
+    }
+
+    fn render_with_elapsed(&self, elapsed: Duration) -> String {
+        let counters = self.counters.lock().unwrap();
+        let mut out = String::new();
+
+        out.push_str("# HELP tx_engine_transactions_total Transactions applied, per type.\n");
+        out.push_str("# TYPE tx_engine_transactions_total counter\n");
+        for (type_, count) in &counters.applied {
+            writeln!(
+                out,
+                "tx_engine_transactions_total{{type=\"{type_}\"}} {count}"
+            )
+            .unwrap();
+        }
+        out.push_str("# HELP tx_engine_rejections_total Transactions rejected, per error kind.\n");
+        out.push_str("# TYPE tx_engine_rejections_total counter\n");
+        for (kind, count) in &counters.rejected {
+            writeln!(out, "tx_engine_rejections_total{{kind=\"{kind}\"}} {count}").unwrap();
+        }
+        out.push_str("# HELP tx_engine_accounts_frozen_total Accounts frozen.\n");
+        out.push_str("# TYPE tx_engine_accounts_frozen_total counter\n");
+        writeln!(out, "tx_engine_accounts_frozen_total {}", counters.frozen).unwrap();
+
+        let processed =
+            counters.applied.values().sum::<u64>() + counters.rejected.values().sum::<u64>();
+        let throughput = processed as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        out.push_str(
+            "# HELP tx_engine_throughput Transactions processed per second since startup.\n",
+        );
+        out.push_str("# TYPE tx_engine_throughput gauge\n");
+        writeln!(out, "tx_engine_throughput {throughput}").unwrap();
+
+        out
+    }
+}
+
+impl Observer for Metrics {
+    fn on_applied(&self, _account: &Account, tx: &Transaction) {
+        let mut counters = self.counters.lock().unwrap();
+        *counters.applied.entry(tx.type_().as_str()).or_default() += 1;
+    }
+
+    fn on_rejected(&self, _tx: &Transaction, error: &AccountError) {
+        let mut counters = self.counters.lock().unwrap();
+        *counters.rejected.entry(error.kind()).or_default() += 1;
+    }
+
+    fn on_frozen(&self, _account: &Account) {
+        self.counters.lock().unwrap().frozen += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::Metrics;
+    use crate::{Accounts, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn render_metrics() {
+        let metrics = Arc::new(Metrics::default());
+        let mut accounts = Accounts::default().with_observer(metrics.clone());
+        accounts
+            .apply_transaction_iter(
+                vec![
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false),
+                    Transaction::new(TransactionType::Withdrawal, 1, 2, Some(2.0), false),
+                    Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+                    Transaction::new(TransactionType::Chargeback, 1, 1, None, false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Lenient,
+            )
+            .unwrap();
+
+        assert_eq!(
+            metrics.render_with_elapsed(Duration::from_secs(2)),
+            "\
+# HELP tx_engine_transactions_total Transactions applied, per type.
+# TYPE tx_engine_transactions_total counter
+tx_engine_transactions_total{type=\"chargeback\"} 1
+tx_engine_transactions_total{type=\"deposit\"} 1
+tx_engine_transactions_total{type=\"dispute\"} 1
+# HELP tx_engine_rejections_total Transactions rejected, per error kind.
+# TYPE tx_engine_rejections_total counter
+tx_engine_rejections_total{kind=\"withdrawal\"} 1
+# HELP tx_engine_accounts_frozen_total Accounts frozen.
+# TYPE tx_engine_accounts_frozen_total counter
+tx_engine_accounts_frozen_total 1
+# HELP tx_engine_throughput Transactions processed per second since startup.
+# TYPE tx_engine_throughput gauge
+tx_engine_throughput 2
+"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use std::{fs::File, path::Path, thread};
+
+use csv::{ByteRecord, ReaderBuilder};
+use memmap2::Mmap;
+
+use super::{
+    account::{AccountError, Accounts},
+    policy::Strictness,
+    transaction::{Transaction, TransactionError},
+};
+#[cfg(feature = "fast-parse")]
+use super::{line::COLUMNS, scan::scan_transactions};
+
+/// Csv file of transactions mapped into memory, parsed straight from the mapped bytes rather
+/// than through read calls. Being addressable as a whole, the file can be split into chunks
+/// parsed in parallel. Records are expected to span a single line, i.e. quoted fields can't
+/// contain newlines, and compressed files aren't supported.
+pub struct MappedCsv {
+    mmap: Mmap,
+    headers: ByteRecord,
+    body: usize,
+}
+
+impl MappedCsv {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, AccountError> {
+        let file = File::open(path)?;
+        // SAFETY: the file must not be modified while mapped, as for any input being
+        // processed.
+        let mmap = unsafe { Mmap::map(&file)? };
+        let body = mmap
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(mmap.len(), |i| i + 1);
+        let mut headers = reader(&mmap[..body]).byte_headers()?.clone();
+        headers.trim();
+        Ok(Self {
+            mmap,
+            headers,
+            body,
+        })
+    }
+
+    /// Transactions of the whole file, in order. With the `fast-parse` feature enabled, files
+    /// with the columns of the csv input in the same order are parsed by
+    /// `scan_transactions`.
+    pub fn iter(&self) -> impl Iterator<Item = Result<Transaction, TransactionError>> + '_ {
+        self.chunk(&self.mmap[self.body..])
+    }
+
+    /// Split the file into at most `n` chunks of similar size, at record boundaries, whose
+    /// transactions concatenated in order are those of the whole file.
+    pub fn chunks(
+        &self,
+        n: usize,
+    ) -> Vec<impl Iterator<Item = Result<Transaction, TransactionError>> + Send + '_> {
+        let body = &self.mmap[self.body..];
+        let size = body.len().div_ceil(n.max(1)).max(1);
+        let mut chunks = vec![];
+        let mut start = 0;
+        while start < body.len() {
+            let end = match body[(start + size).min(body.len())..]
+                .iter()
+                .position(|&b| b == b'\n')
+            {
+                Some(i) => start + size + i + 1,
+                None => body.len(),
+            };
+            chunks.push(self.chunk(&body[start..end]));
+            start = end;
+        }
+        chunks
+    }
+
+    fn chunk<'a>(
+        &'a self,
+        bytes: &'a [u8],
+    ) -> Box<dyn Iterator<Item = Result<Transaction, TransactionError>> + Send + 'a> {
+        // Files with the columns of the csv input in the same order can be scanned.
+        #[cfg(feature = "fast-parse")]
+        if self.headers.len() <= COLUMNS.len()
+            && (self.headers.iter().zip(COLUMNS)).all(|(header, col)| header == col.as_bytes())
+        {
+            return Box::new(scan_transactions(bytes));
+        }
+        Box::new(reader(bytes).into_byte_records().map(|record| {
+            record
+                .and_then(|mut record| {
+                    record.trim();
+                    record.deserialize(Some(&self.headers))
+                })
+                .map_err(Into::into)
+        }))
+    }
+
+    /// Apply the transactions of the file to `accounts`, as `Accounts::apply_transaction_iter`
+    /// would, parsing chunks of it on `threads` threads while applying them in order.
+    pub fn apply_parallel(
+        &self,
+        accounts: &mut Accounts,
+        threads: usize,
+        strictness: Strictness,
+    ) -> Result<(), AccountError> {
+        let threads = threads.max(1);
+        // Parsing a few chunks per thread at a time bounds the transactions held in memory.
+        let mut chunks = self.chunks(threads * 4).into_iter();
+        loop {
+            let batch = chunks.by_ref().take(threads).collect::<Vec<_>>();
+            if batch.is_empty() {
+                return Ok(());
+            }
+            let parsed = thread::scope(|scope| {
+                batch
+                    .into_iter()
+                    .map(|chunk| scope.spawn(move || chunk.collect::<Vec<_>>()))
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .map(|parsing| parsing.join().expect("parsing doesn't panic"))
+                    .collect::<Vec<_>>()
+            });
+            for transactions in parsed {
+                accounts.apply_transaction_iter(transactions.into_iter(), strictness)?;
+            }
+        }
+    }
+}
+
+fn reader(bytes: &[u8]) -> csv::Reader<&[u8]> {
+    ReaderBuilder::new().has_headers(false).from_reader(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MappedCsv;
+    use crate::{Accounts, Strictness, Transactions};
+
+    #[test]
+    fn parse_mapped_chunks() {
+        let path = "src/test_utils/test_txs_whitespaces.csv";
+        let mapped = MappedCsv::open(path).unwrap();
+        let expected = Transactions::from_csv("src/test_utils/test_txs.csv").unwrap();
+        assert_eq!(
+            mapped.iter().collect::<Result<Vec<_>, _>>().unwrap(),
+            expected.0
+        );
+        let chunks = mapped.chunks(3);
+        assert!(chunks.len() > 1);
+        let chunked = chunks.into_iter().flatten().collect::<Result<Vec<_>, _>>();
+        assert_eq!(chunked.unwrap(), expected.0);
+
+        let mut accounts = Accounts::default();
+        mapped
+            .apply_parallel(&mut accounts, 2, Strictness::Lenient)
+            .unwrap();
+        assert_eq!(*accounts.get(1).unwrap().total(), 0.5);
+    }
+}
@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use async_nats::jetstream::consumer::{pull, Consumer};
+use futures_util::TryStreamExt;
+use tokio::runtime::{Builder, Runtime};
+
+use super::{
+    account::{AccountError, Accounts},
+    payload::PayloadFormat,
+};
+
+/// Long-running consumer applying the transactions published on a NATS JetStream subject
+/// to `Accounts` as they arrive, through a durable pull consumer. Messages are acknowledged
+/// only once the state they lead to is persisted, so that the ones not yet acknowledged are
+/// redelivered after a restart, and applied to the last persisted state.
+pub struct NatsIngest {
+    runtime: Runtime,
+    consumer: Consumer<pull::Config>,
+    format: PayloadFormat,
+    batch_size: usize,
+}
+
+impl NatsIngest {
+    pub fn new(
+        url: &str,
+        subject: &str,
+        durable: &str,
+        format: PayloadFormat,
+    ) -> Result<Self, AccountError> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let consumer = runtime.block_on(async {
+            let jetstream = async_nats::jetstream::new(async_nats::connect(url).await?);
+            let stream = jetstream
+                .get_stream(jetstream.stream_by_subject(subject).await?)
+                .await?;
+            let consumer = stream
+                .get_or_create_consumer(
+                    durable,
+                    pull::Config {
+                        durable_name: Some(durable.to_string()),
+                        filter_subject: subject.to_string(),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            Ok::<_, async_nats::Error>(consumer)
+        })?;
+        Ok(Self {
+            runtime,
+            consumer,
+            format,
+            batch_size: 100,
+        })
+    }
+
+    /// Consume the subject indefinitely, applying transactions to `accounts` as in non-strict
+    /// processing, skipping undecodable messages, and calling `snapshot` with the accounts
+    /// after each batch of messages to persist their complete state, e.g. with
+    /// `Checkpoint::save_accounts`, before acknowledging them. Snapshots are taken on every
+    /// batch, rather than periodically, as messages left unacknowledged for longer than the
+    /// ack wait of the consumer would be redelivered.
+    pub fn run<F>(&mut self, accounts: &mut Accounts, mut snapshot: F) -> Result<(), AccountError>
+    where
+        F: FnMut(&Accounts) -> Result<(), AccountError>,
+    {
+        loop {
+            let messages = self.runtime.block_on(async {
+                let mut batch = self
+                    .consumer
+                    .fetch()
+                    .max_messages(self.batch_size)
+                    .expires(Duration::from_secs(1))
+                    .messages()
+                    .await?;
+                let mut messages = vec![];
+                while let Some(msg) = batch.try_next().await? {
+                    messages.push(msg);
+                }
+                Ok::<_, async_nats::Error>(messages)
+            })?;
+            if messages.is_empty() {
+                continue;
+            }
+            let payloads = messages.iter().map(|msg| &msg.payload[..]);
+            apply_batch(accounts, payloads, self.format, &mut snapshot, || {
+                self.runtime.block_on(async {
+                    for msg in &messages {
+                        msg.ack().await?;
+                    }
+                    Ok::<_, async_nats::Error>(())
+                })?;
+                Ok(())
+            })?;
+        }
+    }
+}
+
+/// Apply the transactions of a batch of message `payloads` to `accounts`, calling `snapshot`
+/// with them, and only then `ack` to acknowledge the messages, which are left unacknowledged
+/// if either fails.
+fn apply_batch<'a, F, K>(
+    accounts: &mut Accounts,
+    payloads: impl IntoIterator<Item = &'a [u8]>,
+    format: PayloadFormat,
+    snapshot: &mut F,
+    ack: K,
+) -> Result<(), AccountError>
+where
+    F: FnMut(&Accounts) -> Result<(), AccountError>,
+    K: FnOnce() -> Result<(), AccountError>,
+{
+    accounts.apply_payloads(payloads, format)?;
+    snapshot(accounts)?;
+    ack()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::apply_batch;
+    use crate::{AccountError, Accounts, PayloadFormat};
+
+    const PAYLOADS: [&[u8]; 2] = [
+        br#"{"type":"deposit","client":1,"tx":1,"amount":2.0}"#,
+        br#"{"type":"withdrawal","client":1,"tx":2,"amount":0.5}"#,
+    ];
+
+    #[test]
+    fn ack_snapshotted_batches() {
+        let mut accounts = Accounts::default();
+        let mut snapshotted = None;
+        let mut acked = false;
+        apply_batch(
+            &mut accounts,
+            PAYLOADS,
+            PayloadFormat::Json,
+            &mut |accounts: &Accounts| {
+                snapshotted = Some(*accounts.get(1).unwrap().total());
+                Ok(())
+            },
+            || {
+                acked = true;
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!((snapshotted, acked), (Some(1.5), true));
+    }
+
+    #[test]
+    fn leave_batches_unacked_on_failed_snapshots() {
+        let mut accounts = Accounts::default();
+        let mut acked = false;
+        let res = apply_batch(
+            &mut accounts,
+            PAYLOADS,
+            PayloadFormat::Json,
+            &mut |_: &Accounts| Err(io::Error::other("disk full").into()),
+            || {
+                acked = true;
+                Ok(())
+            },
+        );
+        assert!(matches!(res, Err(AccountError::Io(_))));
+        assert!(!acked);
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use super::{
+    account::{Account, AccountError},
+    amount::Amount,
+    transaction::Transaction,
+};
+
+/// Listener to transaction lifecycle events, registered on `Accounts`, e.g. for monitoring
+/// or metrics. All callbacks default to doing nothing. Observers are `Send`, so that
+/// accounts can be moved across threads.
+pub trait Observer<A: Amount = f64>: Send {
+    /// Invoked after `tx` was successfully applied to `account`.
+    fn on_applied(&self, _account: &Account<A>, _tx: &Transaction<A>) {}
+
+    /// Invoked when applying `tx` failed with `error`, whether processing continues or not.
+    fn on_rejected(&self, _tx: &Transaction<A>, _error: &AccountError) {}
+
+    /// Invoked when applying a transaction froze `account`.
+    fn on_frozen(&self, _account: &Account<A>) {}
+}
+
+impl<A: Amount, O: Observer<A> + Sync + ?Sized> Observer<A> for Arc<O> {
+    fn on_applied(&self, account: &Account<A>, tx: &Transaction<A>) {
+        (**self).on_applied(account, tx)
+    }
+
+    fn on_rejected(&self, tx: &Transaction<A>, error: &AccountError) {
+        (**self).on_rejected(tx, error)
+    }
+
+    fn on_frozen(&self, account: &Account<A>) {
+        (**self).on_frozen(account)
+    }
+}
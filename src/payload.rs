@@ -0,0 +1,101 @@
+#[cfg(feature = "avro")]
+use std::sync::OnceLock;
+
+#[cfg(feature = "avro")]
+use apache_avro::{from_value, reader::datum::GenericDatumReader, Schema};
+
+#[cfg(feature = "avro")]
+use super::avro::TRANSACTION_SCHEMA;
+use super::{
+    account::{AccountError, Accounts},
+    policy::Strictness,
+    transaction::{Transaction, TransactionError},
+};
+
+/// Encoding of transactions received one per message, e.g. from a message broker.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PayloadFormat {
+    /// A json object with the same fields as the csv input.
+    Json,
+    /// A single avro datum, without header, encoded with `TRANSACTION_SCHEMA`.
+    #[cfg(feature = "avro")]
+    Avro,
+}
+
+impl Transaction {
+    /// Decode a transaction from a single message `payload`.
+    pub fn from_payload(payload: &[u8], format: PayloadFormat) -> Result<Self, TransactionError> {
+        match format {
+            PayloadFormat::Json => Ok(serde_json::from_slice(payload)?),
+            #[cfg(feature = "avro")]
+            PayloadFormat::Avro => {
+                static SCHEMA: OnceLock<Schema> = OnceLock::new();
+                let schema = SCHEMA.get_or_init(|| {
+                    Schema::parse_str(TRANSACTION_SCHEMA).expect("transaction schema is valid")
+                });
+                let value = GenericDatumReader::builder(schema)
+                    .build()?
+                    .read_value(&mut &payload[..])?;
+                Ok(from_value(&value)?)
+            }
+        }
+    }
+}
+
+impl Accounts {
+    /// Apply the transactions of message `payloads` as in non-strict processing, skipping the
+    /// ones which can't be decoded, counted as malformed in the report, rather than failing
+    /// on them, so that consumers don't get stuck redelivering them.
+    pub fn apply_payloads<'a, I: IntoIterator<Item = &'a [u8]>>(
+        &mut self,
+        payloads: I,
+        format: PayloadFormat,
+    ) -> Result<(), AccountError> {
+        let mut transactions = Vec::new();
+        for payload in payloads {
+            match Transaction::from_payload(payload, format) {
+                Ok(tx) => transactions.push(tx),
+                #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+                Err(e) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(error = %e, "skipping malformed message");
+                    self.report_mut().record_malformed();
+                }
+            }
+        }
+        self.apply_transaction_iter(transactions.into_iter().map(Ok), Strictness::Lenient)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PayloadFormat;
+    use crate::{Accounts, Transaction, TransactionType};
+
+    #[test]
+    fn decode_json_payload() {
+        let payload = br#"{"type":"withdrawal","client":1,"tx":2,"amount":1.5}"#;
+        assert_eq!(
+            Transaction::from_payload(payload, PayloadFormat::Json).unwrap(),
+            Transaction::new(TransactionType::Withdrawal, 1, 2, Some(1.5), false)
+        );
+    }
+
+    #[test]
+    fn skip_malformed_payloads() {
+        let mut accounts = Accounts::default();
+        accounts
+            .apply_payloads(
+                [
+                    &br#"{"type":"deposit","client":1,"tx":1,"amount":2.0}"#[..],
+                    b"not a transaction",
+                    br#"{"type":"withdrawal","client":1,"tx":2,"amount":0.5}"#,
+                ],
+                PayloadFormat::Json,
+            )
+            .unwrap();
+        assert_eq!(*accounts.get(1).unwrap().total(), 1.5);
+        assert_eq!(accounts.report().malformed, 1);
+        assert_eq!(accounts.report().rejected, 0);
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    panic,
+    sync::mpsc::{self, Receiver, SyncSender},
+    thread::{self, ScopedJoinHandle},
+};
+
+use csv::ByteRecord;
+
+use super::{
+    account::{AccountError, Accounts},
+    policy::Strictness,
+    transaction::{CsvOptions, Transaction, TransactionError, TransactionsCsv},
+};
+
+/// Processing of a csv file split into stages running on their own threads: reading records,
+/// parsing them into transactions and routing these by client to one of several apply stages,
+/// each applying transactions to its own accounts. Stages are connected by bounded channels,
+/// so that a slow stage back-pressures the previous ones rather than letting them race ahead
+/// and buffer the input in memory. Parsing preserves the input order, so that transactions of
+/// each client are applied in the order they appear.
+#[derive(Clone, Debug)]
+pub struct Pipeline {
+    options: CsvOptions,
+    record_buffer: usize,
+    transaction_buffer: usize,
+    appliers: usize,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            options: CsvOptions::default(),
+            record_buffer: 1024,
+            transaction_buffer: 1024,
+            appliers: 1,
+        }
+    }
+}
+
+impl Pipeline {
+    /// Read input laid out as per `options`.
+    pub fn with_csv_options(mut self, options: CsvOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Buffer at most `records` read records awaiting parsing, rather than 1024.
+    pub fn with_record_buffer(mut self, records: usize) -> Self {
+        self.record_buffer = records;
+        self
+    }
+
+    /// Buffer at most `transactions` parsed transactions awaiting each apply stage, rather than
+    /// 1024.
+    pub fn with_transaction_buffer(mut self, transactions: usize) -> Self {
+        self.transaction_buffer = transactions;
+        self
+    }
+
+    /// Apply transactions on `appliers` threads, at least one, rather than a single one.
+    pub fn with_appliers(mut self, appliers: usize) -> Self {
+        self.appliers = appliers.max(1);
+        self
+    }
+
+    /// Apply the transactions of the csv file at `path`, as `Accounts::apply_transaction_iter`
+    /// would, to accounts initialized with `init` for each apply stage, e.g. to configure them,
+    /// which are merged once processing completes.
+    pub fn run<F>(
+        &self,
+        path: &str,
+        mut init: F,
+        strictness: Strictness,
+    ) -> Result<Accounts, AccountError>
+    where
+        F: FnMut() -> Accounts,
+    {
+        let (records_tx, records) = mpsc::sync_channel(self.record_buffer);
+        thread::scope(|scope| {
+            let reader = scope.spawn(move || read_records(path, &self.options, records_tx));
+            let (appliers, applied): (Vec<_>, Vec<_>) = (0..self.appliers)
+                .map(|_| {
+                    let (sender, transactions) = mpsc::sync_channel(self.transaction_buffer);
+                    let mut accounts = init();
+                    let applied = scope.spawn(move || {
+                        accounts
+                            .apply_transaction_iter(transactions.into_iter().map(Ok), strictness)
+                            .map(|()| accounts)
+                    });
+                    (sender, applied)
+                })
+                .unzip();
+            let routed = route(records, appliers);
+
+            // A failing stage stops the ones before it, so errors of later stages come first.
+            let applied = applied
+                .into_iter()
+                .map(join)
+                .collect::<Result<Vec<_>, _>>()?;
+            routed?;
+            join(reader)?;
+            Ok(applied
+                .into_iter()
+                .reduce(|merged, accounts| {
+                    merged
+                        .merge(accounts)
+                        .expect("apply stages hold disjoint clients")
+                })
+                .expect("there is at least one apply stage"))
+        })
+    }
+}
+
+fn join<T>(stage: ScopedJoinHandle<'_, T>) -> T {
+    stage
+        .join()
+        .unwrap_or_else(|panic| panic::resume_unwind(panic))
+}
+
+/// Send the headers of the csv file at `path`, followed by its records, until done or the
+/// parse stage stops.
+fn read_records(
+    path: &str,
+    options: &CsvOptions,
+    records: SyncSender<ByteRecord>,
+) -> Result<(), AccountError> {
+    let mut rdr = TransactionsCsv::from_csv_with_options(path, options)?.0;
+    if records.send(rdr.byte_headers()?.clone()).is_err() {
+        return Ok(());
+    }
+    for record in rdr.into_byte_records() {
+        if records.send(record?).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Parse records into transactions, sending each to the apply stage of its client, until done
+/// or an apply stage stops.
+fn route(
+    records: Receiver<ByteRecord>,
+    appliers: Vec<SyncSender<Transaction>>,
+) -> Result<(), AccountError> {
+    let Ok(headers) = records.recv() else {
+        return Ok(());
+    };
+    for record in records {
+        let tx: Transaction = record
+            .deserialize(Some(&headers))
+            .map_err(TransactionError::from)?;
+        if appliers[*tx.client() as usize % appliers.len()]
+            .send(tx)
+            .is_err()
+        {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::Pipeline;
+    use crate::{Accounts, Strictness, TransactionsCsv};
+
+    #[test]
+    fn pipeline_matches_sequential_processing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pipeline.csv");
+        let mut csv = String::from("type,client,tx,amount\n");
+        for tx in 0..1000 {
+            let client = tx % 7;
+            csv.push_str(&format!("deposit,{client},{tx},1.0\n"));
+            if tx % 3 == 0 {
+                csv.push_str(&format!("dispute,{client},{tx},\n"));
+            }
+        }
+        fs::write(&path, csv).unwrap();
+        let path = path.to_str().unwrap();
+
+        let accounts = Pipeline::default()
+            .with_record_buffer(4)
+            .with_transaction_buffer(2)
+            .with_appliers(3)
+            .run(path, Accounts::default, Strictness::Strict)
+            .unwrap();
+        let mut transactions = TransactionsCsv::from_csv(path).unwrap();
+        let mut expected = Accounts::default();
+        expected
+            .apply_transaction_iter(transactions.iter(), Strictness::Strict)
+            .unwrap();
+        let balances = |accounts: &Accounts| {
+            accounts
+                .iter_sorted()
+                .map(|acc| (*acc.client(), *acc.available(), *acc.held()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(balances(&accounts), balances(&expected));
+        assert_eq!(accounts.report().disputes, 334);
+
+        fs::write(path, "type,client,tx,amount\nwithdrawal,1,1,1.0\n").unwrap();
+        assert!(Pipeline::default()
+            .run(path, Accounts::default, Strictness::Strict)
+            .is_err());
+    }
+}
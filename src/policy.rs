@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use super::{
+    account::DECIMAL_PRECISION,
+    interest::SECONDS_PER_DAY,
+    types::{ClientId, Timestamp},
+};
+
+/// Lower bound on available funds, enforced on every transaction debiting them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BalancePolicy {
+    /// Available funds can't become negative.
+    #[default]
+    NoNegative,
+    /// Available funds can become negative, down to minus the given overdraft limit.
+    Overdraft(f64),
+    /// Available funds are unbounded.
+    AllowNegative,
+}
+
+impl BalancePolicy {
+    pub fn allows(&self, available: f64) -> bool {
+        match self {
+            Self::NoNegative => available >= 0.0,
+            Self::Overdraft(limit) => available >= -limit,
+            Self::AllowNegative => true,
+        }
+    }
+}
+
+/// Handling of amounts with more decimal places than the four supported.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PrecisionPolicy {
+    /// Keep amounts as they are, the excess precision only being truncated on output.
+    #[default]
+    Keep,
+    /// Truncate amounts to four decimal places.
+    Truncate,
+    /// Round amounts to four decimal places, half away from zero.
+    Round,
+    /// Reject transactions with such amounts.
+    Reject,
+}
+
+impl PrecisionPolicy {
+    /// Amount to apply in place of `amount`, or `None` if the transaction is to be rejected.
+    pub fn apply(&self, amount: f64) -> Option<f64> {
+        let scale = 10.0f64.powi(DECIMAL_PRECISION);
+        let scaled = amount * scale;
+        // Tolerate the representation error of amounts with up to four decimal places.
+        if (scaled - scaled.round()).abs() <= scaled.abs() * f64::EPSILON * 4.0 {
+            return Some(amount);
+        }
+        match self {
+            Self::Keep => Some(amount),
+            Self::Truncate => Some(scaled.trunc() / scale),
+            Self::Round => Some(scaled.round() / scale),
+            Self::Reject => None,
+        }
+    }
+}
+
+/// Handling of transactions breaking business rules, i.e. failing with a recoverable error.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Strictness {
+    /// Skip such transactions.
+    #[default]
+    Lenient,
+    /// Skip such transactions, collecting their errors as warnings.
+    Warn,
+    /// Abort processing on such transactions.
+    Strict,
+}
+
+/// Conditions upon which accounts are frozen.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FreezePolicy {
+    /// Freeze on the first chargeback.
+    #[default]
+    FirstChargeback,
+    /// Freeze once the given number of chargebacks was applied to the account.
+    Chargebacks(usize),
+    /// Freeze once the given number of disputes was applied to the account.
+    Disputes(usize),
+    /// Freeze once held funds exceed the given threshold.
+    HeldAbove(f64),
+}
+
+/// Settlement of disputes left open past a `DisputeTimeout`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeoutAction {
+    /// Resolve stale disputes, releasing the disputed funds.
+    #[default]
+    Resolve,
+    /// Charge stale disputes back, as if the disputed transaction did not take place.
+    Chargeback,
+}
+
+/// Settlement of disputes open for longer than a given time, based on transaction
+/// timestamps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisputeTimeout {
+    /// Time after which disputes are settled, in seconds.
+    pub after: Timestamp,
+    pub action: TimeoutAction,
+}
+
+impl Default for DisputeTimeout {
+    fn default() -> Self {
+        Self {
+            after: 30 * SECONDS_PER_DAY,
+            action: TimeoutAction::default(),
+        }
+    }
+}
+
+/// Withdrawal limits over a rolling time window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VelocityLimit {
+    /// Length of the rolling window, in seconds.
+    pub window: Timestamp,
+    /// Maximum number of withdrawals within the window.
+    pub max_count: Option<usize>,
+    /// Maximum amount withdrawn within the window.
+    pub max_amount: Option<f64>,
+}
+
+impl Default for VelocityLimit {
+    fn default() -> Self {
+        Self {
+            window: SECONDS_PER_DAY,
+            max_count: None,
+            max_amount: None,
+        }
+    }
+}
+
+impl VelocityLimit {
+    /// Whether a withdrawal of `amount` is allowed, given the `count` withdrawals amounting
+    /// to `withdrawn` which were already applied within the window.
+    pub fn allows(&self, count: usize, withdrawn: f64, amount: f64) -> bool {
+        self.max_count.is_none_or(|max| count < max)
+            && self.max_amount.is_none_or(|max| withdrawn + amount <= max)
+    }
+}
+
+/// Withdrawal velocity limits, applying to all clients unless overridden per client.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VelocityLimits {
+    pub default: Option<VelocityLimit>,
+    pub per_client: HashMap<ClientId, VelocityLimit>,
+}
+
+impl VelocityLimits {
+    pub fn limit(&self, client: ClientId) -> Option<&VelocityLimit> {
+        self.per_client.get(&client).or(self.default.as_ref())
+    }
+}
+
+/// Caps on the amount withdrawn by a client per calendar day, in UTC, based on transaction
+/// timestamps, applying to all clients unless overridden per client.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DailyWithdrawalLimits {
+    pub default: Option<f64>,
+    pub per_client: HashMap<ClientId, f64>,
+}
+
+impl DailyWithdrawalLimits {
+    pub fn limit(&self, client: ClientId) -> Option<f64> {
+        self.per_client
+            .get(&client)
+            .or(self.default.as_ref())
+            .copied()
+    }
+}
+
+/// Handling of deposits which would take the total funds of an account past a `BalanceCap`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CapAction {
+    /// Reject the deposit.
+    #[default]
+    Reject,
+    /// Credit the deposit up to the cap, leaving the excess unapplied.
+    Partial,
+}
+
+/// Upper bound on the total funds of accounts, enforced on deposits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BalanceCap {
+    pub max_total: f64,
+    pub action: CapAction,
+}
+
+impl BalanceCap {
+    /// Amount which can still be deposited given `total` funds.
+    pub fn headroom(&self, total: f64) -> f64 {
+        (self.max_total - total).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BalancePolicy, VelocityLimit};
+
+    #[test]
+    fn balance_policies() {
+        assert!(BalancePolicy::NoNegative.allows(0.0));
+        assert!(!BalancePolicy::NoNegative.allows(-0.1));
+        assert!(BalancePolicy::Overdraft(1.0).allows(-1.0));
+        assert!(!BalancePolicy::Overdraft(1.0).allows(-1.1));
+        assert!(BalancePolicy::AllowNegative.allows(f64::MIN));
+    }
+
+    #[test]
+    fn velocity_limits() {
+        let limit = VelocityLimit {
+            max_count: Some(2),
+            max_amount: Some(10.0),
+            ..VelocityLimit::default()
+        };
+        assert!(limit.allows(1, 5.0, 5.0));
+        assert!(!limit.allows(2, 5.0, 1.0));
+        assert!(!limit.allows(1, 5.0, 5.1));
+    }
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use postgres::NoTls;
+use r2d2::Pool;
+use r2d2_postgres::PostgresConnectionManager;
+
+use super::account::{truncate_to_decimal_precision, AccountError, Accounts};
+
+/// Connection and pooling settings of `PostgresSink`.
+#[derive(Clone, Debug)]
+pub struct PostgresConfig {
+    /// Connection string, either as key-value pairs, e.g. `host=localhost user=postgres`, or
+    /// as a `postgresql://` url.
+    pub url: String,
+    /// Table to upsert accounts into, optionally qualified by its schema, created if missing.
+    pub table: String,
+    /// Maximum number of pooled connections.
+    pub pool_size: u32,
+    /// How long to wait for a pooled connection before failing.
+    pub connect_timeout: Duration,
+}
+
+impl Default for PostgresConfig {
+    fn default() -> Self {
+        Self {
+            url: "host=localhost user=postgres".to_string(),
+            table: "accounts".to_string(),
+            pool_size: 4,
+            connect_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Sink upserting final account balances into a Postgres table, keyed by client, e.g. for
+/// teams loading results into their warehouse every run. Connections are pooled, so that
+/// the sink can be shared across threads.
+pub struct PostgresSink {
+    pool: Pool<PostgresConnectionManager<NoTls>>,
+    table: String,
+}
+
+impl PostgresSink {
+    /// Connect as per `config`, creating the table if missing.
+    pub fn connect(config: &PostgresConfig) -> Result<Self, AccountError> {
+        let manager = PostgresConnectionManager::new(config.url.parse()?, NoTls);
+        let pool = Pool::builder()
+            .max_size(config.pool_size)
+            .connection_timeout(config.connect_timeout)
+            .build(manager)?;
+        let table = quote_table(&config.table);
+        pool.get()?.batch_execute(&format!(
+            "CREATE TABLE IF NOT EXISTS {table} (
+                client INTEGER PRIMARY KEY,
+                available DOUBLE PRECISION NOT NULL,
+                held DOUBLE PRECISION NOT NULL,
+                total DOUBLE PRECISION NOT NULL,
+                locked BOOLEAN NOT NULL,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )"
+        ))?;
+        Ok(Self { pool, table })
+    }
+
+    /// Upsert the balances of `accounts` within a single database transaction, so that the
+    /// table never reflects a partial run, returning the number of accounts upserted.
+    /// Accounts of clients missing from `accounts` are left untouched.
+    pub fn upsert(&self, accounts: &Accounts) -> Result<u64, AccountError> {
+        let mut conn = self.pool.get()?;
+        let mut db = conn.transaction()?;
+        let upsert = db.prepare(&upsert_statement(&self.table))?;
+        let mut upserted = 0;
+        for acc in accounts.iter_sorted() {
+            upserted += db.execute(
+                &upsert,
+                &[
+                    &i32::from(*acc.client()),
+                    &truncate_to_decimal_precision(*acc.available()),
+                    &truncate_to_decimal_precision(*acc.held()),
+                    &truncate_to_decimal_precision(*acc.total()),
+                    acc.locked(),
+                ],
+            )?;
+        }
+        db.commit()?;
+        Ok(upserted)
+    }
+}
+
+/// Quote each part of a possibly schema-qualified table name as an identifier.
+fn quote_table(table: &str) -> String {
+    table
+        .split('.')
+        .map(|part| format!("\"{}\"", part.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn upsert_statement(table: &str) -> String {
+    format!(
+        "INSERT INTO {table} (client, available, held, total, locked, updated_at)
+        VALUES ($1, $2, $3, $4, $5, now())
+        ON CONFLICT (client) DO UPDATE SET
+            available = EXCLUDED.available,
+            held = EXCLUDED.held,
+            total = EXCLUDED.total,
+            locked = EXCLUDED.locked,
+            updated_at = EXCLUDED.updated_at"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quote_table, upsert_statement};
+
+    #[test]
+    fn build_upsert_statement() {
+        assert_eq!(quote_table("ledger.accounts"), r#""ledger"."accounts""#);
+        assert_eq!(quote_table(r#"odd"name"#), r#""odd""name""#);
+        let statement = upsert_statement(&quote_table("accounts"));
+        assert!(statement.starts_with(r#"INSERT INTO "accounts" (client,"#));
+        assert!(statement.contains("ON CONFLICT (client) DO UPDATE SET"));
+    }
+}
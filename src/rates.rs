@@ -0,0 +1,235 @@
+#[cfg(feature = "csv")]
+use std::io::Write;
+use std::{collections::BTreeMap, sync::Mutex};
+
+use serde::Serialize;
+
+#[cfg(feature = "csv")]
+use super::account::AccountError;
+use super::{
+    account::{serialize_to_decimal_precision, Account},
+    observer::Observer,
+    transaction::{Transaction, TransactionType},
+    types::ClientId,
+};
+
+/// Rates above which clients are flagged, as percentages of deposits by count, e.g. `1.0`
+/// for one chargeback per hundred deposits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChargebackThresholds {
+    pub max_dispute_rate: Option<f64>,
+    pub max_chargeback_rate: Option<f64>,
+}
+
+impl Default for ChargebackThresholds {
+    fn default() -> Self {
+        Self {
+            max_dispute_rate: None,
+            max_chargeback_rate: Some(1.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Counts {
+    deposits: u64,
+    deposited: f64,
+    disputes: u64,
+    disputed: f64,
+    chargebacks: u64,
+    charged_back: f64,
+}
+
+impl Counts {
+    fn add(&mut self, other: &Counts) {
+        self.deposits += other.deposits;
+        self.deposited += other.deposited;
+        self.disputes += other.disputes;
+        self.disputed += other.disputed;
+        self.chargebacks += other.chargebacks;
+        self.charged_back += other.charged_back;
+    }
+}
+
+/// Disputes and chargebacks of a client, or of all clients, along with their rates as
+/// percentages of deposits, zero without deposits.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ChargebackRates {
+    /// Client, or none for all clients.
+    pub client: Option<ClientId>,
+    pub deposits: u64,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub deposited: f64,
+    pub disputes: u64,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub disputed: f64,
+    pub chargebacks: u64,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub charged_back: f64,
+    /// Disputes as a percentage of deposits, by count.
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub dispute_rate: f64,
+    /// Disputed amounts as a percentage of deposited ones.
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub disputed_rate: f64,
+    /// Chargebacks as a percentage of deposits, by count.
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub chargeback_rate: f64,
+    /// Charged back amounts as a percentage of deposited ones.
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub charged_back_rate: f64,
+    /// Whether a rate is above its threshold.
+    pub flagged: bool,
+}
+
+impl ChargebackRates {
+    fn new(client: Option<ClientId>, counts: &Counts, thresholds: &ChargebackThresholds) -> Self {
+        let percentage = |part: f64, whole: f64| match whole > 0.0 {
+            true => part / whole * 100.0,
+            false => 0.0,
+        };
+        let dispute_rate = percentage(counts.disputes as f64, counts.deposits as f64);
+        let chargeback_rate = percentage(counts.chargebacks as f64, counts.deposits as f64);
+        let above = |rate: f64, max: Option<f64>| max.is_some_and(|max| rate > max);
+        Self {
+            client,
+            deposits: counts.deposits,
+            deposited: counts.deposited,
+            disputes: counts.disputes,
+            disputed: counts.disputed,
+            chargebacks: counts.chargebacks,
+            charged_back: counts.charged_back,
+            dispute_rate,
+            disputed_rate: percentage(counts.disputed, counts.deposited),
+            chargeback_rate,
+            charged_back_rate: percentage(counts.charged_back, counts.deposited),
+            flagged: above(dispute_rate, thresholds.max_dispute_rate)
+                || above(chargeback_rate, thresholds.max_chargeback_rate),
+        }
+    }
+}
+
+/// Observer counting deposits, disputes and chargebacks, to report dispute and chargeback
+/// rates per client and globally, flagging clients above thresholds. Disputes and
+/// chargebacks amount to the deposit they refer to. Share it through an `Arc` to read the
+/// rates after processing.
+#[derive(Debug, Default)]
+pub struct ChargebackMonitor {
+    thresholds: ChargebackThresholds,
+    counts: Mutex<BTreeMap<ClientId, Counts>>,
+}
+
+impl ChargebackMonitor {
+    pub fn new(thresholds: ChargebackThresholds) -> Self {
+        Self {
+            thresholds,
+            counts: Mutex::default(),
+        }
+    }
+
+    /// Rates of all clients, first, then of each client, ordered by client id.
+    pub fn rates(&self) -> Vec<ChargebackRates> {
+        let counts = self.counts.lock().unwrap();
+        let mut all = Counts::default();
+        for client in counts.values() {
+            all.add(client);
+        }
+        let mut rates = vec![ChargebackRates::new(None, &all, &self.thresholds)];
+        rates.extend(
+            counts.iter().map(|(client, counts)| {
+                ChargebackRates::new(Some(*client), counts, &self.thresholds)
+            }),
+        );
+        rates
+    }
+
+    /// Clients with a rate above its threshold, ordered by client id.
+    pub fn flagged(&self) -> Vec<ClientId> {
+        self.rates()
+            .into_iter()
+            .filter(|rates| rates.flagged)
+            .filter_map(|rates| rates.client)
+            .collect()
+    }
+
+    /// Write the rates to `wrt` as csv, the ones of all clients first, with no client.
+    #[cfg(feature = "csv")]
+    pub fn to_csv<W: Write>(&self, wrt: W) -> Result<(), AccountError> {
+        let mut wrt = csv::Writer::from_writer(wrt);
+        for rates in self.rates() {
+            wrt.serialize(rates)?;
+        }
+        wrt.flush()?;
+        Ok(())
+    }
+}
+
+impl Observer for ChargebackMonitor {
+    fn on_applied(&self, account: &Account, tx: &Transaction) {
+        let amount = || account.stored(*tx.tx()).map_or(0.0, Transaction::amount);
+        let mut counts = self.counts.lock().unwrap();
+        let counts = counts.entry(*tx.client()).or_default();
+        match tx.type_() {
+            TransactionType::Deposit => {
+                counts.deposits += 1;
+                counts.deposited += tx.amount();
+            }
+            TransactionType::Dispute => {
+                counts.disputes += 1;
+                counts.disputed += amount();
+            }
+            TransactionType::Chargeback => {
+                counts.chargebacks += 1;
+                counts.charged_back += amount();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(all(test, feature = "csv"))]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{ChargebackMonitor, ChargebackThresholds};
+    use crate::{Accounts, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn compute_chargeback_rates() {
+        let monitor = Arc::new(ChargebackMonitor::new(ChargebackThresholds {
+            max_dispute_rate: Some(50.0),
+            max_chargeback_rate: Some(20.0),
+        }));
+        let mut accounts = Accounts::default().with_observer(monitor.clone());
+        accounts
+            .apply_transaction_iter(
+                [
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(3.0), false),
+                    Transaction::new(TransactionType::Deposit, 1, 2, Some(1.0), false),
+                    Transaction::new(TransactionType::Dispute, 1, 2, None, false),
+                    Transaction::new(TransactionType::Chargeback, 1, 2, None, false),
+                    Transaction::new(TransactionType::Deposit, 2, 3, Some(1.0), false),
+                    Transaction::new(TransactionType::Deposit, 2, 4, Some(1.0), false),
+                    Transaction::new(TransactionType::Dispute, 2, 3, None, false),
+                    Transaction::new(TransactionType::Resolve, 2, 3, None, false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+
+        let mut wrt = vec![];
+        monitor.to_csv(&mut wrt).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&wrt).unwrap(),
+            "\
+client,deposits,deposited,disputes,disputed,chargebacks,charged_back,dispute_rate,disputed_rate,chargeback_rate,charged_back_rate,flagged
+,4,6.0,2,2.0,1,1.0,50.0,33.3333,25.0,16.6666,true
+1,2,4.0,1,1.0,1,1.0,50.0,25.0,50.0,25.0,true
+2,2,2.0,1,1.0,0,0.0,50.0,50.0,0.0,0.0,false
+"
+        );
+        assert_eq!(monitor.flagged(), vec![1]);
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "csv")]
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+#[cfg(feature = "csv")]
+use csv::Error as CsvError;
+use serde::{Deserialize, Serialize};
+
+use super::{account::Account, types::ClientId};
+
+/// Balances are compared up to the output precision of four decimal places.
+const TOLERANCE: f64 = 0.0001;
+
+/// Record of the accounts csv output by the engine.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AccountRecord {
+    pub client: ClientId,
+    pub available: f64,
+    pub held: f64,
+    pub total: f64,
+    pub locked: bool,
+}
+
+#[cfg(feature = "csv")]
+impl AccountRecord {
+    pub fn from_csv<P: AsRef<Path>>(path: P) -> Result<Vec<Self>, CsvError> {
+        Self::from_csv_reader(File::open(path)?)
+    }
+
+    /// Parse the records of accounts csv read from `rdr`, e.g. a string in memory.
+    pub fn from_csv_reader<R: Read>(rdr: R) -> Result<Vec<Self>, CsvError> {
+        csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(rdr)
+            .deserialize()
+            .collect()
+    }
+}
+
+impl From<&Account> for AccountRecord {
+    fn from(acc: &Account) -> Self {
+        Self {
+            client: *acc.client(),
+            available: *acc.available(),
+            held: *acc.held(),
+            total: *acc.total(),
+            locked: *acc.locked(),
+        }
+    }
+}
+
+/// Field of an account whose produced and expected values differ. Accounts missing from
+/// either side are reported with field `account`, and a value of `missing`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Mismatch {
+    pub client: ClientId,
+    pub field: &'static str,
+    pub produced: String,
+    pub expected: String,
+}
+
+/// Compare produced accounts against expected ones, returning mismatches ordered by client.
+pub fn reconcile(produced: &[AccountRecord], expected: &[AccountRecord]) -> Vec<Mismatch> {
+    let by_client = |records: &[AccountRecord]| {
+        records
+            .iter()
+            .map(|rec| (rec.client, rec.clone()))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let (produced, mut expected) = (by_client(produced), by_client(expected));
+
+    let mut mismatches = vec![];
+    for (client, prod) in produced {
+        let Some(exp) = expected.remove(&client) else {
+            mismatches.push(Mismatch {
+                client,
+                field: "account",
+                produced: "present".to_string(),
+                expected: "missing".to_string(),
+            });
+            continue;
+        };
+        for (field, p, e) in [
+            ("available", prod.available, exp.available),
+            ("held", prod.held, exp.held),
+            ("total", prod.total, exp.total),
+        ] {
+            if (p - e).abs() >= TOLERANCE {
+                mismatches.push(Mismatch {
+                    client,
+                    field,
+                    produced: p.to_string(),
+                    expected: e.to_string(),
+                });
+            }
+        }
+        if prod.locked != exp.locked {
+            mismatches.push(Mismatch {
+                client,
+                field: "locked",
+                produced: prod.locked.to_string(),
+                expected: exp.locked.to_string(),
+            });
+        }
+    }
+    for client in expected.into_keys() {
+        mismatches.push(Mismatch {
+            client,
+            field: "account",
+            produced: "missing".to_string(),
+            expected: "present".to_string(),
+        });
+    }
+    mismatches.sort_by_key(|mismatch| mismatch.client);
+    mismatches
+}
+
+#[cfg(feature = "csv")]
+pub fn mismatches_to_csv<W: Write>(mismatches: &[Mismatch], wrt: W) -> Result<(), CsvError> {
+    let mut wrt = csv::Writer::from_writer(wrt);
+    for mismatch in mismatches {
+        wrt.serialize(mismatch)?;
+    }
+    wrt.flush()?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "csv"))]
+mod tests {
+    use super::{mismatches_to_csv, reconcile, AccountRecord};
+
+    #[test]
+    fn reconcile_accounts() {
+        let record = |client, available, held, locked| AccountRecord {
+            client,
+            available,
+            held,
+            total: available + held,
+            locked,
+        };
+        let produced = [
+            record(1, 1.0, 0.0, false),
+            record(2, 1.00004, 0.0, false),
+            record(3, 1.0, 1.0, false),
+            record(4, 1.0, 0.0, false),
+        ];
+        let expected = [
+            record(1, 1.0, 0.0, false),
+            record(2, 1.0, 0.0, false),
+            record(3, 1.0, 0.0, true),
+            record(5, 1.0, 0.0, false),
+        ];
+
+        let mut wrt = vec![];
+        mismatches_to_csv(&reconcile(&produced, &expected), &mut wrt).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&wrt).unwrap(),
+            "\
+client,field,produced,expected
+3,held,1,0
+3,total,2,1
+3,locked,false,true
+4,account,present,missing
+5,account,missing,present
+"
+        );
+    }
+}
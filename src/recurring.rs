@@ -0,0 +1,95 @@
+#[cfg(feature = "csv")]
+use std::{fs::File, io::Read, path::Path};
+
+#[cfg(feature = "csv")]
+use csv::Error as CsvError;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    transaction::TransactionType,
+    types::{ClientId, Timestamp, TransactionId},
+};
+
+/// Instruction to apply a transaction at regular intervals, e.g. a weekly deposit, expanded
+/// into transactions scheduled at the date of each occurrence as processing advances.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Recurrence {
+    #[serde(rename = "type")]
+    pub type_: TransactionType,
+    pub client: ClientId,
+    pub amount: f64,
+    /// Date of the first occurrence.
+    pub start: Timestamp,
+    /// Time between occurrences, in seconds, or zero for a single one.
+    pub every: Timestamp,
+    /// Number of occurrences, if bounded.
+    #[serde(default)]
+    pub count: Option<u32>,
+    /// Id of the transaction of the first occurrence, incremented for each following one.
+    pub first_tx: TransactionId,
+}
+
+#[cfg(feature = "csv")]
+impl Recurrence {
+    pub fn from_csv<P: AsRef<Path>>(path: P) -> Result<Vec<Self>, CsvError> {
+        Self::from_csv_reader(File::open(path)?)
+    }
+
+    /// Parse the recurrences of csv read from `rdr`, e.g. a string in memory.
+    pub fn from_csv_reader<R: Read>(rdr: R) -> Result<Vec<Self>, CsvError> {
+        csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(rdr)
+            .deserialize()
+            .collect()
+    }
+}
+
+impl Recurrence {
+    /// Date and transaction id of the `n`th occurrence, counting from zero, if any.
+    pub fn occurrence(&self, n: u32) -> Option<(Timestamp, TransactionId)> {
+        if self.count.is_some_and(|count| n >= count) || (n > 0 && self.every == 0) {
+            return None;
+        }
+        let at = self.every.checked_mul(n.into())?.checked_add(self.start)?;
+        Some((at, self.first_tx.checked_add(n)?))
+    }
+}
+
+#[cfg(all(test, feature = "csv"))]
+mod tests {
+    use super::Recurrence;
+    use crate::{Accounts, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn expand_recurrences() {
+        let recurrences = Recurrence::from_csv_reader(
+            "type,client,amount,start,every,count,first_tx\n\
+             deposit,1,10.0,100,50,,1000\n\
+             withdrawal,1,4.0,120,50,2,2000\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let mut accounts = Accounts::default();
+        for recurrence in recurrences {
+            accounts.add_recurrence(recurrence);
+        }
+        accounts
+            .apply_transaction_iter(
+                [
+                    Transaction::new(TransactionType::Deposit, 2, 1, Some(1.0), false)
+                        .with_timestamp(260),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+
+        // Deposits at 100, 150, 200 and 250, withdrawals at 120 and 170.
+        assert_eq!(*accounts.get(1).unwrap().total(), 32.0);
+        let account = accounts.get(1).unwrap();
+        assert!(account.history().any(|tx| *tx.tx() == 1003));
+        assert!(!account.history().any(|tx| *tx.tx() == 2002));
+    }
+}
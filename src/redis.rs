@@ -0,0 +1,74 @@
+use redis::{Client, Commands, Connection};
+
+use super::{
+    account::{Account, AccountError, AccountState},
+    engine::StateStore,
+    types::ClientId,
+};
+
+/// Store keeping the complete state of accounts in Redis, as json, so that multiple engine
+/// instances in server mode share balances and disputes with low latency, and accounts
+/// outlive restarts.
+pub struct RedisStateStore {
+    conn: Connection,
+    prefix: String,
+}
+
+impl RedisStateStore {
+    /// Connect to the Redis server at `url`, e.g. `redis://127.0.0.1/`, storing accounts
+    /// under keys starting with `prefix`, so that deployments can share a server.
+    pub fn connect(url: &str, prefix: &str) -> Result<Self, AccountError> {
+        Ok(Self {
+            conn: Client::open(url)?.get_connection()?,
+            prefix: prefix.to_string(),
+        })
+    }
+}
+
+impl StateStore for RedisStateStore {
+    fn load(&mut self, client: ClientId) -> Result<Option<Account>, AccountError> {
+        let state: Option<Vec<u8>> = self.conn.get(account_key(&self.prefix, client))?;
+        Ok(state.map(|state| decode(&state)).transpose()?)
+    }
+
+    fn save(&mut self, account: &Account) -> Result<(), AccountError> {
+        let key = account_key(&self.prefix, *account.client());
+        self.conn.set::<_, _, ()>(key, encode(account)?)?;
+        Ok(())
+    }
+}
+
+fn account_key(prefix: &str, client: ClientId) -> String {
+    format!("{prefix}:account:{client}")
+}
+
+fn encode(account: &Account) -> Result<Vec<u8>, serde_json::Error> {
+    serde_json::to_vec(&AccountState::from(account))
+}
+
+fn decode(state: &[u8]) -> Result<Account, serde_json::Error> {
+    serde_json::from_slice::<AccountState>(state).map(Account::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{account_key, decode, encode};
+    use crate::{Accounts, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn encode_account_state() {
+        assert_eq!(account_key("tx-engine", 7), "tx-engine:account:7");
+        let accounts = Accounts::from_transaction_iter(
+            [
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false),
+                Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+            ]
+            .into_iter()
+            .map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap();
+        let account = accounts.get(1).unwrap();
+        assert_eq!(&decode(&encode(account).unwrap()).unwrap(), account);
+    }
+}
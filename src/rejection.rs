@@ -0,0 +1,34 @@
+use super::{
+    account::AccountError, amount::Amount, report::ProcessingReport, transaction::Transaction,
+};
+
+/// Outcome of handling a rejected transaction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorDecision {
+    /// Skip the transaction and keep processing.
+    Continue,
+    /// Stop processing, failing with the error of the transaction.
+    Abort,
+}
+
+/// Transaction which failed to apply, along with the processing summary so far, which
+/// doesn't account for it yet.
+#[derive(Debug)]
+pub struct RejectedTx<'a, A: Amount = f64> {
+    pub tx: &'a Transaction<A>,
+    pub error: &'a AccountError,
+    pub report: &'a ProcessingReport,
+}
+
+/// Hook invoked for each transaction failing with a recoverable error, deciding whether
+/// processing continues in place of the `Strictness` it's run with, e.g. to abort only once
+/// the rate of rejections exceeds a threshold.
+pub trait ErrorHandler<A: Amount = f64>: Send {
+    fn on_error(&mut self, rejected: &RejectedTx<A>) -> ErrorDecision;
+}
+
+impl<A: Amount, F: FnMut(&RejectedTx<A>) -> ErrorDecision + Send> ErrorHandler<A> for F {
+    fn on_error(&mut self, rejected: &RejectedTx<A>) -> ErrorDecision {
+        self(rejected)
+    }
+}
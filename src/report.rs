@@ -0,0 +1,191 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    account::truncate_to_decimal_precision, transaction::TransactionType, types::ClientId,
+};
+
+/// Summary of the transactions processed by `Accounts`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ProcessingReport {
+    pub deposits: u64,
+    pub withdrawals: u64,
+    pub disputes: u64,
+    pub resolves: u64,
+    pub chargebacks: u64,
+    /// Applied transactions of any other type.
+    pub others: u64,
+    /// Transactions skipped because they broke a business rule.
+    pub rejected: u64,
+    /// Transactions skipped because they were already processed, as per the deduplication
+    /// store.
+    #[serde(default)]
+    pub duplicates: u64,
+    /// Messages skipped because they couldn't be decoded, e.g. by stream consumers.
+    #[serde(default)]
+    pub malformed: u64,
+    /// Transactions withheld for review, as flagged by anomaly detectors.
+    #[serde(default)]
+    pub withheld: u64,
+    /// Deposits partially applied, up to the balance cap.
+    #[serde(default)]
+    pub capped: u64,
+    /// Total amount of partially applied deposits left unapplied.
+    #[serde(default)]
+    pub capped_excess: f64,
+    /// Total amount deposited.
+    pub deposited: f64,
+    /// Total amount withdrawn.
+    pub withdrawn: f64,
+}
+
+impl ProcessingReport {
+    pub fn record_applied(&mut self, type_: TransactionType, amount: f64) {
+        match type_ {
+            TransactionType::Deposit => {
+                self.deposits += 1;
+                self.deposited += amount;
+            }
+            TransactionType::Withdrawal => {
+                self.withdrawals += 1;
+                self.withdrawn += amount;
+            }
+            TransactionType::Dispute => self.disputes += 1,
+            TransactionType::Resolve => self.resolves += 1,
+            TransactionType::Chargeback => self.chargebacks += 1,
+            _ => self.others += 1,
+        }
+    }
+
+    pub fn record_rejected(&mut self) {
+        self.rejected += 1;
+    }
+
+    pub fn record_duplicate(&mut self) {
+        self.duplicates += 1;
+    }
+
+    pub fn record_malformed(&mut self) {
+        self.malformed += 1;
+    }
+
+    pub fn record_withheld(&mut self) {
+        self.withheld += 1;
+    }
+
+    /// Record a deposit partially applied, leaving `excess` unapplied.
+    pub fn record_capped(&mut self, excess: f64) {
+        self.capped += 1;
+        self.capped_excess += excess;
+    }
+
+    /// Add the counts and totals of `other` to this report.
+    pub fn merge(&mut self, other: &ProcessingReport) {
+        self.deposits += other.deposits;
+        self.withdrawals += other.withdrawals;
+        self.disputes += other.disputes;
+        self.resolves += other.resolves;
+        self.chargebacks += other.chargebacks;
+        self.others += other.others;
+        self.rejected += other.rejected;
+        self.duplicates += other.duplicates;
+        self.malformed += other.malformed;
+        self.withheld += other.withheld;
+        self.capped += other.capped;
+        self.capped_excess += other.capped_excess;
+        self.deposited += other.deposited;
+        self.withdrawn += other.withdrawn;
+    }
+}
+
+impl Display for ProcessingReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "deposits: {}", self.deposits)?;
+        writeln!(f, "withdrawals: {}", self.withdrawals)?;
+        writeln!(f, "disputes: {}", self.disputes)?;
+        writeln!(f, "resolves: {}", self.resolves)?;
+        writeln!(f, "chargebacks: {}", self.chargebacks)?;
+        writeln!(f, "others: {}", self.others)?;
+        writeln!(f, "rejected: {}", self.rejected)?;
+        writeln!(f, "duplicates: {}", self.duplicates)?;
+        writeln!(f, "malformed: {}", self.malformed)?;
+        writeln!(f, "withheld: {}", self.withheld)?;
+        writeln!(f, "capped: {}", self.capped)?;
+        writeln!(f, "capped excess: {}", self.capped_excess)?;
+        writeln!(f, "deposited: {}", self.deposited)?;
+        write!(f, "withdrawn: {}", self.withdrawn)
+    }
+}
+
+/// Funds across all accounts, e.g. to check that the books balance after a run, as
+/// returned by `Accounts::funds_summary`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct FundsSummary {
+    pub available: f64,
+    pub held: f64,
+    pub total: f64,
+    /// Number of locked accounts.
+    pub locked: usize,
+    /// Clients with negative available or total funds, ordered by client id.
+    pub negative: Vec<ClientId>,
+}
+
+impl Display for FundsSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "available: {}",
+            truncate_to_decimal_precision(self.available)
+        )?;
+        writeln!(f, "held: {}", truncate_to_decimal_precision(self.held))?;
+        writeln!(f, "total: {}", truncate_to_decimal_precision(self.total))?;
+        writeln!(f, "locked accounts: {}", self.locked)?;
+        if self.negative.is_empty() {
+            return write!(f, "negative balances: none");
+        }
+        let negative = self
+            .negative
+            .iter()
+            .map(ClientId::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "negative balances: {}", negative.join(", "))
+    }
+}
+
+/// Progress through csv input, as reported by `TransactionsCsv::with_progress`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Progress {
+    /// Bytes read from the input, before any decompression.
+    pub bytes: u64,
+    /// Records read from the input, whether they could be parsed or not.
+    pub records: u64,
+}
+
+/// Row of csv input skipped because it couldn't be parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MalformedRow {
+    /// Line of the row in the input, starting from one.
+    pub line: u64,
+    /// Fields of the row, joined by commas.
+    pub content: String,
+    pub error: String,
+}
+
+/// Rows of csv input skipped because they couldn't be parsed, in the order they were read.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParseReport {
+    pub malformed: Vec<MalformedRow>,
+}
+
+impl Display for ParseReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, row) in self.malformed.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "line {}: {}: {}", row.line, row.content, row.error)?;
+        }
+        Ok(())
+    }
+}
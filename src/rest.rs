@@ -0,0 +1,302 @@
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use serde_json::json;
+use tokio::{
+    net::TcpListener,
+    sync::broadcast::{error::RecvError, Receiver},
+};
+
+use super::{
+    account::AccountError,
+    engine::{AccountEvent, EngineHandle},
+    reconcile::AccountRecord,
+    report::ProcessingReport,
+    transaction::Transaction,
+    types::{ClientId, Timestamp},
+};
+
+impl IntoResponse for AccountError {
+    fn into_response(self) -> Response {
+        let status = if self.is_recoverable() {
+            StatusCode::UNPROCESSABLE_ENTITY
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        let body = json!({ "error": self.to_string(), "kind": self.kind(), "code": self.code() });
+        (status, Json(body)).into_response()
+    }
+}
+
+async fn submit_transaction(
+    State(engine): State<EngineHandle>,
+    Json(tx): Json<Transaction>,
+) -> Result<Json<AccountRecord>, AccountError> {
+    Ok(Json(engine.submit(tx).await?))
+}
+
+async fn get_account(
+    State(engine): State<EngineHandle>,
+    Path(client): Path<ClientId>,
+) -> Result<Json<AccountRecord>, StatusCode> {
+    engine
+        .account(client)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn list_accounts(State(engine): State<EngineHandle>) -> Json<Vec<AccountRecord>> {
+    Json(engine.accounts().await)
+}
+
+async fn get_report(State(engine): State<EngineHandle>) -> Json<ProcessingReport> {
+    Json(engine.report().await)
+}
+
+async fn tick(
+    State(engine): State<EngineHandle>,
+    Path(now): Path<Timestamp>,
+) -> Result<StatusCode, AccountError> {
+    engine.tick(now).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn flush(State(engine): State<EngineHandle>) -> Result<StatusCode, AccountError> {
+    engine.flush().await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn set_tier(
+    State(engine): State<EngineHandle>,
+    Path((client, tier)): Path<(ClientId, String)>,
+) -> StatusCode {
+    engine.set_tier(client, tier).await;
+    StatusCode::NO_CONTENT
+}
+
+async fn stream_events(State(engine): State<EngineHandle>, ws: WebSocketUpgrade) -> Response {
+    let events = engine.subscribe();
+    ws.on_upgrade(|socket| push_events(socket, events))
+}
+
+/// Push every account event to `socket` as a json text message, until either side goes away.
+/// Events missed by a lagging subscriber are skipped, as dashboards only need the latest
+/// state.
+async fn push_events(mut socket: WebSocket, mut events: Receiver<AccountEvent>) {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        };
+        let msg = serde_json::to_string(&event).expect("account events serialize to json");
+        if socket.send(Message::Text(msg.into())).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+async fn get_metrics(State(engine): State<EngineHandle>) -> impl IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4",
+        )],
+        engine.metrics().render(),
+    )
+}
+
+fn router(engine: EngineHandle) -> Router {
+    let router = Router::new()
+        .route("/transactions", post(submit_transaction))
+        .route("/accounts", get(list_accounts))
+        .route("/accounts/{client}", get(get_account))
+        .route("/accounts/{client}/tier/{tier}", put(set_tier))
+        .route("/report", get(get_report))
+        .route("/tick/{now}", post(tick))
+        .route("/flush", post(flush))
+        .route("/events", get(stream_events));
+    #[cfg(feature = "metrics")]
+    let router = router.route("/metrics", get(get_metrics));
+    router.with_state(engine)
+}
+
+/// Serve the engine as a json api on `addr` until the server fails:
+/// - `POST /transactions` applies a transaction, returning the updated account;
+/// - `GET /accounts/{client}` and `GET /accounts` return accounts;
+/// - `PUT /accounts/{client}/tier/{tier}` assigns a client to a KYC tier;
+/// - `GET /report` returns the processing report;
+/// - `POST /tick/{now}` and `POST /flush` apply scheduled transactions;
+/// - `GET /events` upgrades to a WebSocket pushing account events as transactions are applied;
+/// - `GET /metrics` returns Prometheus metrics, with the `metrics` feature.
+pub async fn serve_rest(engine: EngineHandle, addr: SocketAddr) -> Result<(), AccountError> {
+    axum::serve(TcpListener::bind(addr).await?, router(engine)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{Shutdown, TcpStream},
+    };
+
+    use tokio::{net::TcpListener, runtime::Builder};
+
+    use super::router;
+    use crate::{Accounts, Config, EngineHandle, KycTiers, TierLimits};
+
+    fn request(addr: &str, req: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(req.as_bytes()).unwrap();
+        let mut resp = String::new();
+        stream.read_to_string(&mut resp).unwrap();
+        stream.shutdown(Shutdown::Both).unwrap();
+        resp
+    }
+
+    fn post(addr: &str, path: &str, body: &str) -> String {
+        request(
+            addr,
+            &format!(
+                "POST {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\
+                 Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            ),
+        )
+    }
+
+    fn get(addr: &str, path: &str) -> String {
+        request(
+            addr,
+            &format!("GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n"),
+        )
+    }
+
+    #[test]
+    fn serve_requests() {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let engine = EngineHandle::spawn(|| {
+            Accounts::with_config(Config {
+                tiers: KycTiers::default().with_tier(
+                    "basic",
+                    TierLimits {
+                        max_withdrawal: Some(1.0),
+                        ..TierLimits::default()
+                    },
+                ),
+                ..Config::default()
+            })
+        });
+        runtime.spawn(async { axum::serve(listener, router(engine)).await });
+
+        let resp = post(
+            &addr,
+            "/transactions",
+            r#"{"type":"deposit","client":1,"tx":1,"amount":2.0}"#,
+        );
+        assert!(resp.starts_with("HTTP/1.1 200"));
+        assert!(
+            resp.ends_with(r#"{"client":1,"available":2.0,"held":0.0,"total":2.0,"locked":false}"#)
+        );
+
+        let resp = post(
+            &addr,
+            "/transactions",
+            r#"{"type":"withdrawal","client":1,"tx":2,"amount":3.0}"#,
+        );
+        assert!(resp.starts_with("HTTP/1.1 422"));
+
+        assert!(get(&addr, "/accounts/2").starts_with("HTTP/1.1 404"));
+        assert!(get(&addr, "/accounts")
+            .ends_with(r#"[{"client":1,"available":2.0,"held":0.0,"total":2.0,"locked":false}]"#));
+        assert!(get(&addr, "/report").contains(r#""rejected":1"#));
+
+        let resp = post(
+            &addr,
+            "/transactions",
+            r#"{"type":"deposit","client":2,"tx":3,"amount":1.0,"effective":100}"#,
+        );
+        assert!(resp.contains(r#""total":0.0"#));
+        assert!(get(&addr, "/accounts/2").starts_with("HTTP/1.1 404"));
+        assert!(post(&addr, "/tick/100", "").starts_with("HTTP/1.1 204"));
+        assert!(get(&addr, "/accounts/2").contains(r#""total":1.0"#));
+
+        let resp = request(
+            &addr,
+            &format!(
+                "PUT /accounts/1/tier/basic HTTP/1.1\r\nHost: {addr}\r\n\
+                 Connection: close\r\nContent-Length: 0\r\n\r\n"
+            ),
+        );
+        assert!(resp.starts_with("HTTP/1.1 204"));
+        let resp = post(
+            &addr,
+            "/transactions",
+            r#"{"type":"withdrawal","client":1,"tx":4,"amount":1.5}"#,
+        );
+        assert!(resp.contains(r#""kind":"tier_withdrawal""#));
+
+        #[cfg(feature = "metrics")]
+        assert!(
+            get(&addr, "/metrics").contains(r#"tx_engine_transactions_total{type="deposit"} 2"#)
+        );
+    }
+
+    #[test]
+    fn push_account_events() {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let engine = EngineHandle::spawn(Accounts::default);
+        runtime.spawn(async { axum::serve(listener, router(engine)).await });
+
+        let mut socket = TcpStream::connect(&addr).unwrap();
+        socket
+            .write_all(
+                format!(
+                    "GET /events HTTP/1.1\r\nHost: {addr}\r\nConnection: Upgrade\r\n\
+                     Upgrade: websocket\r\nSec-WebSocket-Version: 13\r\n\
+                     Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n"
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+        let mut handshake = vec![];
+        while !handshake.ends_with(b"\r\n\r\n") {
+            let mut byte = [0];
+            socket.read_exact(&mut byte).unwrap();
+            handshake.push(byte[0]);
+        }
+        assert!(handshake.starts_with(b"HTTP/1.1 101"));
+
+        post(
+            &addr,
+            "/transactions",
+            r#"{"type":"deposit","client":1,"tx":1,"amount":2.0}"#,
+        );
+        // Unmasked text frame shorter than 126 bytes.
+        let mut header = [0; 2];
+        socket.read_exact(&mut header).unwrap();
+        assert_eq!(header[0], 0x81);
+        let mut payload = vec![0; header[1] as usize];
+        socket.read_exact(&mut payload).unwrap();
+        assert_eq!(
+            String::from_utf8(payload).unwrap(),
+            r#"{"client":1,"tx":1,"type":"deposit","available":2.0,"held":0.0,"total":2.0,"locked":false}"#
+        );
+    }
+}
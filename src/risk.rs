@@ -0,0 +1,37 @@
+use super::{
+    account::Account,
+    amount::Amount,
+    transaction::Transaction,
+    types::{ClientId, TransactionId},
+};
+
+/// Outcome of scoring a transaction before it's applied.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RiskDecision {
+    /// Apply the transaction.
+    Allow,
+    /// Apply the transaction, flagging it for review with the given annotation.
+    Flag(String),
+    /// Reject the transaction, for the given reason.
+    Veto(String),
+}
+
+/// Hook invoked before applying each transaction to the account it refers to, e.g. to plug
+/// fraud detection models into the engine.
+pub trait RiskScorer<A: Amount = f64>: Send {
+    fn score(&self, account: &Account<A>, tx: &Transaction<A>) -> RiskDecision;
+}
+
+impl<A: Amount, F: Fn(&Account<A>, &Transaction<A>) -> RiskDecision + Send> RiskScorer<A> for F {
+    fn score(&self, account: &Account<A>, tx: &Transaction<A>) -> RiskDecision {
+        self(account, tx)
+    }
+}
+
+/// Transaction flagged by a `RiskScorer`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RiskFlag {
+    pub client: ClientId,
+    pub tx: TransactionId,
+    pub annotation: String,
+}
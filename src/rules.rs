@@ -0,0 +1,276 @@
+#[cfg(any(feature = "json", feature = "toml"))]
+use std::{
+    ffi::OsStr,
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use serde::Deserialize;
+
+#[cfg(any(feature = "json", feature = "toml"))]
+use super::account::AccountError;
+use super::{
+    account::Account,
+    amount::Amount,
+    transaction::{Transaction, TransactionType},
+    types::Timestamp,
+};
+
+/// Action taken on the transactions matching a `Rule`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    /// Reject the transaction.
+    #[default]
+    Reject,
+    /// Apply the transaction, flagging it for review along with the risk scorer flags.
+    Flag,
+    /// Reject the transaction and freeze the account.
+    Freeze,
+}
+
+/// Activity of the client over a rolling time window ending at the transaction, based on
+/// transaction timestamps.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct VelocityCondition {
+    /// Length of the window, in seconds.
+    pub window: Timestamp,
+    /// Minimum number of stored transactions of the same type already applied within the
+    /// window.
+    pub min_count: Option<usize>,
+    /// Minimum amount of the stored transactions of the same type already applied within
+    /// the window.
+    pub min_amount: Option<f64>,
+}
+
+/// Conditions over a transaction and the state of the account it refers to, holding if
+/// all the given ones do.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Condition {
+    /// Types of the transaction, any if empty.
+    pub types: Vec<TransactionType>,
+    /// Bounds on the amount of the transaction, zero for the ones referring to stored
+    /// transactions, e.g. disputes.
+    pub min_amount: Option<f64>,
+    pub max_amount: Option<f64>,
+    /// Recent activity of the client, not holding for transactions without a timestamp.
+    pub velocity: Option<VelocityCondition>,
+    /// Whether the account is locked.
+    pub locked: Option<bool>,
+    /// Bounds on the funds of the account before applying the transaction.
+    pub min_available: Option<f64>,
+    pub max_available: Option<f64>,
+    pub min_held: Option<f64>,
+    pub min_total: Option<f64>,
+    /// Minimum number of disputes and chargebacks the account went through.
+    pub min_disputes: Option<usize>,
+    pub min_chargebacks: Option<usize>,
+}
+
+impl Condition {
+    pub fn matches<A: Amount>(&self, account: &Account<A>, tx: &Transaction<A>) -> bool {
+        let amount = tx.amount().to_f64();
+        (self.types.is_empty() || self.types.contains(tx.type_()))
+            && self.min_amount.is_none_or(|min| amount >= min)
+            && self.max_amount.is_none_or(|max| amount <= max)
+            && self
+                .velocity
+                .is_none_or(|velocity| velocity.matches(account, tx))
+            && self.locked.is_none_or(|locked| *account.locked() == locked)
+            && (self.min_available).is_none_or(|min| account.available().to_f64() >= min)
+            && (self.max_available).is_none_or(|max| account.available().to_f64() <= max)
+            && (self.min_held).is_none_or(|min| account.held().to_f64() >= min)
+            && (self.min_total).is_none_or(|min| account.total().to_f64() >= min)
+            && (self.min_disputes).is_none_or(|min| *account.disputes() >= min)
+            && (self.min_chargebacks).is_none_or(|min| *account.chargebacks() >= min)
+    }
+}
+
+impl VelocityCondition {
+    fn matches<A: Amount>(&self, account: &Account<A>, tx: &Transaction<A>) -> bool {
+        let Some(now) = *tx.timestamp() else {
+            return false;
+        };
+        let (count, amount) = account
+            .history()
+            .filter(|stored| stored.type_() == tx.type_())
+            .filter(|stored| {
+                stored
+                    .timestamp()
+                    .is_some_and(|ts| ts <= now && ts.saturating_add(self.window) > now)
+            })
+            .fold((0, 0.0), |(count, amount), stored| {
+                (count + 1, amount + stored.amount().to_f64())
+            });
+        self.min_count.is_none_or(|min| count >= min)
+            && self.min_amount.is_none_or(|min| amount >= min)
+    }
+}
+
+/// Rule taking `action` on the transactions matching its conditions.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Name of the rule, reported along with the transactions it matches.
+    pub name: String,
+    #[serde(default)]
+    pub when: Condition,
+    pub action: RuleAction,
+}
+
+/// Rules evaluated before applying each transaction, in order, the first one matching
+/// determining the action taken, e.g. defined by risk analysts in a JSON or TOML file.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// First rule matching `tx`, given the state of the account it refers to, if any.
+    pub fn evaluate<A: Amount>(&self, account: &Account<A>, tx: &Transaction<A>) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.when.matches(account, tx))
+    }
+
+    #[cfg(feature = "json")]
+    pub fn from_json(rules: &str) -> Result<Self, AccountError> {
+        Ok(serde_json::from_str(rules)?)
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml(rules: &str) -> Result<Self, AccountError> {
+        Ok(toml::from_str(rules)?)
+    }
+
+    /// Parse the rules of the file at `path`, in JSON or TOML as per its extension.
+    #[cfg(any(feature = "json", feature = "toml"))]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, AccountError> {
+        let path = path.as_ref();
+        let rules = fs::read_to_string(path)?;
+        match path.extension().and_then(OsStr::to_str) {
+            #[cfg(feature = "json")]
+            Some("json") => Self::from_json(&rules),
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml(&rules),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("unsupported rules format: {}", path.display()),
+            )
+            .into()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "json", feature = "toml"))]
+mod tests {
+    use super::{RuleAction, RuleSet};
+    use crate::{AccountError, Accounts, Config, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn apply_rules() {
+        let toml = RuleSet::from_toml(
+            r#"
+            [[rules]]
+            name = "frozen account"
+            action = "reject"
+            when = { locked = true }
+
+            [[rules]]
+            name = "rapid withdrawals"
+            action = "freeze"
+            when = { types = ["withdrawal"], velocity = { window = 60, min_count = 2 } }
+
+            [[rules]]
+            name = "large deposit"
+            action = "flag"
+            when = { types = ["deposit"], min_amount = 100.0 }
+
+            [[rules]]
+            name = "disputed account"
+            action = "reject"
+            when = { types = ["withdrawal"], min_held = 0.0001 }
+            "#,
+        )
+        .unwrap();
+        let json = RuleSet::from_json(
+            r#"{"rules": [
+                {"name": "frozen account", "action": "reject", "when": {"locked": true}},
+                {"name": "rapid withdrawals", "action": "freeze",
+                 "when": {"types": ["withdrawal"], "velocity": {"window": 60, "min_count": 2}}},
+                {"name": "large deposit", "action": "flag",
+                 "when": {"types": ["deposit"], "min_amount": 100.0}},
+                {"name": "disputed account", "action": "reject",
+                 "when": {"types": ["withdrawal"], "min_held": 0.0001}}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(toml, json);
+        assert_eq!(toml.rules[1].action, RuleAction::Freeze);
+
+        let mut accounts = Accounts::with_config(Config {
+            rules: toml,
+            ..Config::default()
+        });
+        let tx = |type_, client, tx, amount: Option<f64>, timestamp| {
+            Transaction::new(type_, client, tx, amount, false).with_timestamp(timestamp)
+        };
+        accounts
+            .apply_transaction_iter(
+                [
+                    tx(TransactionType::Deposit, 1, 1, Some(150.0), 0),
+                    tx(TransactionType::Deposit, 2, 2, Some(10.0), 0),
+                    tx(TransactionType::Deposit, 2, 3, Some(5.0), 0),
+                    tx(TransactionType::Dispute, 2, 3, None, 10),
+                    tx(TransactionType::Withdrawal, 2, 4, Some(1.0), 20),
+                    tx(TransactionType::Withdrawal, 1, 5, Some(1.0), 30),
+                    tx(TransactionType::Withdrawal, 1, 6, Some(1.0), 40),
+                    tx(TransactionType::Withdrawal, 1, 7, Some(1.0), 50),
+                    tx(TransactionType::Withdrawal, 1, 8, Some(1.0), 100),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Warn,
+            )
+            .unwrap();
+        assert!(matches!(
+            accounts.warnings(),
+            [
+                AccountError::RuleViolated(2, 4, disputed),
+                AccountError::RuleViolated(1, 7, rapid),
+                AccountError::RuleViolated(1, 8, frozen),
+            ] if disputed == "disputed account"
+                && rapid == "rapid withdrawals"
+                && frozen == "frozen account"
+        ));
+        assert_eq!(accounts.risk_flags().len(), 1);
+        assert_eq!(accounts.risk_flags()[0].annotation, "rule: large deposit");
+        let account = accounts.get(1).unwrap();
+        assert!(*account.locked());
+        assert_eq!(*account.total(), 148.0);
+        // Windows extending past the latest timestamp don't overflow
+        accounts
+            .apply_transaction_iter(
+                [
+                    tx(TransactionType::Deposit, 3, 9, Some(10.0), u64::MAX - 2),
+                    tx(TransactionType::Withdrawal, 3, 10, Some(1.0), u64::MAX - 1),
+                    tx(TransactionType::Withdrawal, 3, 11, Some(1.0), u64::MAX),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Warn,
+            )
+            .unwrap();
+        assert_eq!(*accounts.get(3).unwrap().total(), 8.0);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{iter::once, str};
+
+use csv::{ByteRecord, ReaderBuilder};
+use memchr::{memchr, memchr_iter};
+
+use super::transaction::{invalid_field, Transaction, TransactionError};
+
+/// Powers of ten exactly representable as `f64`.
+const POWERS_OF_TEN: [f64; 16] = [
+    1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15,
+];
+
+/// Transactions of the csv records in `bytes`, one per line, with the columns of the csv
+/// input in the same order and without headers, as for `Transaction::from_byte_record`.
+/// Newlines and commas are located with SIMD-accelerated searches, and numbers parsed by
+/// hand, so lines with quoted fields fall back to a full csv parser.
+pub fn scan_transactions(
+    bytes: &[u8],
+) -> impl Iterator<Item = Result<Transaction, TransactionError>> + Send + '_ {
+    let mut start = 0;
+    memchr_iter(b'\n', bytes)
+        .chain(once(bytes.len()))
+        .filter_map(move |end| {
+            let line = bytes[start.min(end)..end].trim_ascii();
+            start = end + 1;
+            (!line.is_empty()).then(|| scan_line(line))
+        })
+}
+
+fn scan_line(line: &[u8]) -> Result<Transaction, TransactionError> {
+    if memchr(b'"', line).is_some() {
+        let mut record = ByteRecord::new();
+        ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(line)
+            .read_byte_record(&mut record)?;
+        return Transaction::from_byte_record(&record);
+    }
+
+    let mut fields = [&b""[..]; 10];
+    let mut start = 0;
+    for (field, end) in fields
+        .iter_mut()
+        .zip(memchr_iter(b',', line).chain(once(line.len())))
+    {
+        *field = line[start..end].trim_ascii();
+        start = end + 1;
+    }
+    let [type_, client, tx, amount, timestamp, key, effective, reason, asset, to_asset] = fields;
+    let mut scanned = Transaction::new(
+        str::from_utf8(type_)
+            .map_err(|_| invalid_field("type", type_))?
+            .parse()?,
+        parse_integer(client)
+            .and_then(|client| client.try_into().ok())
+            .ok_or_else(|| invalid_field("client", client))?,
+        parse_integer(tx)
+            .and_then(|tx| tx.try_into().ok())
+            .ok_or_else(|| invalid_field("tx", tx))?,
+        match amount {
+            b"" => None,
+            _ => Some(
+                parse_decimal(amount)
+                    .filter(|amount| amount.is_finite())
+                    .ok_or_else(|| invalid_field("amount", amount))?,
+            ),
+        },
+        false,
+    );
+    if !timestamp.is_empty() {
+        scanned = scanned.with_timestamp(
+            parse_integer(timestamp).ok_or_else(|| invalid_field("timestamp", timestamp))?,
+        );
+    }
+    if !key.is_empty() {
+        scanned = scanned.with_idempotency_key(
+            str::from_utf8(key).map_err(|_| invalid_field("idempotency_key", key))?,
+        );
+    }
+    if !effective.is_empty() {
+        scanned = scanned.with_effective(
+            parse_integer(effective).ok_or_else(|| invalid_field("effective", effective))?,
+        );
+    }
+    if !reason.is_empty() {
+        scanned = scanned
+            .with_reason(str::from_utf8(reason).map_err(|_| invalid_field("reason", reason))?);
+    }
+    if !asset.is_empty() {
+        scanned =
+            scanned.with_asset(str::from_utf8(asset).map_err(|_| invalid_field("asset", asset))?);
+    }
+    if !to_asset.is_empty() {
+        scanned = scanned.with_to_asset(
+            str::from_utf8(to_asset).map_err(|_| invalid_field("to_asset", to_asset))?,
+        );
+    }
+    Ok(scanned)
+}
+
+/// Parse an unsigned integer made of ascii digits only.
+fn parse_integer(bytes: &[u8]) -> Option<u64> {
+    if bytes.is_empty() {
+        return None;
+    }
+    bytes.iter().try_fold(0u64, |n, &b| {
+        let digit = b.wrapping_sub(b'0');
+        (digit < 10).then_some(())?;
+        n.checked_mul(10)?.checked_add(u64::from(digit))
+    })
+}
+
+/// Parse a decimal number, by hand when plain digits with an optional sign and fractional
+/// part whose digits fit the mantissa, yielding the correctly rounded result as both the
+/// digits and the power of ten are exact; or else with the standard library, e.g. for
+/// exponents.
+fn parse_decimal(bytes: &[u8]) -> Option<f64> {
+    let (negative, digits) = match bytes.split_first() {
+        Some((b'-', digits)) => (true, digits),
+        Some((b'+', digits)) => (false, digits),
+        _ => (false, bytes),
+    };
+    let (int, frac) = match memchr(b'.', digits) {
+        Some(i) => (&digits[..i], &digits[i + 1..]),
+        None => (digits, &b""[..]),
+    };
+    if !int.is_empty() && int.len() + frac.len() < POWERS_OF_TEN.len() {
+        let mantissa = if frac.is_empty() {
+            parse_integer(int)
+        } else {
+            parse_integer(int)
+                .zip(parse_integer(frac))
+                .map(|(whole, fraction)| whole * 10u64.pow(frac.len() as u32) + fraction)
+        };
+        if let Some(mantissa) = mantissa {
+            let value = mantissa as f64 / POWERS_OF_TEN[frac.len()];
+            return Some(if negative { -value } else { value });
+        }
+    }
+    str::from_utf8(bytes).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_decimal, scan_transactions};
+    use crate::{Transaction, TransactionType};
+
+    #[test]
+    fn scan_lines() {
+        let bytes =
+            b"deposit,1,1,2.0\r\n\nwithdrawal, 1, 2, 1.5,1700000000,\"key,1\"\ndispute,1,x,";
+        let transactions = scan_transactions(bytes).collect::<Vec<_>>();
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(
+            *transactions[0].as_ref().unwrap(),
+            Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false)
+        );
+        assert_eq!(
+            *transactions[1].as_ref().unwrap(),
+            Transaction::new(TransactionType::Withdrawal, 1, 2, Some(1.5), false)
+                .with_timestamp(1700000000)
+                .with_idempotency_key("key,1")
+        );
+        assert!(transactions[2].is_err());
+
+        for decimal in ["0.1", "-12.3456", "1e3", "123456789.123456789", ".5"] {
+            assert_eq!(
+                parse_decimal(decimal.as_bytes()),
+                decimal.parse::<f64>().ok()
+            );
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::{iter::once, sync::Mutex};
+
+use super::{
+    account::{Account, AccountError, Accounts},
+    amount::Amount,
+    policy::Strictness,
+    report::ProcessingReport,
+    transaction::{Transaction, TransactionError},
+    types::ClientId,
+};
+
+/// Accounts split into shards by client id, each behind its own lock, so that multiple
+/// threads can apply transactions concurrently, only contending when their clients share a
+/// shard. Configuration and hooks are per shard, so the ones spanning clients aren't
+/// supported: globally unique transaction ids, deduplication and quarantine retries, which
+/// need later transactions of the stream, not seen by transactions applied one at a time.
+pub struct ShardedAccounts<A: Amount = f64> {
+    shards: Vec<Mutex<Accounts<A>>>,
+}
+
+impl<A: Amount> ShardedAccounts<A> {
+    /// Split accounts into `shards` shards, at least one, each initialized with `init`, e.g.
+    /// to configure them, failing if configured with unsupported options.
+    pub fn new<F: FnMut() -> Accounts<A>>(
+        shards: usize,
+        mut init: F,
+    ) -> Result<Self, AccountError> {
+        let shards = (0..shards.max(1))
+            .map(|_| {
+                let accounts = init();
+                if accounts.config().unique_tx_ids {
+                    return Err(AccountError::ShardConfig("unique transaction ids"));
+                }
+                if accounts.config().quarantine_retries > 0 {
+                    return Err(AccountError::ShardConfig("quarantine retries"));
+                }
+                if accounts.dedup().is_some() {
+                    return Err(AccountError::ShardConfig("deduplication"));
+                }
+                Ok(Mutex::new(accounts))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { shards })
+    }
+
+    fn shard(&self, client: ClientId) -> &Mutex<Accounts<A>> {
+        &self.shards[client as usize % self.shards.len()]
+    }
+
+    /// Apply `tx` to the shard of its client, as `Accounts::apply_transaction_iter` would.
+    pub fn apply_transaction(
+        &self,
+        tx: Transaction<A>,
+        strictness: Strictness,
+    ) -> Result<(), AccountError> {
+        self.shard(*tx.client())
+            .lock()
+            .unwrap()
+            .apply_transaction_iter(once(Ok(tx)), strictness)
+    }
+
+    /// Apply transactions one at a time, each locking only the shard of its client.
+    pub fn apply_transaction_iter<T: Iterator<Item = Result<Transaction<A>, TransactionError>>>(
+        &self,
+        tx_iter: T,
+        strictness: Strictness,
+    ) -> Result<(), AccountError> {
+        for tx in tx_iter {
+            self.apply_transaction(tx?, strictness)?;
+        }
+        Ok(())
+    }
+
+    /// Call `f` with the account of `client`, if any, while holding the lock of its shard.
+    pub fn with_account<R, F: FnOnce(Option<&Account<A>>) -> R>(
+        &self,
+        client: ClientId,
+        f: F,
+    ) -> R {
+        f(self.shard(client).lock().unwrap().get(client))
+    }
+
+    /// Summary of the transactions processed so far by all shards.
+    pub fn report(&self) -> ProcessingReport {
+        let mut report = ProcessingReport::default();
+        for shard in &self.shards {
+            report.merge(shard.lock().unwrap().report());
+        }
+        report
+    }
+
+    /// Combine the shards into a single `Accounts`, retaining the configuration and hooks of
+    /// the first one, failing if accounts of the same client clash, e.g. when initialized
+    /// with the same accounts.
+    pub fn into_accounts(self) -> Result<Accounts<A>, AccountError> {
+        let mut shards = self
+            .shards
+            .into_iter()
+            .map(|shard| shard.into_inner().unwrap());
+        let first = shards.next().expect("there is at least one shard");
+        Ok(shards.try_fold(first, Accounts::merge)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{iter::once, thread};
+
+    use super::ShardedAccounts;
+    use crate::{
+        AccountError, Accounts, Config, MemoryDedupStore, MergeError, Strictness, Transaction,
+        TransactionType,
+    };
+
+    #[test]
+    fn apply_from_multiple_threads() {
+        let accounts = ShardedAccounts::new(4, Accounts::default).unwrap();
+        thread::scope(|scope| {
+            for client in 1..=8 {
+                let accounts = &accounts;
+                scope.spawn(move || {
+                    for tx in 0..100 {
+                        let tx = Transaction::new(
+                            TransactionType::Deposit,
+                            client,
+                            u32::from(client) * 100 + tx,
+                            Some(1.0),
+                            false,
+                        );
+                        accounts.apply_transaction(tx, Strictness::Strict).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(accounts.with_account(3, |acc| *acc.unwrap().total()), 100.0);
+        assert_eq!(accounts.report().deposits, 800);
+        let accounts = accounts.into_accounts().unwrap();
+        assert_eq!(accounts.len(), 8);
+        assert!(accounts.values().all(|acc| *acc.total() == 100.0));
+    }
+
+    #[test]
+    fn reject_options_spanning_shards() {
+        // Transaction ids reused across shards would go unnoticed.
+        let unique_tx_ids = || -> Accounts {
+            Accounts::with_config(Config {
+                unique_tx_ids: true,
+                ..Config::default()
+            })
+        };
+        assert!(matches!(
+            ShardedAccounts::new(2, unique_tx_ids),
+            Err(AccountError::ShardConfig("unique transaction ids"))
+        ));
+        let quarantine = || -> Accounts {
+            Accounts::with_config(Config {
+                quarantine_retries: 1,
+                ..Config::default()
+            })
+        };
+        assert!(matches!(
+            ShardedAccounts::new(2, quarantine),
+            Err(AccountError::ShardConfig("quarantine retries"))
+        ));
+        let dedup = || -> Accounts { Accounts::default().with_dedup(MemoryDedupStore::default()) };
+        assert!(matches!(
+            ShardedAccounts::new(2, dedup),
+            Err(AccountError::ShardConfig("deduplication"))
+        ));
+    }
+
+    #[test]
+    fn merge_overlapping_shards() {
+        // Every shard is initialized with the same account.
+        let init = || {
+            let deposit = Transaction::new(TransactionType::Deposit, 1, 1, Some(1.0), false);
+            Accounts::from_transaction_iter(once(Ok(deposit)), Strictness::Strict).unwrap()
+        };
+        let accounts = ShardedAccounts::new(2, init).unwrap();
+        assert!(matches!(
+            accounts.into_accounts(),
+            Err(AccountError::Merge(MergeError::TransactionClash(1, 1)))
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use bincode::{
+    config::{self, Configuration},
+    error::DecodeError,
+    serde::{decode_from_std_read, encode_into_std_write},
+};
+
+use super::account::{Account, AccountError, AccountState, Accounts};
+
+/// Leading bytes of snapshots, telling them apart from other files.
+const MAGIC: &[u8; 4] = b"TXES";
+
+const VERSION: u32 = 7;
+
+const CONFIG: Configuration = config::standard();
+
+impl Accounts {
+    /// Save the complete state of accounts to `path` as a compact binary snapshot, atomically
+    /// replacing any previous one once written. Following a header with the format version,
+    /// the processing report is encoded, then accounts one at a time, ordered by client id,
+    /// so that memory use doesn't grow with the number of accounts.
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<(), AccountError> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let mut wrt = BufWriter::new(File::create(&tmp)?);
+        wrt.write_all(MAGIC)?;
+        wrt.write_all(&VERSION.to_le_bytes())?;
+        encode_into_std_write(self.report(), &mut wrt, CONFIG)?;
+        encode_into_std_write(self.len() as u64, &mut wrt, CONFIG)?;
+        for acc in self.iter_sorted() {
+            encode_into_std_write(AccountState::from(acc), &mut wrt, CONFIG)?;
+        }
+        wrt.flush()?;
+        wrt.get_ref().sync_all()?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// Load accounts from the snapshot saved at `path`. As with deserialized accounts,
+    /// configuration and hooks aren't restored: merge the loaded accounts into configured ones
+    /// with `Accounts::merge` to keep processing with them.
+    pub fn load_snapshot<P: AsRef<Path>>(path: P) -> Result<Self, AccountError> {
+        let mut rdr = BufReader::new(File::open(path)?);
+        let mut magic = [0; 4];
+        rdr.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(DecodeError::Other("not a snapshot").into());
+        }
+        let mut version = [0; 4];
+        rdr.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != VERSION {
+            return Err(AccountError::SnapshotVersion(version));
+        }
+
+        let mut accounts = Self::default();
+        *accounts.report_mut() = decode_from_std_read(&mut rdr, CONFIG)?;
+        let len: u64 = decode_from_std_read(&mut rdr, CONFIG)?;
+        for _ in 0..len {
+            let state: AccountState = decode_from_std_read(&mut rdr, CONFIG)?;
+            let acc = Account::from(state);
+            accounts.insert(*acc.client(), acc);
+        }
+        Ok(accounts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, iter::once};
+
+    use crate::{AccountError, Accounts, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn restore_from_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot.bin");
+        let accounts = Accounts::from_transaction_iter(
+            [
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false),
+                Transaction::new(TransactionType::Deposit, 1, 2, Some(1.0), false),
+                Transaction::new(TransactionType::Dispute, 1, 2, None, false),
+                Transaction::new(TransactionType::Deposit, 2, 3, Some(1.0), false),
+                Transaction::new(TransactionType::Dispute, 2, 3, None, false),
+                Transaction::new(TransactionType::Chargeback, 2, 3, None, false),
+            ]
+            .into_iter()
+            .map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap();
+        accounts.save_snapshot(&path).unwrap();
+
+        let mut restored = Accounts::load_snapshot(&path).unwrap();
+        assert_eq!(restored.get(1), accounts.get(1));
+        assert_eq!(restored.get(2), accounts.get(2));
+        assert_eq!(restored.report(), accounts.report());
+        // Disputes of the snapshotted accounts can still be resolved.
+        restored
+            .apply_transaction_iter(
+                once(Ok(Transaction::new(
+                    TransactionType::Resolve,
+                    1,
+                    2,
+                    None,
+                    false,
+                ))),
+                Strictness::Strict,
+            )
+            .unwrap();
+        assert_eq!(*restored.get(1).unwrap().available(), 3.0);
+
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[4] = 8;
+        fs::write(&path, bytes).unwrap();
+        assert!(matches!(
+            Accounts::load_snapshot(&path),
+            Err(AccountError::SnapshotVersion(8))
+        ));
+    }
+}
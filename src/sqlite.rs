@@ -0,0 +1,230 @@
+use std::{
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{params, Connection};
+
+use super::{
+    account::{truncate_to_decimal_precision, AccountError, Accounts},
+    observer::Observer,
+    transaction::Transaction,
+    types::Timestamp,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    input TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    finished_at INTEGER NOT NULL,
+    deposits INTEGER NOT NULL,
+    withdrawals INTEGER NOT NULL,
+    disputes INTEGER NOT NULL,
+    resolves INTEGER NOT NULL,
+    chargebacks INTEGER NOT NULL,
+    others INTEGER NOT NULL,
+    rejected INTEGER NOT NULL,
+    duplicates INTEGER NOT NULL,
+    deposited REAL NOT NULL,
+    withdrawn REAL NOT NULL
+);
+CREATE TABLE IF NOT EXISTS accounts (
+    run_id INTEGER NOT NULL REFERENCES runs (id),
+    client INTEGER NOT NULL,
+    available REAL NOT NULL,
+    held REAL NOT NULL,
+    total REAL NOT NULL,
+    locked INTEGER NOT NULL,
+    PRIMARY KEY (run_id, client)
+);
+CREATE TABLE IF NOT EXISTS rejected_transactions (
+    run_id INTEGER NOT NULL REFERENCES runs (id),
+    type TEXT NOT NULL,
+    client INTEGER NOT NULL,
+    tx INTEGER NOT NULL,
+    amount REAL NOT NULL,
+    kind TEXT NOT NULL,
+    code INTEGER NOT NULL,
+    error TEXT NOT NULL
+);
+";
+
+/// Transaction which failed to apply, as recorded by `SqliteSink`.
+struct Rejection {
+    tx: Transaction,
+    kind: &'static str,
+    code: u16,
+    error: String,
+}
+
+/// Sink writing the results of processing runs to a SQLite database, for ad-hoc querying:
+/// run metadata and the processing report to the `runs` table, accounts to the `accounts`
+/// table, and rejected transactions, as observed during the run, to the
+/// `rejected_transactions` table. Register it as an observer through an `Arc`, then write
+/// the processed accounts once processing completes.
+pub struct SqliteSink {
+    conn: Mutex<Connection>,
+    rejected: Mutex<Vec<Rejection>>,
+    started_at: Timestamp,
+}
+
+impl SqliteSink {
+    /// Open the database at `path`, creating it and its tables if missing. Runs are appended
+    /// to the ones already in the database.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, AccountError> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            rejected: Mutex::default(),
+            started_at: now(),
+        })
+    }
+
+    /// Write a run over `input`, e.g. the transactions file, with `accounts` and the
+    /// transactions rejected so far, in a single database transaction. Returns the id of the
+    /// run.
+    pub fn write(&self, accounts: &Accounts, input: &str) -> Result<i64, AccountError> {
+        let mut conn = self.conn.lock().unwrap();
+        let db = conn.transaction()?;
+        let report = accounts.report();
+        db.execute(
+            "INSERT INTO runs (
+                input, started_at, finished_at, deposits, withdrawals, disputes, resolves,
+                chargebacks, others, rejected, duplicates, deposited, withdrawn
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                input,
+                self.started_at,
+                now(),
+                report.deposits,
+                report.withdrawals,
+                report.disputes,
+                report.resolves,
+                report.chargebacks,
+                report.others,
+                report.rejected,
+                report.duplicates,
+                report.deposited,
+                report.withdrawn,
+            ],
+        )?;
+        let run = db.last_insert_rowid();
+        {
+            let mut insert = db.prepare(
+                "INSERT INTO accounts (run_id, client, available, held, total, locked)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for acc in accounts.iter_sorted() {
+                insert.execute(params![
+                    run,
+                    acc.client(),
+                    truncate_to_decimal_precision(*acc.available()),
+                    truncate_to_decimal_precision(*acc.held()),
+                    truncate_to_decimal_precision(*acc.total()),
+                    acc.locked(),
+                ])?;
+            }
+            let mut insert = db.prepare(
+                "INSERT INTO rejected_transactions (
+                    run_id, type, client, tx, amount, kind, code, error
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            for rejection in self.rejected.lock().unwrap().iter() {
+                let tx = &rejection.tx;
+                insert.execute(params![
+                    run,
+                    tx.type_().as_str(),
+                    tx.client(),
+                    tx.tx(),
+                    tx.amount(),
+                    rejection.kind,
+                    rejection.code,
+                    rejection.error,
+                ])?;
+            }
+        }
+        db.commit()?;
+        Ok(run)
+    }
+}
+
+impl Observer for SqliteSink {
+    fn on_rejected(&self, tx: &Transaction, error: &AccountError) {
+        self.rejected.lock().unwrap().push(Rejection {
+            tx: tx.clone(),
+            kind: error.kind(),
+            code: error.code(),
+            error: error.to_string(),
+        });
+    }
+}
+
+fn now() -> Timestamp {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rusqlite::Connection;
+
+    use super::SqliteSink;
+    use crate::{Accounts, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn write_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sink.db");
+        let sink = Arc::new(SqliteSink::open(&path).unwrap());
+        let mut accounts = Accounts::default().with_observer(sink.clone());
+        accounts
+            .apply_transaction_iter(
+                [
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false),
+                    Transaction::new(TransactionType::Withdrawal, 1, 2, Some(3.0), false),
+                    Transaction::new(TransactionType::Deposit, 2, 3, Some(1.5), false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Lenient,
+            )
+            .unwrap();
+        assert_eq!(sink.write(&accounts, "transactions.csv").unwrap(), 1);
+        assert_eq!(sink.write(&accounts, "transactions.csv").unwrap(), 2);
+
+        let conn = Connection::open(&path).unwrap();
+        let run: (String, u64, u64) = conn
+            .query_row(
+                "SELECT input, deposits, rejected FROM runs WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(run, ("transactions.csv".to_string(), 2, 1));
+        let total: f64 = conn
+            .query_row(
+                "SELECT sum(total) FROM accounts WHERE run_id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(total, 3.5);
+        let rejected: (String, u32, String, u16) = conn
+            .query_row(
+                "SELECT type, tx, kind, code FROM rejected_transactions WHERE run_id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            rejected,
+            ("withdrawal".to_string(), 2, "withdrawal".to_string(), 200)
+        );
+    }
+}
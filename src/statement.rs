@@ -0,0 +1,125 @@
+#[cfg(any(feature = "csv", feature = "json"))]
+use std::io::Write;
+
+use serde::Serialize;
+
+#[cfg(any(feature = "csv", feature = "json"))]
+use super::account::AccountError;
+use super::{
+    account::serialize_to_decimal_precision,
+    amount::Amount,
+    transaction::{Transaction, TransactionType},
+    types::{ClientId, Timestamp, TransactionId},
+};
+
+/// Dispute state of a stored transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeStatus {
+    Disputed,
+    ChargedBack,
+}
+
+impl DisputeStatus {
+    pub(crate) fn of<A: Amount>(tx: &Transaction<A>) -> Option<Self> {
+        if *tx.disputed() {
+            Some(Self::Disputed)
+        } else if *tx.charged_back() {
+            Some(Self::ChargedBack)
+        } else {
+            None
+        }
+    }
+}
+
+/// Transaction applied to an account, along with the balances of the account right after it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StatementEntry {
+    #[serde(rename = "type")]
+    pub type_: TransactionType,
+    pub tx: TransactionId,
+    pub timestamp: Option<Timestamp>,
+    /// Amount of the transaction, or of the stored one it refers to, e.g. a disputed deposit.
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub amount: f64,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub available: f64,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub held: f64,
+    #[serde(serialize_with = "serialize_to_decimal_precision")]
+    pub total: f64,
+    pub locked: bool,
+    /// Dispute state of the transaction, or of the stored one it refers to, as of the end of
+    /// the statement.
+    pub dispute: Option<DisputeStatus>,
+}
+
+/// Transactions applied to the account of a client, in the order they were applied, as
+/// returned by `Accounts::statement`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Statement {
+    pub client: ClientId,
+    pub entries: Vec<StatementEntry>,
+}
+
+impl Statement {
+    /// Write the entries of the statement to `wrt` as csv, one per row.
+    #[cfg(feature = "csv")]
+    pub fn to_csv<W: Write>(&self, wrt: W) -> Result<(), AccountError> {
+        let mut wrt = csv::Writer::from_writer(wrt);
+        for entry in &self.entries {
+            wrt.serialize(entry)?;
+        }
+        wrt.flush()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json<W: Write>(&self, wrt: W) -> Result<(), AccountError> {
+        serde_json::to_writer(wrt, self)?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "csv"))]
+mod tests {
+    use crate::{Accounts, Config, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn generate_statement() {
+        let mut accounts = Accounts::with_config(Config {
+            journal: true,
+            ..Config::default()
+        });
+        accounts
+            .apply_transaction_iter(
+                [
+                    Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false),
+                    Transaction::new(TransactionType::Deposit, 1, 2, Some(1.5), false),
+                    Transaction::new(TransactionType::Withdrawal, 1, 3, Some(0.5), false),
+                    Transaction::new(TransactionType::Dispute, 1, 2, None, false),
+                    Transaction::new(TransactionType::Chargeback, 1, 2, None, false),
+                ]
+                .into_iter()
+                .map(Ok),
+                Strictness::Strict,
+            )
+            .unwrap();
+
+        let mut wrt = vec![];
+        let statement = accounts.statement(1).unwrap().unwrap();
+        statement.to_csv(&mut wrt).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&wrt).unwrap(),
+            "\
+type,tx,timestamp,amount,available,held,total,locked,dispute
+deposit,1,,2.0,2.0,0.0,2.0,false,
+deposit,2,,1.5,3.5,0.0,3.5,false,charged_back
+withdrawal,3,,0.5,3.0,0.0,3.0,false,
+dispute,2,,1.5,1.5,1.5,3.0,false,charged_back
+chargeback,2,,1.5,1.5,0.0,1.5,true,charged_back
+"
+        );
+        assert!(accounts.statement(2).unwrap().is_none());
+    }
+}
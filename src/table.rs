@@ -0,0 +1,92 @@
+use std::io::Write;
+
+use super::account::{truncate_to_decimal_precision, Account, Accounts, DECIMAL_PRECISION};
+
+impl Accounts {
+    /// Write accounts to `wrt` as an aligned text table, ordered by client id, for
+    /// inspection in a terminal.
+    pub fn to_table<W: Write>(&self, wrt: W) -> std::io::Result<()> {
+        self.to_table_of(self.iter_sorted(), wrt)
+    }
+
+    /// Write `accounts`, e.g. as returned by `Accounts::top_by`, to `wrt` as with `to_table`,
+    /// in the order given.
+    pub fn to_table_of<'a, I: IntoIterator<Item = &'a Account>, W: Write>(
+        &self,
+        accounts: I,
+        mut wrt: W,
+    ) -> std::io::Result<()> {
+        let with_fees = self.config().fees.is_some();
+        let amount = |num: &f64| {
+            format!(
+                "{:.*}",
+                DECIMAL_PRECISION as usize,
+                truncate_to_decimal_precision(*num)
+            )
+        };
+
+        let mut header = vec!["client", "available", "held", "total", "locked"];
+        if with_fees {
+            header.push("fees");
+        }
+        let mut rows = vec![header.into_iter().map(String::from).collect::<Vec<_>>()];
+        for acc in accounts {
+            let mut row = vec![
+                acc.client().to_string(),
+                amount(acc.available()),
+                amount(acc.held()),
+                amount(acc.total()),
+                acc.locked().to_string(),
+            ];
+            if with_fees {
+                row.push(amount(acc.fees()));
+            }
+            rows.push(row);
+        }
+
+        let widths = (0..rows[0].len())
+            .map(|col| rows.iter().map(|row| row[col].len()).max().unwrap_or(0))
+            .collect::<Vec<_>>();
+        for row in rows {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:>width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(wrt, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Accounts, Strictness, Transaction, TransactionType};
+
+    #[test]
+    fn render_table() {
+        let accounts = Accounts::from_transaction_iter(
+            vec![
+                Transaction::new(TransactionType::Deposit, 2, 1, Some(1.123456), false),
+                Transaction::new(TransactionType::Deposit, 10, 2, Some(1000.0), false),
+                Transaction::new(TransactionType::Dispute, 10, 2, None, false),
+            ]
+            .into_iter()
+            .map(Ok),
+            Strictness::Strict,
+        )
+        .unwrap();
+
+        let mut wrt = vec![];
+        accounts.to_table(&mut wrt).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&wrt).unwrap(),
+            "\
+client  available       held      total  locked
+     2     1.1234     0.0000     1.1234   false
+    10     0.0000  1000.0000  1000.0000   false
+"
+        );
+    }
+}
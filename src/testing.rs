@@ -0,0 +1,184 @@
+use proptest::{
+    arbitrary::Arbitrary,
+    collection::vec,
+    prelude::{any, prop_oneof, BoxedStrategy, Just, Strategy},
+    sample::select,
+};
+
+use super::{
+    account::{Account, Accounts},
+    policy::Strictness,
+    transaction::{Transaction, TransactionType},
+    types::{ClientId, TransactionId},
+};
+
+/// Largest amount generated.
+const MAX_AMOUNT: u64 = 1_000_000;
+
+/// Transactions of an account generated by `arb_account`, at most.
+const MAX_ACCOUNT_TRANSACTIONS: usize = 32;
+
+/// Amounts with up to four decimal places, up to a million.
+pub fn arb_amount() -> impl Strategy<Value = f64> {
+    (0..=MAX_AMOUNT * 10_000).prop_map(|minor| minor as f64 / 10_000.0)
+}
+
+/// Transactions of any type, with an amount when their type takes one, referring to
+/// transactions which may or may not exist.
+pub fn arb_transaction() -> impl Strategy<Value = Transaction> {
+    (
+        any::<TransactionType>(),
+        any::<ClientId>(),
+        any::<TransactionId>(),
+        arb_amount(),
+    )
+        .prop_map(|(type_, client, tx, amount)| {
+            let amount = matches!(
+                type_,
+                TransactionType::Deposit
+                    | TransactionType::Withdrawal
+                    | TransactionType::Auth
+                    | TransactionType::Interest
+                    | TransactionType::Hold
+                    | TransactionType::Adjustment
+                    | TransactionType::Convert
+            )
+            .then_some(amount);
+            Transaction::new(type_, client, tx, amount, false)
+        })
+}
+
+/// Step of a generated transaction sequence: a deposit, possibly disputed and then resolved
+/// or charged back, or a withdrawal.
+#[derive(Clone, Copy, Debug)]
+enum Step {
+    Deposit,
+    Withdrawal,
+    Dispute,
+    Resolve,
+    Chargeback,
+}
+
+/// Sequences of up to `max_len` deposits and withdrawals of clients from one to `clients`,
+/// with unique ids, where disputes only refer to deposits of the same client, and resolves
+/// and chargebacks only to disputed ones. Withdrawals and disputes may still fail for lack
+/// of funds.
+pub fn arb_transaction_sequence(
+    clients: ClientId,
+    max_len: usize,
+) -> impl Strategy<Value = Vec<Transaction>> {
+    let step = select(vec![
+        Step::Deposit,
+        Step::Withdrawal,
+        Step::Dispute,
+        Step::Resolve,
+        Step::Chargeback,
+    ]);
+    vec((1..=clients.max(1), step, arb_amount()), 0..=max_len).prop_map(|steps| {
+        let mut transactions = Vec::new();
+        for (i, (client, step, amount)) in steps.into_iter().enumerate() {
+            let tx = i as TransactionId + 1;
+            let type_ = match step {
+                Step::Withdrawal => TransactionType::Withdrawal,
+                _ => TransactionType::Deposit,
+            };
+            transactions.push(Transaction::new(type_, client, tx, Some(amount), false));
+            let outcome = match step {
+                Step::Deposit | Step::Withdrawal => continue,
+                Step::Dispute => None,
+                Step::Resolve => Some(TransactionType::Resolve),
+                Step::Chargeback => Some(TransactionType::Chargeback),
+            };
+            let dispute = Transaction::new(TransactionType::Dispute, client, tx, None, false);
+            transactions.push(dispute);
+            if let Some(outcome) = outcome {
+                transactions.push(Transaction::new(outcome, client, tx, None, false));
+            }
+        }
+        transactions
+    })
+}
+
+/// States of an account reachable by applying a generated transaction sequence.
+pub fn arb_account() -> impl Strategy<Value = Account> {
+    arb_transaction_sequence(1, MAX_ACCOUNT_TRANSACTIONS).prop_map(|transactions| {
+        Accounts::from_transaction_iter(transactions.into_iter().map(Ok), Strictness::Lenient)
+            .expect("only business rules can fail")
+            .remove(&1)
+            .unwrap_or_else(|| Account::new(1))
+    })
+}
+
+impl Arbitrary for TransactionType {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            Just(Self::Deposit),
+            Just(Self::Withdrawal),
+            Just(Self::Dispute),
+            Just(Self::Resolve),
+            Just(Self::Chargeback),
+            Just(Self::Representment),
+            Just(Self::Unlock),
+            Just(Self::Refund),
+            Just(Self::Auth),
+            Just(Self::Capture),
+            Just(Self::Void),
+            Just(Self::Interest),
+            Just(Self::Hold),
+            Just(Self::Release),
+            Just(Self::Adjustment),
+            Just(Self::Reversal),
+            Just(Self::Convert),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Transaction {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        arb_transaction().boxed()
+    }
+}
+
+impl Arbitrary for Account {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        arb_account().boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{prop_assert, prop_assert_eq, proptest};
+
+    use super::arb_transaction_sequence;
+    use crate::{Accounts, Strictness, Transaction};
+
+    proptest! {
+        #[test]
+        fn balances_add_up(transactions in arb_transaction_sequence(4, 64)) {
+            let accounts =
+                Accounts::from_transaction_iter(transactions.into_iter().map(Ok), Strictness::Lenient)
+                    .unwrap();
+            for account in accounts.values() {
+                prop_assert!(*account.held() >= 0.0);
+                let sum = *account.available() + *account.held();
+                prop_assert!((sum - *account.total()).abs() < 1e-6);
+            }
+        }
+
+        #[cfg(feature = "csv")]
+        #[test]
+        fn parse_transactions_back(tx: Transaction) {
+            prop_assert_eq!(Transaction::from_line(&tx.to_line()).unwrap(), tx);
+        }
+    }
+}
@@ -1,14 +1,38 @@
-use std::{fmt::Display, fs::File, io::Read};
+#[cfg(feature = "csv")]
+use std::{
+    cell::Cell,
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    rc::Rc,
+};
+use std::{
+    fmt::Display,
+    str::{self, FromStr},
+};
 
-use csv::{DeserializeRecordsIter, Error as CsvError, Reader as CsvReader};
+#[cfg(feature = "csv")]
+use csv::{
+    ByteRecord, DeserializeRecordsIter, Error as CsvError, Reader as CsvReader, ReaderBuilder,
+    StringRecord, Trim,
+};
 use derive_getters::Getters;
-use derive_more::{Constructor, Deref, DerefMut};
-use serde::Deserialize;
+use derive_more::{Deref, DerefMut};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use thiserror::Error;
 
-use super::types::{ClientId, TransactionId};
+#[cfg(all(feature = "csv", feature = "http"))]
+use super::http::HttpReader;
+#[cfg(feature = "csv")]
+use super::report::{MalformedRow, ParseReport, Progress};
+#[cfg(all(feature = "csv", feature = "s3"))]
+use super::s3::S3Reader;
+use super::{
+    amount::Amount,
+    policy::PrecisionPolicy,
+    types::{ClientId, Timestamp, TransactionId},
+};
 
-#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TransactionType {
     Deposit,
@@ -16,34 +40,396 @@ pub enum TransactionType {
     Dispute,
     Resolve,
     Chargeback,
+    #[serde(alias = "chargeback_reversal")]
+    Representment,
+    Unlock,
+    Refund,
+    Auth,
+    Capture,
+    Void,
+    Interest,
+    Hold,
+    Release,
+    Adjustment,
+    Reversal,
+    Convert,
 }
 
-#[derive(Clone, Constructor, Debug, Deserialize, Getters, PartialEq)]
-pub struct Transaction {
+impl TransactionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Deposit => "deposit",
+            Self::Withdrawal => "withdrawal",
+            Self::Dispute => "dispute",
+            Self::Resolve => "resolve",
+            Self::Chargeback => "chargeback",
+            Self::Representment => "representment",
+            Self::Unlock => "unlock",
+            Self::Refund => "refund",
+            Self::Auth => "auth",
+            Self::Capture => "capture",
+            Self::Void => "void",
+            Self::Interest => "interest",
+            Self::Hold => "hold",
+            Self::Release => "release",
+            Self::Adjustment => "adjustment",
+            Self::Reversal => "reversal",
+            Self::Convert => "convert",
+        }
+    }
+
+    /// Whether transactions of the type are stored by accounts, to be referred to by later
+    /// ones, e.g. disputes.
+    pub(crate) fn is_stored(&self) -> bool {
+        matches!(
+            self,
+            Self::Deposit | Self::Withdrawal | Self::Auth | Self::Hold
+        )
+    }
+
+    /// Whether transactions of the type refer to a stored transaction by its id.
+    pub(crate) fn is_reference(&self) -> bool {
+        !self.is_stored()
+            && !matches!(
+                self,
+                Self::Unlock | Self::Interest | Self::Adjustment | Self::Convert
+            )
+    }
+}
+
+impl FromStr for TransactionType {
+    type Err = TransactionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "deposit" => Self::Deposit,
+            "withdrawal" => Self::Withdrawal,
+            "dispute" => Self::Dispute,
+            "resolve" => Self::Resolve,
+            "chargeback" => Self::Chargeback,
+            "representment" | "chargeback_reversal" => Self::Representment,
+            "unlock" => Self::Unlock,
+            "refund" => Self::Refund,
+            "auth" => Self::Auth,
+            "capture" => Self::Capture,
+            "void" => Self::Void,
+            "interest" => Self::Interest,
+            "hold" => Self::Hold,
+            "release" => Self::Release,
+            "adjustment" => Self::Adjustment,
+            "reversal" => Self::Reversal,
+            "convert" => Self::Convert,
+            _ => return Err(TransactionError::UnknownType(s.to_string())),
+        })
+    }
+}
+
+impl Display for TransactionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Getters, PartialEq, Serialize)]
+pub struct Transaction<A: Amount = f64> {
     #[serde(rename = "type")]
     type_: TransactionType,
     client: ClientId,
     tx: TransactionId,
     #[getter(skip)]
-    amount: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_amount")]
+    amount: Option<A>,
+    #[serde(default)]
+    timestamp: Option<Timestamp>,
+    /// Date from which the transaction takes effect, when scheduled for later.
+    #[serde(default, skip_serializing)]
+    effective: Option<Timestamp>,
+    /// Key identifying the transaction across re-deliveries, for deduplication.
+    #[serde(default, skip_serializing)]
+    idempotency_key: Option<String>,
+    /// Reason code of an adjustment, e.g. referencing a reconciliation case.
+    #[serde(default, skip_serializing)]
+    reason: Option<String>,
+    /// Symbol of the asset the transaction is denominated in, if not the default one.
+    #[serde(default, skip_serializing)]
+    asset: Option<String>,
+    /// Symbol of the asset a conversion credits, if not the default one.
+    #[serde(default, skip_serializing)]
+    to_asset: Option<String>,
+    /// Exchange rate a conversion was applied at, recorded once applied.
     #[serde(skip)]
+    rate: Option<f64>,
+    #[serde(skip_deserializing)]
     disputed: bool,
+    #[serde(skip_deserializing)]
+    charged_back: bool,
+    #[serde(skip_deserializing)]
+    refunded: bool,
+    #[serde(skip_deserializing)]
+    settled: bool,
+    #[serde(skip_deserializing)]
+    reversed: bool,
+    /// Timestamp of the dispute of the transaction, while disputed, if it had one.
+    #[serde(skip)]
+    disputed_at: Option<Timestamp>,
+    /// Whether the transaction is an interest posting of the engine rather than an input.
+    #[serde(skip)]
+    posted: bool,
 }
 
-impl Transaction {
-    pub fn amount(&self) -> f64 {
-        self.amount.map_or(0.0, |a| a)
+impl<A: Amount> Transaction<A> {
+    pub fn new(
+        type_: TransactionType,
+        client: ClientId,
+        tx: TransactionId,
+        amount: Option<A>,
+        disputed: bool,
+    ) -> Self {
+        Self {
+            type_,
+            client,
+            tx,
+            amount,
+            timestamp: None,
+            effective: None,
+            idempotency_key: None,
+            reason: None,
+            asset: None,
+            to_asset: None,
+            rate: None,
+            disputed,
+            charged_back: false,
+            refunded: false,
+            settled: false,
+            reversed: false,
+            disputed_at: None,
+            posted: false,
+        }
+    }
+
+    /// Interest posting of `amount` to `client` as of `at`, as accrued under an
+    /// `InterestPolicy`. Interest postings aren't stored, so they all have id 0.
+    pub(crate) fn interest(client: ClientId, at: Timestamp, amount: A) -> Self {
+        Self {
+            posted: true,
+            ..Self::new(TransactionType::Interest, client, 0, Some(amount), false)
+        }
+        .with_timestamp(at)
+    }
+
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn with_reason<R: Into<String>>(mut self, reason: R) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Denominate the transaction in `asset`, e.g. `EUR`, rather than the default asset.
+    pub fn with_asset<S: Into<String>>(mut self, asset: S) -> Self {
+        self.asset = Some(asset.into());
+        self
+    }
+
+    /// Convert into `asset` rather than the default asset, for conversions.
+    pub fn with_to_asset<S: Into<String>>(mut self, asset: S) -> Self {
+        self.to_asset = Some(asset.into());
+        self
+    }
+
+    /// Record the exchange rate a conversion is applied at.
+    pub(crate) fn with_rate(mut self, rate: f64) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    /// Remove the asset of the transaction, once routed to the balances of that asset.
+    pub(crate) fn take_asset(&mut self) -> Option<String> {
+        self.asset.take()
+    }
+
+    /// Schedule the transaction to take effect once processing reaches `effective`.
+    pub fn with_effective(mut self, effective: Timestamp) -> Self {
+        self.effective = Some(effective);
+        self
+    }
+
+    pub fn with_idempotency_key<K: Into<String>>(mut self, key: K) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
+    pub fn amount(&self) -> A {
+        self.amount.unwrap_or(A::ZERO)
+    }
+
+    /// Replace the amount of the transaction, e.g. once capped.
+    pub(crate) fn with_amount(mut self, amount: A) -> Self {
+        self.amount = Some(amount);
+        self
     }
 
     pub fn dispute(&mut self) {
+        self.dispute_at(None);
+    }
+
+    /// Mark the transaction as disputed by a dispute timestamped at `at`, if at all.
+    pub(crate) fn dispute_at(&mut self, at: Option<Timestamp>) {
         self.disputed = true;
+        self.disputed_at = at;
     }
 
     pub fn resolve(&mut self) {
         self.disputed = false;
+        self.disputed_at = None;
+    }
+
+    pub fn charge_back(&mut self) {
+        self.disputed = false;
+        self.disputed_at = None;
+        self.charged_back = true;
+    }
+
+    pub fn represent(&mut self) {
+        self.charged_back = false;
+    }
+
+    pub fn refund(&mut self) {
+        self.refunded = true;
+    }
+
+    pub fn reverse(&mut self) {
+        self.reversed = true;
+    }
+
+    pub fn settle(&mut self) {
+        self.settled = true;
+    }
+
+    /// Check that the amount of the transaction, if any, is finite and isn't negative, unless
+    /// an adjustment.
+    pub fn validate(&self) -> Result<(), TransactionError> {
+        // Adjustments debit accounts by negative amounts.
+        let signed = self.type_ == TransactionType::Adjustment;
+        match self.amount {
+            Some(amount) if (amount < A::ZERO && !signed) || !amount.is_finite() => Err(
+                TransactionError::InvalidAmount(self.client, self.tx, amount.to_f64()),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Adjust the amount of the transaction, if any, to four decimal places as per `policy`.
+    pub fn with_precision(mut self, policy: PrecisionPolicy) -> Result<Self, TransactionError> {
+        if let Some(amount) = self.amount {
+            let amount = amount.to_f64();
+            self.amount = Some(A::from_f64(policy.apply(amount).ok_or(
+                TransactionError::ExcessPrecision(self.client, self.tx, amount),
+            )?));
+        }
+        Ok(self)
+    }
+
+    /// Encode the transaction as a csv record with the columns of the csv input, the inverse
+    /// of `Transaction::from_line`.
+    pub fn to_line(&self) -> String {
+        let amount = self.amount.map(|a| a.to_string()).unwrap_or_default();
+        let timestamp = self.timestamp.map(|t| t.to_string()).unwrap_or_default();
+        let line = format!(
+            "{},{},{},{amount},{timestamp}",
+            self.type_, self.client, self.tx
+        );
+        // Trailing optional columns, up to the last one set.
+        let quote = |field: &str| {
+            if field.contains([',', '"', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        };
+        let optional = [
+            self.idempotency_key
+                .as_deref()
+                .map(quote)
+                .unwrap_or_default(),
+            self.effective.map(|e| e.to_string()).unwrap_or_default(),
+            self.reason.as_deref().map(quote).unwrap_or_default(),
+            self.asset.as_deref().map(quote).unwrap_or_default(),
+            self.to_asset.as_deref().map(quote).unwrap_or_default(),
+        ];
+        match optional.iter().rposition(|field| !field.is_empty()) {
+            Some(last) => format!("{line},{}", optional[..=last].join(",")),
+            None => line,
+        }
+    }
+}
+
+#[cfg(feature = "csv")]
+impl Transaction {
+    /// Parse a transaction from a csv record with the columns of the csv input, in the same
+    /// order, without going through serde nor allocating, except for idempotency keys.
+    pub fn from_byte_record(record: &ByteRecord) -> Result<Self, TransactionError> {
+        let field = |i| record.get(i).map_or(&b""[..], <[u8]>::trim_ascii);
+        let type_ = str::from_utf8(field(0))
+            .map_err(|_| invalid_field("type", field(0)))?
+            .parse()?;
+        let amount = parse_optional_field::<f64>("amount", field(3))?;
+        if amount.is_some_and(|amount| !amount.is_finite()) {
+            return Err(invalid_field("amount", field(3)));
+        }
+        let mut tx = Self::new(
+            type_,
+            parse_field("client", field(1))?,
+            parse_field("tx", field(2))?,
+            amount,
+            false,
+        );
+        tx.timestamp = parse_optional_field("timestamp", field(4))?;
+        tx.idempotency_key = parse_optional_field("idempotency_key", field(5))?;
+        tx.effective = parse_optional_field("effective", field(6))?;
+        tx.reason = parse_optional_field("reason", field(7))?;
+        tx.asset = parse_optional_field("asset", field(8))?;
+        tx.to_asset = parse_optional_field("to_asset", field(9))?;
+        Ok(tx)
     }
 }
-impl Display for Transaction {
+
+/// Deserialize an optional amount, rejecting NaN and infinite ones, which would poison balances.
+fn deserialize_amount<'de, A: Amount, D: Deserializer<'de>>(de: D) -> Result<Option<A>, D::Error> {
+    match Option::<A>::deserialize(de)? {
+        Some(amount) if !amount.is_finite() => {
+            Err(D::Error::custom(format!("non-finite amount: {amount}")))
+        }
+        amount => Ok(amount),
+    }
+}
+
+#[cfg(feature = "csv")]
+pub(crate) fn invalid_field(name: &'static str, value: &[u8]) -> TransactionError {
+    TransactionError::InvalidField(name, String::from_utf8_lossy(value).into_owned())
+}
+
+#[cfg(feature = "csv")]
+fn parse_field<T: FromStr>(name: &'static str, value: &[u8]) -> Result<T, TransactionError> {
+    str::from_utf8(value)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| invalid_field(name, value))
+}
+
+#[cfg(feature = "csv")]
+fn parse_optional_field<T: FromStr>(
+    name: &'static str,
+    value: &[u8],
+) -> Result<Option<T>, TransactionError> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    parse_field(name, value).map(Some)
+}
+impl<A: Amount> Display for Transaction<A> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some(amount) = self.amount {
             write!(
@@ -61,12 +447,94 @@ impl Display for Transaction {
     }
 }
 
+/// Complete state of a transaction, including its lifecycle flags, as persisted in checkpoints
+/// and serialized along with `Accounts`.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TransactionState {
+    #[serde(rename = "type")]
+    type_: TransactionType,
+    client: ClientId,
+    tx: TransactionId,
+    amount: Option<f64>,
+    timestamp: Option<Timestamp>,
+    #[serde(default)]
+    idempotency_key: Option<String>,
+    #[serde(default)]
+    asset: Option<String>,
+    #[serde(default)]
+    to_asset: Option<String>,
+    #[serde(default)]
+    rate: Option<f64>,
+    disputed: bool,
+    charged_back: bool,
+    refunded: bool,
+    settled: bool,
+    #[serde(default)]
+    reversed: bool,
+    #[serde(default)]
+    disputed_at: Option<Timestamp>,
+}
+
+impl From<&Transaction> for TransactionState {
+    fn from(tx: &Transaction) -> Self {
+        Self {
+            type_: tx.type_,
+            client: tx.client,
+            tx: tx.tx,
+            amount: tx.amount,
+            timestamp: tx.timestamp,
+            idempotency_key: tx.idempotency_key.clone(),
+            asset: tx.asset.clone(),
+            to_asset: tx.to_asset.clone(),
+            rate: tx.rate,
+            disputed: tx.disputed,
+            charged_back: tx.charged_back,
+            refunded: tx.refunded,
+            settled: tx.settled,
+            reversed: tx.reversed,
+            disputed_at: tx.disputed_at,
+        }
+    }
+}
+
+impl From<TransactionState> for Transaction {
+    fn from(state: TransactionState) -> Self {
+        Self {
+            type_: state.type_,
+            client: state.client,
+            tx: state.tx,
+            amount: state.amount,
+            timestamp: state.timestamp,
+            effective: None,
+            idempotency_key: state.idempotency_key,
+            reason: None,
+            asset: state.asset,
+            to_asset: state.to_asset,
+            rate: state.rate,
+            disputed: state.disputed,
+            charged_back: state.charged_back,
+            refunded: state.refunded,
+            settled: state.settled,
+            reversed: state.reversed,
+            disputed_at: state.disputed_at,
+            posted: false,
+        }
+    }
+}
+
 #[derive(Debug, Default, Deref, DerefMut, PartialEq)]
 pub struct Transactions(pub Vec<Transaction>);
 
+#[cfg(feature = "csv")]
 impl Transactions {
+    #[cfg_attr(feature = "tracing", tracing::instrument(err))]
     pub fn from_csv(path: &str) -> Result<Self, CsvError> {
-        CsvReader::from_path(path)?
+        Self::from_csv_reader(open(path)?)
+    }
+
+    /// Parse the transactions of csv input read from `rdr`, e.g. a string in memory.
+    pub fn from_csv_reader<R: Read>(rdr: R) -> Result<Self, CsvError> {
+        CsvReader::from_reader(rdr)
             .deserialize()
             .collect::<Result<_, _>>()
             .map(Self)
@@ -74,65 +542,424 @@ impl Transactions {
 }
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum TransactionError {
+    #[cfg(feature = "csv")]
     #[error("csv error: {0}")]
     Csv(#[from] CsvError),
+    #[cfg(feature = "arrow")]
+    #[error("arrow error: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+    #[cfg(feature = "avro")]
+    #[error("avro error: {0}")]
+    Avro(#[from] apache_avro::Error),
+    #[cfg(feature = "json")]
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "polars")]
+    #[error("polars error: {0}")]
+    Polars(#[from] polars::prelude::PolarsError),
+    #[error("unknown transaction type: {0}")]
+    UnknownType(String),
+    #[error("invalid {0}: {1}")]
+    InvalidField(&'static str, String),
+    #[error("too many malformed rows, more than {0}")]
+    TooManyMalformed(usize),
+    #[error("invalid amount, account: {0}, transaction: {1}, amount: {2}")]
+    InvalidAmount(ClientId, TransactionId, f64),
+    #[error(
+        "amount with more than four decimal places, account: {0}, transaction: {1}, amount: {2}"
+    )]
+    ExcessPrecision(ClientId, TransactionId, f64),
 }
 
-struct TransactionCsvFileReader(File);
+impl TransactionError {
+    /// Name of the kind of error, e.g. for labelling metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "csv")]
+            Self::Csv(..) => "csv",
+            #[cfg(feature = "arrow")]
+            Self::Arrow(..) => "arrow",
+            #[cfg(feature = "avro")]
+            Self::Avro(..) => "avro",
+            #[cfg(feature = "json")]
+            Self::Json(..) => "json",
+            #[cfg(feature = "polars")]
+            Self::Polars(..) => "polars",
+            Self::UnknownType(..) => "unknown_type",
+            Self::InvalidField(..) => "invalid_field",
+            Self::TooManyMalformed(..) => "too_many_malformed",
+            Self::InvalidAmount(..) => "invalid_amount",
+            Self::ExcessPrecision(..) => "excess_precision",
+        }
+    }
 
-impl Read for TransactionCsvFileReader {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let len = self.0.read(buf)?;
-        let mut i = -1isize;
-        let mut j = 0;
-        while j < len {
-            if buf[j] != b' ' {
-                i += 1;
-                buf[i as usize] = buf[j];
-            }
-            j += 1;
+    /// Stable numeric code of the error, e.g. for mapping to api responses. Codes are never
+    /// reused.
+    pub fn code(&self) -> u16 {
+        match self {
+            #[cfg(feature = "csv")]
+            Self::Csv(..) => 300,
+            #[cfg(feature = "arrow")]
+            Self::Arrow(..) => 301,
+            #[cfg(feature = "avro")]
+            Self::Avro(..) => 302,
+            #[cfg(feature = "json")]
+            Self::Json(..) => 303,
+            #[cfg(feature = "polars")]
+            Self::Polars(..) => 304,
+            Self::UnknownType(..) => 310,
+            Self::InvalidField(..) => 311,
+            Self::TooManyMalformed(..) => 312,
+            Self::InvalidAmount(..) => 320,
+            Self::ExcessPrecision(..) => 321,
+        }
+    }
+}
+
+#[cfg(feature = "csv")]
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+#[cfg(feature = "csv")]
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+#[cfg(feature = "csv")]
+/// Open the file at `path`, or stream it when a http(s) or s3 url, transparently decompressing it
+/// when gzip or zstd compressed, as detected by its magic bytes.
+fn open(path: &str) -> io::Result<Box<dyn Read>> {
+    open_counted(path, Rc::default())
+}
+
+#[cfg(feature = "csv")]
+/// Open the file at `path` as `open` does, adding the bytes read from it, before any
+/// decompression, to `count`.
+fn open_counted(path: &str, count: Rc<Cell<u64>>) -> io::Result<Box<dyn Read>> {
+    let rdr: Box<dyn Read> = if path.starts_with("http://") || path.starts_with("https://") {
+        #[cfg(feature = "http")]
+        {
+            Box::new(HttpReader::new(path)?)
+        }
+        #[cfg(not(feature = "http"))]
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "reading from urls requires the http feature",
+        ));
+    } else if path.starts_with("s3://") {
+        #[cfg(feature = "s3")]
+        {
+            Box::new(S3Reader::from_url(path)?)
+        }
+        #[cfg(not(feature = "s3"))]
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "reading from s3 requires the s3 feature",
+        ));
+    } else {
+        Box::new(File::open(path)?)
+    };
+    let mut rdr = BufReader::new(CountingReader { inner: rdr, count });
+    let magic = rdr.fill_buf()?;
+    if magic.starts_with(GZIP_MAGIC) {
+        #[cfg(feature = "gzip")]
+        return Ok(Box::new(flate2::bufread::MultiGzDecoder::new(rdr)));
+        #[cfg(not(feature = "gzip"))]
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "reading gzip compressed files requires the gzip feature",
+        ));
+    }
+    if magic.starts_with(ZSTD_MAGIC) {
+        #[cfg(feature = "zstd")]
+        return Ok(Box::new(zstd::Decoder::with_buffer(rdr)?));
+        #[cfg(not(feature = "zstd"))]
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "reading zstd compressed files requires the zstd feature",
+        ));
+    }
+    Ok(Box::new(rdr))
+}
+
+#[cfg(feature = "csv")]
+/// Reader adding the bytes read from `inner` to `count`.
+struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+#[cfg(feature = "csv")]
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.count.set(self.count.get() + len as u64);
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "csv")]
+/// Progress through the input of `TransactionsCsv`, reported to the hook, if any, on every
+/// record read.
+#[derive(Default)]
+pub(crate) struct InputProgress {
+    bytes: Rc<Cell<u64>>,
+    records: u64,
+    hook: Option<Box<dyn FnMut(Progress)>>,
+}
+
+#[cfg(feature = "csv")]
+impl InputProgress {
+    fn record(&mut self) {
+        self.records += 1;
+        if let Some(hook) = &mut self.hook {
+            hook(Progress {
+                bytes: self.bytes.get(),
+                records: self.records,
+            });
+        }
+    }
+}
+
+#[cfg(feature = "csv")]
+/// Column of csv input, by header name or position starting from zero.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Column {
+    Name(String),
+    Position(usize),
+}
+
+#[cfg(feature = "csv")]
+/// How csv input is laid out.
+#[derive(Clone, Debug)]
+pub struct CsvOptions {
+    /// Field delimiter, e.g. `b'\t'` for tab-separated files.
+    pub delimiter: u8,
+    /// Columns to read transaction fields from, when not named after them, e.g.
+    /// `(Column::Name("txid".to_string()), "tx".to_string())`.
+    pub columns: Vec<(Column, String)>,
+}
+
+#[cfg(feature = "csv")]
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            columns: vec![],
         }
-        i += 1;
-        Ok(i as usize)
     }
 }
-pub struct TransactionsCsv(CsvReader<TransactionCsvFileReader>);
 
+#[cfg(feature = "csv")]
+impl CsvOptions {
+    /// Transaction field read from the column at `position` named `name`, if mapped.
+    fn field(&self, position: usize, name: &str) -> Option<&str> {
+        self.columns
+            .iter()
+            .find(|(column, _)| match column {
+                Column::Name(column) => column == name,
+                Column::Position(column) => *column == position,
+            })
+            .map(|(_, field)| field.as_str())
+    }
+}
+
+#[cfg(feature = "csv")]
+pub struct TransactionsCsv(pub(crate) CsvReader<Box<dyn Read>>, InputProgress);
+
+#[cfg(feature = "csv")]
 impl TransactionsCsv {
     pub fn from_csv(path: &str) -> Result<Self, CsvError> {
-        let csv_file = File::open(path)?;
+        Self::from_csv_with_options(path, &CsvOptions::default())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(err))]
+    pub fn from_csv_with_options(path: &str, options: &CsvOptions) -> Result<Self, CsvError> {
+        let progress = InputProgress::default();
+        let csv_file = open_counted(path, progress.bytes.clone())?;
+
+        let mut rdr = ReaderBuilder::new()
+            .delimiter(options.delimiter)
+            .trim(Trim::All)
+            .from_reader(csv_file);
+        if !options.columns.is_empty() {
+            let headers = rdr
+                .headers()?
+                .iter()
+                .enumerate()
+                .map(|(position, name)| options.field(position, name).unwrap_or(name))
+                .collect();
+            rdr.set_headers(headers);
+        }
+        Ok(Self(rdr, progress))
+    }
 
-        Ok(Self(CsvReader::from_reader(TransactionCsvFileReader(
-            csv_file,
-        ))))
+    /// Report progress through the input to `hook` on every record read, e.g. to render a
+    /// progress bar for large files. Bytes are counted as read from the input, before any
+    /// decompression, and ahead of the records parsed by up to a buffer.
+    pub fn with_progress<F: FnMut(Progress) + 'static>(mut self, hook: F) -> Self {
+        self.1.hook = Some(Box::new(hook));
+        self
     }
 }
 
+#[cfg(feature = "csv")]
 impl TransactionsCsv {
     pub fn iter(&mut self) -> TransactionCsvIterator<'_> {
+        self.iter_as()
+    }
+
+    /// Iterate over transactions with amounts parsed as `A`, e.g. `MinorUnits`.
+    pub fn iter_as<A: Amount>(&mut self) -> TransactionCsvIterator<'_, A> {
         TransactionCsvIterator {
             csv_deserializer: self.0.deserialize(),
+            progress: &mut self.1,
+        }
+    }
+}
+
+#[cfg(feature = "csv")]
+impl TransactionsCsv {
+    /// Iterate over transactions parsed by `Transaction::from_byte_record` into a reused
+    /// record, which is faster than `iter` on large files, but requires the columns to be in
+    /// the order of the csv input.
+    pub fn iter_fast(&mut self) -> TransactionCsvFastIterator<'_> {
+        TransactionCsvFastIterator {
+            rdr: &mut self.0,
+            progress: &mut self.1,
+            record: ByteRecord::new(),
         }
     }
 }
 
-pub struct TransactionCsvIterator<'a> {
-    csv_deserializer: DeserializeRecordsIter<'a, TransactionCsvFileReader, Transaction>,
+#[cfg(feature = "csv")]
+impl TransactionsCsv {
+    /// Iterate over transactions like `iter`, but skipping malformed rows rather than failing,
+    /// recording them to `report`. Iteration fails once more than `max_malformed` rows are
+    /// skipped.
+    pub fn iter_skip_malformed<'a>(
+        &'a mut self,
+        report: &'a mut ParseReport,
+        max_malformed: usize,
+    ) -> TransactionCsvSkippingIterator<'a> {
+        TransactionCsvSkippingIterator {
+            rdr: &mut self.0,
+            progress: &mut self.1,
+            headers: None,
+            record: StringRecord::new(),
+            report,
+            max_malformed,
+        }
+    }
 }
 
-impl Iterator for TransactionCsvIterator<'_> {
+#[cfg(feature = "csv")]
+pub struct TransactionCsvIterator<'a, A: Amount = f64> {
+    csv_deserializer: DeserializeRecordsIter<'a, Box<dyn Read>, Transaction<A>>,
+    progress: &'a mut InputProgress,
+}
+
+#[cfg(feature = "csv")]
+impl<A: Amount> Iterator for TransactionCsvIterator<'_, A> {
+    type Item = Result<Transaction<A>, TransactionError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let tx = self.csv_deserializer.next()?;
+        self.progress.record();
+        #[cfg(feature = "tracing")]
+        match &tx {
+            Ok(tx) => tracing::trace!(%tx, "transaction parsed"),
+            Err(e) => tracing::warn!(error = %e, "transaction parsing failed"),
+        }
+        Some(tx.map_err(|e| e.into()))
+    }
+}
+
+#[cfg(feature = "csv")]
+pub struct TransactionCsvFastIterator<'a> {
+    rdr: &'a mut CsvReader<Box<dyn Read>>,
+    progress: &'a mut InputProgress,
+    record: ByteRecord,
+}
+
+#[cfg(feature = "csv")]
+impl Iterator for TransactionCsvFastIterator<'_> {
     type Item = Result<Transaction, TransactionError>;
     fn next(&mut self) -> Option<Self::Item> {
-        self.csv_deserializer
-            .next()
-            .map(|tx| tx.map_err(|e| e.into()))
+        let tx = match self.rdr.read_byte_record(&mut self.record) {
+            Ok(true) => Transaction::from_byte_record(&self.record),
+            Ok(false) => return None,
+            Err(e) => Err(e.into()),
+        };
+        self.progress.record();
+        #[cfg(feature = "tracing")]
+        match &tx {
+            Ok(tx) => tracing::trace!(%tx, "transaction parsed"),
+            Err(e) => tracing::warn!(error = %e, "transaction parsing failed"),
+        }
+        Some(tx)
     }
 }
 
-#[cfg(test)]
+#[cfg(feature = "csv")]
+pub struct TransactionCsvSkippingIterator<'a> {
+    rdr: &'a mut CsvReader<Box<dyn Read>>,
+    progress: &'a mut InputProgress,
+    headers: Option<StringRecord>,
+    record: StringRecord,
+    report: &'a mut ParseReport,
+    max_malformed: usize,
+}
+
+#[cfg(feature = "csv")]
+impl Iterator for TransactionCsvSkippingIterator<'_> {
+    type Item = Result<Transaction, TransactionError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let headers = match &self.headers {
+            Some(headers) => headers,
+            None => match self.rdr.headers() {
+                Ok(headers) => self.headers.insert(headers.clone()),
+                Err(e) => return Some(Err(e.into())),
+            },
+        };
+        loop {
+            let read = self.rdr.read_record(&mut self.record);
+            if matches!(read, Ok(true)) {
+                self.progress.record();
+            }
+            let error = match read {
+                Ok(false) => return None,
+                Ok(true) => match self.record.deserialize(Some(headers)) {
+                    Ok(tx) => return Some(Ok(tx)),
+                    Err(e) => e,
+                },
+                Err(e) if e.is_io_error() => return Some(Err(e.into())),
+                Err(e) => e,
+            };
+            #[cfg(feature = "tracing")]
+            tracing::warn!(error = %error, "malformed row skipped");
+            self.report.malformed.push(MalformedRow {
+                line: error
+                    .position()
+                    .or(self.record.position())
+                    .map_or(0, |pos| pos.line()),
+                content: self.record.iter().collect::<Vec<_>>().join(","),
+                error: error.to_string(),
+            });
+            if self.report.malformed.len() > self.max_malformed {
+                return Some(Err(TransactionError::TooManyMalformed(self.max_malformed)));
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "csv"))]
 mod tests {
-    use super::{Transaction, TransactionType, Transactions, TransactionsCsv};
+    use std::{cell::RefCell, rc::Rc};
+
+    use csv::ByteRecord;
+
+    use super::{
+        Column, CsvOptions, ParseReport, Progress, Transaction, TransactionError, TransactionType,
+        Transactions, TransactionsCsv,
+    };
 
     #[test]
     fn deserialize_transactions() {
@@ -146,40 +973,115 @@ mod tests {
                     client: 1,
                     tx: 1,
                     amount: Some(2.0),
-                    disputed: false
+                    timestamp: None,
+                    effective: None,
+                    idempotency_key: None,
+                    reason: None,
+                    asset: None,
+                    to_asset: None,
+                    rate: None,
+                    disputed: false,
+                    charged_back: false,
+                    refunded: false,
+                    settled: false,
+                    reversed: false,
+                    disputed_at: None,
+                    posted: false,
                 },
                 Transaction {
                     type_: TransactionType::Withdrawal,
                     client: 1,
                     tx: 2,
                     amount: Some(1.5),
-                    disputed: false
+                    timestamp: None,
+                    effective: None,
+                    idempotency_key: None,
+                    reason: None,
+                    asset: None,
+                    to_asset: None,
+                    rate: None,
+                    disputed: false,
+                    charged_back: false,
+                    refunded: false,
+                    settled: false,
+                    reversed: false,
+                    disputed_at: None,
+                    posted: false,
                 },
                 Transaction {
                     type_: TransactionType::Dispute,
                     client: 1,
                     tx: 2,
                     amount: None,
-                    disputed: false
+                    timestamp: None,
+                    effective: None,
+                    idempotency_key: None,
+                    reason: None,
+                    asset: None,
+                    to_asset: None,
+                    rate: None,
+                    disputed: false,
+                    charged_back: false,
+                    refunded: false,
+                    settled: false,
+                    reversed: false,
+                    disputed_at: None,
+                    posted: false,
                 },
                 Transaction {
                     type_: TransactionType::Resolve,
                     client: 1,
                     tx: 2,
                     amount: None,
-                    disputed: false
+                    timestamp: None,
+                    effective: None,
+                    idempotency_key: None,
+                    reason: None,
+                    asset: None,
+                    to_asset: None,
+                    rate: None,
+                    disputed: false,
+                    charged_back: false,
+                    refunded: false,
+                    settled: false,
+                    reversed: false,
+                    disputed_at: None,
+                    posted: false,
                 },
                 Transaction {
                     type_: TransactionType::Chargeback,
                     client: 1,
                     tx: 2,
                     amount: None,
-                    disputed: false
+                    timestamp: None,
+                    effective: None,
+                    idempotency_key: None,
+                    reason: None,
+                    asset: None,
+                    to_asset: None,
+                    rate: None,
+                    disputed: false,
+                    charged_back: false,
+                    refunded: false,
+                    settled: false,
+                    reversed: false,
+                    disputed_at: None,
+                    posted: false,
                 },
             ])
         );
     }
 
+    #[test]
+    fn deserialize_transactions_from_reader() {
+        let sample_path = "src/test_utils/test_txs.csv";
+        let csv = std::fs::read(sample_path).unwrap();
+        assert_eq!(
+            Transactions::from_csv_reader(csv.as_slice()).unwrap(),
+            Transactions::from_csv(sample_path).unwrap()
+        );
+    }
+
     #[test]
     fn deserialize_transactions_iterator() {
         let sample_path = "src/test_utils/test_txs.csv";
@@ -191,6 +1093,54 @@ mod tests {
         assert_eq!(transactions, Transactions::from_csv(sample_path).unwrap().0);
     }
 
+    #[test]
+    fn report_progress() {
+        let sample_path = "src/test_utils/test_txs.csv";
+        let reported = Rc::new(RefCell::new(Vec::new()));
+        let mut transactions_csv = TransactionsCsv::from_csv(sample_path)
+            .unwrap()
+            .with_progress({
+                let reported = reported.clone();
+                move |progress| reported.borrow_mut().push(progress)
+            });
+        assert_eq!(transactions_csv.iter().count(), 5);
+        let reported = reported.borrow();
+        assert_eq!(reported.len(), 5);
+        assert_eq!(
+            reported.last(),
+            Some(&Progress {
+                bytes: 99,
+                records: 5
+            })
+        );
+    }
+
+    #[test]
+    fn parse_transactions_fast() {
+        for sample_path in [
+            "src/test_utils/test_txs.csv",
+            "src/test_utils/test_txs_whitespaces.csv",
+            "src/test_utils/test_txs_timestamps.csv",
+        ] {
+            let mut transactions_csv = TransactionsCsv::from_csv(sample_path).unwrap();
+            let transactions = transactions_csv
+                .iter_fast()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            let mut transactions_csv = TransactionsCsv::from_csv(sample_path).unwrap();
+            let expected = transactions_csv
+                .iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(transactions, expected);
+        }
+        let record = ByteRecord::from(vec!["deposit", "1", "x", "1.0"]);
+        assert!(matches!(
+            Transaction::from_byte_record(&record),
+            Err(TransactionError::InvalidField("tx", _))
+        ));
+    }
+
     #[test]
     fn deserialize_transactions_whitespaces() {
         let sample_path_ws = "src/test_utils/test_txs_whitespaces.csv";
@@ -207,4 +1157,188 @@ mod tests {
             .unwrap();
         assert_eq!(transactions_ws, transactions);
     }
+
+    #[test]
+    fn trim_fields_keeping_inner_spaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("adjustments.csv");
+        std::fs::write(
+            &path,
+            "type, client, tx, amount, timestamp, idempotency_key, effective, reason\n\
+             adjustment, 1, 1, 2.0, , recon 17, , manual correction \n",
+        )
+        .unwrap();
+        let mut transactions_csv = TransactionsCsv::from_csv(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            transactions_csv
+                .iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![
+                Transaction::new(TransactionType::Adjustment, 1, 1, Some(2.0), false)
+                    .with_idempotency_key("recon 17")
+                    .with_reason("manual correction")
+            ]
+        );
+    }
+
+    #[test]
+    fn deserialize_transactions_tsv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transactions.tsv");
+        std::fs::write(&path, "type\tclient\ttx\tamount\ndeposit\t1\t1\t2.0\n").unwrap();
+        let options = CsvOptions {
+            delimiter: b'\t',
+            ..CsvOptions::default()
+        };
+        let mut transactions_csv =
+            TransactionsCsv::from_csv_with_options(path.to_str().unwrap(), &options).unwrap();
+        assert_eq!(
+            transactions_csv
+                .iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![Transaction::new(
+                TransactionType::Deposit,
+                1,
+                1,
+                Some(2.0),
+                false
+            )]
+        );
+    }
+
+    #[test]
+    fn deserialize_transactions_mapped_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transactions-mapped.csv");
+        std::fs::write(&path, "kind,client,txid,value\ndeposit,1,1,2.0\n").unwrap();
+        let options = CsvOptions {
+            columns: vec![
+                (Column::Name("kind".to_string()), "type".to_string()),
+                (Column::Name("txid".to_string()), "tx".to_string()),
+                (Column::Position(3), "amount".to_string()),
+            ],
+            ..CsvOptions::default()
+        };
+        let mut transactions_csv =
+            TransactionsCsv::from_csv_with_options(path.to_str().unwrap(), &options).unwrap();
+        assert_eq!(
+            transactions_csv
+                .iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![Transaction::new(
+                TransactionType::Deposit,
+                1,
+                1,
+                Some(2.0),
+                false
+            )]
+        );
+    }
+
+    #[test]
+    fn skip_malformed_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transactions-malformed.csv");
+        std::fs::write(
+            &path,
+            "type,client,tx,amount\ndeposit,1,1,2.0\ndeposit,x,2,1.0\ndeposit,1\ndeposit,1,4,1.0\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+        let mut report = ParseReport::default();
+        let mut transactions_csv = TransactionsCsv::from_csv(path).unwrap();
+        let transactions = transactions_csv
+            .iter_skip_malformed(&mut report, 2)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(report.malformed.len(), 2);
+        assert_eq!(report.malformed[0].line, 3);
+        assert_eq!(report.malformed[0].content, "deposit,x,2,1.0");
+        assert_eq!(report.malformed[1].line, 4);
+
+        let mut transactions_csv = TransactionsCsv::from_csv(path).unwrap();
+        assert!(matches!(
+            transactions_csv
+                .iter_skip_malformed(&mut ParseReport::default(), 1)
+                .collect::<Result<Vec<_>, _>>(),
+            Err(TransactionError::TooManyMalformed(1))
+        ));
+    }
+
+    #[test]
+    fn reject_non_finite_amounts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transactions-non-finite.csv");
+        std::fs::write(
+            &path,
+            "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,1,2,nan\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+        let mut transactions_csv = TransactionsCsv::from_csv(path).unwrap();
+        let err = transactions_csv
+            .iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_err();
+        assert!(err.to_string().contains("line: 3"));
+        assert!(err.to_string().contains("non-finite amount: NaN"));
+
+        let mut transactions_csv = TransactionsCsv::from_csv(path).unwrap();
+        assert!(matches!(
+            transactions_csv.iter_fast().nth(1),
+            Some(Err(TransactionError::InvalidField("amount", _)))
+        ));
+    }
+
+    #[test]
+    fn deserialize_transactions_timestamps() {
+        let sample_path = "src/test_utils/test_txs_timestamps.csv";
+        let transactions = Transactions::from_csv(sample_path).unwrap();
+        assert_eq!(
+            transactions,
+            Transactions(vec![
+                Transaction::new(TransactionType::Deposit, 1, 1, Some(2.0), false)
+                    .with_timestamp(1700000000),
+                Transaction::new(TransactionType::Interest, 1, 2, Some(0.1), false)
+                    .with_timestamp(1700086400),
+                Transaction::new(TransactionType::Withdrawal, 1, 3, Some(1.5), false),
+            ])
+        );
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn deserialize_transactions_gzip() {
+        let sample_path_gz = "src/test_utils/test_txs.csv.gz";
+        let sample_path = "src/test_utils/test_txs.csv";
+        let mut transactions_csv_gz = TransactionsCsv::from_csv(sample_path_gz).unwrap();
+        let transactions_gz = transactions_csv_gz
+            .iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            transactions_gz,
+            Transactions::from_csv(sample_path).unwrap().0
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn deserialize_transactions_zstd() {
+        let sample_path_zst = "src/test_utils/test_txs.csv.zst";
+        let sample_path = "src/test_utils/test_txs.csv";
+        let mut transactions_csv_zst = TransactionsCsv::from_csv(sample_path_zst).unwrap();
+        let transactions_zst = transactions_csv_zst
+            .iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            transactions_zst,
+            Transactions::from_csv(sample_path).unwrap().0
+        );
+    }
 }
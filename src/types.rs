@@ -1,2 +1,4 @@
 pub type ClientId = u16;
 pub type TransactionId = u32;
+/// Seconds since the unix epoch.
+pub type Timestamp = u64;
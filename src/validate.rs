@@ -0,0 +1,73 @@
+use super::{
+    account::{Account, Accounts},
+    amount::Amount,
+    policy::BalancePolicy,
+    types::ClientId,
+};
+
+/// Tolerance on float rounding errors when comparing balances.
+const TOLERANCE: f64 = 1e-9;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// Total funds don't amount to available plus held funds.
+    TotalMismatch {
+        available: f64,
+        held: f64,
+        total: f64,
+    },
+    NegativeHeld(f64),
+    /// Negative total funds, unless allowed by the balance policy.
+    NegativeTotal(f64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvariantViolation {
+    pub client: ClientId,
+    pub violation: Violation,
+}
+
+impl<A: Amount> Account<A> {
+    /// Check balance invariants, assuming the default balance policy.
+    pub fn validate(&self) -> Vec<InvariantViolation> {
+        self.validate_with_policy(&BalancePolicy::default())
+    }
+
+    pub fn validate_with_policy(&self, policy: &BalancePolicy) -> Vec<InvariantViolation> {
+        let (available, held, total) = (
+            self.available().to_f64(),
+            self.held().to_f64(),
+            self.total().to_f64(),
+        );
+        let mut violations = vec![];
+        if (available + held - total).abs() > TOLERANCE {
+            violations.push(Violation::TotalMismatch {
+                available,
+                held,
+                total,
+            });
+        }
+        if held < -TOLERANCE {
+            violations.push(Violation::NegativeHeld(held));
+        }
+        if total < -TOLERANCE && *policy == BalancePolicy::NoNegative {
+            violations.push(Violation::NegativeTotal(total));
+        }
+        violations
+            .into_iter()
+            .map(|violation| InvariantViolation {
+                client: *self.client(),
+                violation,
+            })
+            .collect()
+    }
+}
+
+impl<A: Amount> Accounts<A> {
+    /// Check balance invariants of all accounts, ordered by client id.
+    pub fn validate(&self) -> Vec<InvariantViolation> {
+        self.iter_sorted()
+            .flat_map(|acc| acc.validate_with_policy(&self.config().balance))
+            .collect()
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    account::{AccountError, Accounts},
+    policy::Strictness,
+    transaction::Transaction,
+};
+
+/// Append-only log of the transactions received by a long-running engine, each written and
+/// synced to disk before being applied, so that the accounts can be recovered after a crash by
+/// replaying the log. Transactions are logged one per line, as by `Transaction::to_line`.
+pub struct WriteAheadLog {
+    path: PathBuf,
+    file: File,
+}
+
+impl WriteAheadLog {
+    /// Open the log at `path`, creating it if needed. A last line left partially written by a
+    /// crash is discarded, as its transaction was never applied.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, AccountError> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().append(true).create(true).open(&path)?;
+        let contents = fs::read(&path)?;
+        if !contents.is_empty() && !contents.ends_with(b"\n") {
+            let complete = contents
+                .iter()
+                .rposition(|&b| b == b'\n')
+                .map_or(0, |i| i + 1);
+            file.set_len(complete as u64)?;
+        }
+        Ok(Self { path, file })
+    }
+
+    /// Apply the logged transactions to `accounts` as in non-strict processing, returning how
+    /// many were logged.
+    pub fn replay(&self, accounts: &mut Accounts) -> Result<usize, AccountError> {
+        Self::replay_file(&self.path, accounts)
+    }
+
+    /// Apply the transactions logged to the file at `path` to `accounts` as in non-strict
+    /// processing, returning how many were logged, without opening the log for writing, e.g.
+    /// to audit it. A last line left partially written is disregarded.
+    pub fn replay_file<P: AsRef<Path>>(
+        path: P,
+        accounts: &mut Accounts,
+    ) -> Result<usize, AccountError> {
+        let contents = fs::read_to_string(path)?;
+        let complete = contents.rfind('\n').map_or("", |i| &contents[..=i]);
+        let lines = complete.lines().filter(|line| !line.is_empty());
+        accounts.apply_transaction_iter(
+            lines.clone().map(Transaction::from_line),
+            Strictness::Lenient,
+        )?;
+        Ok(lines.count())
+    }
+
+    /// Log `tx`, returning once it is synced to disk.
+    pub fn append(&mut self, tx: &Transaction) -> Result<(), AccountError> {
+        writeln!(self.file, "{}", tx.to_line())?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    /// Empty the log, e.g. once the accounts it was replayed into are checkpointed.
+    pub fn truncate(&mut self) -> Result<(), AccountError> {
+        self.file.set_len(0)?;
+        self.file.sync_all()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write};
+
+    use super::WriteAheadLog;
+    use crate::{Accounts, Transaction, TransactionType};
+
+    #[test]
+    fn recover_from_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wal.log");
+        let mut wal = WriteAheadLog::open(&path).unwrap();
+        for tx in [
+            Transaction::new(TransactionType::Deposit, 1, 1, Some(2.5), false),
+            Transaction::new(TransactionType::Dispute, 1, 1, None, false),
+        ] {
+            wal.append(&tx).unwrap();
+        }
+        // Simulate a crash while logging a transaction.
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"deposit,1,2")
+            .unwrap();
+        drop(wal);
+
+        let wal = WriteAheadLog::open(&path).unwrap();
+        let mut accounts = Accounts::default();
+        assert_eq!(wal.replay(&mut accounts).unwrap(), 2);
+        let account = accounts.get(1).unwrap();
+        assert_eq!((*account.available(), *account.held()), (0.0, 2.5));
+    }
+
+    #[test]
+    fn replay_without_opening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wal-replay.log");
+        fs::write(&path, "deposit,1,1,2.5\ndeposit,1,2").unwrap();
+        let mut accounts = Accounts::default();
+        assert_eq!(WriteAheadLog::replay_file(&path, &mut accounts).unwrap(), 1);
+        assert_eq!(*accounts.get(1).unwrap().total(), 2.5);
+        // The partially written line is left as is.
+        assert!(fs::read_to_string(&path).unwrap().ends_with("deposit,1,2"));
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
+
+use notify::{
+    event::{AccessKind, AccessMode, ModifyKind, RenameMode},
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+
+use super::{
+    account::{AccountError, Accounts},
+    policy::Strictness,
+    transaction::TransactionsCsv,
+};
+
+const EXTENSIONS: [&str; 3] = [".csv", ".csv.gz", ".csv.zst"];
+
+/// Long-running watcher of a directory, applying the transactions of the csv files landing in
+/// it to `Accounts`. A file is processed once written and closed, or moved into the
+/// directory, so producers writing files elsewhere and renaming them into the directory avoid
+/// it being processed while partially written. Files already in the directory are ignored.
+pub struct DirectoryWatch {
+    // Events stop being delivered once the watcher is dropped.
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    processed: HashSet<PathBuf>,
+}
+
+impl DirectoryWatch {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self, AccountError> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(dir.as_ref(), RecursiveMode::NonRecursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            processed: HashSet::new(),
+        })
+    }
+
+    /// Watch the directory indefinitely, applying the transactions of every new file to
+    /// `accounts` as in non-strict processing, and calling `on_file` with the file and the
+    /// updated accounts once done with it, e.g. to output them.
+    pub fn run<F>(&mut self, accounts: &mut Accounts, mut on_file: F) -> Result<(), AccountError>
+    where
+        F: FnMut(&Path, &Accounts) -> Result<(), AccountError>,
+    {
+        for event in &self.events {
+            let event = event?;
+            let path = match event.kind {
+                EventKind::Access(AccessKind::Close(AccessMode::Write))
+                | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => event.paths.first(),
+                // Renames within the watched directory carry both the source and destination.
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => event.paths.get(1),
+                _ => None,
+            };
+            let Some(path) = path else {
+                continue;
+            };
+            let is_csv = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| EXTENSIONS.iter().any(|ext| name.ends_with(ext)));
+            // Files rewritten in place are only processed the first time they land.
+            if !is_csv || !self.processed.insert(path.clone()) {
+                continue;
+            }
+
+            let mut transactions = TransactionsCsv::from_csv(&path.to_string_lossy())?;
+            accounts.apply_transaction_iter(transactions.iter(), Strictness::Lenient)?;
+            on_file(path, accounts)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::DirectoryWatch;
+    use crate::{AccountError, Accounts};
+
+    #[test]
+    fn process_landing_files() {
+        let (dir, staging) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let dir = dir.path();
+        fs::write(
+            dir.join("existing.csv"),
+            "type,client,tx,amount\ndeposit,1,1,5.0\n",
+        )
+        .unwrap();
+        let mut watch = DirectoryWatch::new(dir).unwrap();
+
+        fs::write(
+            dir.join("ignored.txt"),
+            "type,client,tx,amount\ndeposit,1,2,5.0\n",
+        )
+        .unwrap();
+        let staged = staging.path().join("staged.csv");
+        fs::copy("src/test_utils/test_txs.csv", &staged).unwrap();
+        fs::rename(&staged, dir.join("landed.csv")).unwrap();
+
+        let mut accounts = Accounts::default();
+        let mut processed = vec![];
+        // Stop watching once the landed file is processed.
+        let res = watch.run(&mut accounts, |path: &Path, _| {
+            processed.push(path.file_name().unwrap().to_owned());
+            Err(std::io::Error::other("done").into())
+        });
+        assert!(matches!(res, Err(AccountError::Io(_))));
+        assert_eq!(processed, vec!["landed.csv"]);
+        assert_eq!(*accounts.get(1).unwrap().total(), 0.5);
+    }
+}
@@ -0,0 +1,115 @@
+use std::{thread::sleep, time::Duration};
+
+use serde::Serialize;
+
+use super::{
+    account::Account,
+    observer::Observer,
+    transaction::{Transaction, TransactionType},
+    types::{ClientId, TransactionId},
+};
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase", tag = "event")]
+pub enum WebhookEvent {
+    Chargeback {
+        client: ClientId,
+        tx: TransactionId,
+        available: f64,
+        held: f64,
+        total: f64,
+        locked: bool,
+    },
+    Frozen {
+        client: ClientId,
+        available: f64,
+        held: f64,
+        total: f64,
+    },
+}
+
+/// Observer posting a JSON `WebhookEvent` to each configured url whenever a chargeback is
+/// applied or an account is frozen. Failed deliveries are retried with exponential backoff,
+/// blocking processing meanwhile, and eventually dropped.
+#[derive(Clone, Debug)]
+pub struct WebhookNotifier {
+    urls: Vec<String>,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl WebhookNotifier {
+    pub fn new<U: Into<String>, I: IntoIterator<Item = U>>(urls: I) -> Self {
+        Self {
+            urls: urls.into_iter().map(Into::into).collect(),
+            max_retries: 3,
+            backoff: Duration::from_millis(100),
+        }
+    }
+
+    /// Retry failed deliveries up to `max_retries` times, waiting `backoff` before the first
+    /// retry and doubling the wait on every subsequent one.
+    pub fn with_retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn notify(&self, event: &WebhookEvent) {
+        for url in &self.urls {
+            let mut backoff = self.backoff;
+            for attempt in 0..=self.max_retries {
+                if ureq::post(url).send_json(event).is_ok() {
+                    break;
+                }
+                if attempt < self.max_retries {
+                    sleep(backoff);
+                    backoff *= 2;
+                }
+            }
+        }
+    }
+}
+
+impl Observer for WebhookNotifier {
+    fn on_applied(&self, account: &Account, tx: &Transaction) {
+        if *tx.type_() == TransactionType::Chargeback {
+            self.notify(&WebhookEvent::Chargeback {
+                client: *account.client(),
+                tx: *tx.tx(),
+                available: *account.available(),
+                held: *account.held(),
+                total: *account.total(),
+                locked: *account.locked(),
+            });
+        }
+    }
+
+    fn on_frozen(&self, account: &Account) {
+        self.notify(&WebhookEvent::Frozen {
+            client: *account.client(),
+            available: *account.available(),
+            held: *account.held(),
+            total: *account.total(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WebhookEvent;
+
+    #[test]
+    fn serialize_webhook_events() {
+        let event = WebhookEvent::Frozen {
+            client: 1,
+            available: 1.0,
+            held: 0.0,
+            total: 1.0,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"frozen","client":1,"available":1.0,"held":0.0,"total":1.0}"#
+        );
+    }
+}
@@ -0,0 +1,27 @@
+use tx_engine::{AccountRecord, Accounts, Strictness, Transaction, Transactions};
+use wasm_bindgen::prelude::{wasm_bindgen, JsError, JsValue};
+
+/// Accounts resulting from `transactions`, skipping the ones breaking business rules,
+/// ordered by client id.
+fn summaries(transactions: Vec<Transaction>) -> Result<Vec<AccountRecord>, JsError> {
+    let accounts =
+        Accounts::from_transaction_iter(transactions.into_iter().map(Ok), Strictness::Lenient)?;
+    Ok(accounts.iter_sorted().map(AccountRecord::from).collect())
+}
+
+/// Process the transactions of `csv`, with the columns of the csv input, returning an array
+/// of accounts, objects with the columns of the csv output.
+#[wasm_bindgen(js_name = processCsv)]
+pub fn process_csv(csv: &str) -> Result<JsValue, JsError> {
+    let transactions = Transactions::from_csv_reader(csv.as_bytes())?;
+    Ok(serde_wasm_bindgen::to_value(&summaries(transactions.0)?)?)
+}
+
+/// Process `transactions`, an array of objects with the fields of the csv input, e.g.
+/// `{type: "deposit", client: 1, tx: 1, amount: 1.5}`, returning an array of accounts,
+/// objects with the columns of the csv output.
+#[wasm_bindgen(js_name = processTransactions)]
+pub fn process_transactions(transactions: JsValue) -> Result<JsValue, JsError> {
+    let transactions = serde_wasm_bindgen::from_value(transactions)?;
+    Ok(serde_wasm_bindgen::to_value(&summaries(transactions)?)?)
+}